@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use failure::Error;
+use serde::{Deserialize, Serialize};
+
+use super::{DkcError, ParamValue};
+
+/// Current version of the scene collection file format.
+pub const COLLECTION_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CanvasDesc {
+    pub width: u16,
+    pub height: u16,
+    #[serde(default = "default_framerate")]
+    pub framerate: (i32, i32),
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default = "default_audio_rate")]
+    pub audio_rate: i32,
+    #[serde(default = "default_audio_channels")]
+    pub audio_channels: i32,
+    #[serde(default = "default_audio_tracks")]
+    pub audio_tracks: u32,
+}
+
+fn default_framerate() -> (i32, i32) { (30, 1) }
+fn default_audio_rate() -> i32 { 48000 }
+fn default_audio_channels() -> i32 { 2 }
+fn default_audio_tracks() -> u32 { 1 }
+
+impl Default for CanvasDesc {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            framerate: default_framerate(),
+            format: None,
+            audio_rate: default_audio_rate(),
+            audio_channels: default_audio_channels(),
+            audio_tracks: default_audio_tracks(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceDesc {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub source_type: String,
+    #[serde(default)]
+    pub properties: BTreeMap<String, ParamValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterDesc {
+    pub name: String,
+    pub kind: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, ParamValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneItemDesc {
+    pub source: String,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    #[serde(default)]
+    pub params: BTreeMap<String, ParamValue>,
+    /// Video filter chain, in processing order.
+    #[serde(default)]
+    pub filters: Vec<FilterDesc>,
+    /// Audio filter chain, in processing order.
+    #[serde(default)]
+    pub audio_filters: Vec<FilterDesc>,
+}
+
+fn default_visible() -> bool { true }
+
+/// What the canvas shows where no scene item covers it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Background {
+    /// Checker pattern, for previews.
+    Checker,
+    Black,
+    White,
+    Transparent,
+    /// Solid color, as 0xAARRGGBB.
+    Color(u32),
+    /// Image stretched over the canvas.
+    Image(PathBuf),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Black
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneDesc {
+    pub name: String,
+    #[serde(default)]
+    pub items: Vec<SceneItemDesc>,
+    #[serde(default)]
+    pub background: Background,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SinkDesc {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub sink_type: String,
+    #[serde(default)]
+    pub properties: BTreeMap<String, ParamValue>,
+    #[serde(default)]
+    pub output: BTreeMap<String, ParamValue>,
+}
+
+/// Everything needed to rebuild an `App`: canvas, sources, scenes and sinks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneCollection {
+    pub version: u32,
+    pub canvas: CanvasDesc,
+    /// Scene on program, if any.
+    #[serde(default)]
+    pub current_scene: Option<String>,
+    #[serde(default)]
+    pub sources: Vec<SourceDesc>,
+    #[serde(default)]
+    pub scenes: Vec<SceneDesc>,
+    #[serde(default)]
+    pub sinks: Vec<SinkDesc>,
+}
+
+impl SceneCollection {
+    pub fn from_json(json: &str) -> Result<SceneCollection, Error> {
+
+        let collection: SceneCollection = serde_json::from_str(json)?;
+
+        if collection.version > COLLECTION_VERSION {
+            return Err(DkcError {src_name: "collection".to_string(),
+                                 src_type: "SceneCollection".to_string(),
+                                 error_msg: format!("Unsupported collection version {} (expected at most {}).",
+                                                    collection.version, COLLECTION_VERSION)}.into());
+        }
+
+        Ok(collection)
+
+    }
+
+    pub fn to_json(self: &Self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: &Path) -> Result<SceneCollection, Error> {
+        SceneCollection::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(self: &Self, path: &Path) -> Result<(), Error> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
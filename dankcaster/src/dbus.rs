@@ -0,0 +1,333 @@
+//! D-Bus control interface, `org.dankcaster.DankCaster1` at `/org/dankcaster/DankCaster` on
+//! the `org.dankcaster.DankCaster` bus name.
+//!
+//! Scene items are identified by their id in the scene (see `WrappedSource::id`), and sources
+//! by name. Method calls are served from the bus connection executor and handled on the thread
+//! owning the `App`, from its thread default glib main context: it has to be running or
+//! iterated, as `App::turn_on` does. Errors are reported as `org.freedesktop.DBus.Error.Failed`.
+
+use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use failure::Error;
+use futures_channel::oneshot;
+use gst::glib;
+use zbus::{dbus_interface, fdo, SignalContext};
+
+use super::{App, AppEvent, DkcError};
+
+pub const BUS_NAME: &str = "org.dankcaster.DankCaster";
+pub const OBJECT_PATH: &str = "/org/dankcaster/DankCaster";
+pub const INTERFACE: &str = "org.dankcaster.DankCaster1";
+
+type AppCall = Box<dyn FnOnce(&App) + Send>;
+
+/// Running D-Bus control interface, removed from the bus when dropped.
+pub struct DbusServer {
+    app: App,
+    context: glib::MainContext,
+    source_id: Option<glib::SourceId>,
+    subscription: usize,
+    _connection: zbus::blocking::Connection,
+}
+
+struct Control {
+    calls: Mutex<glib::Sender<AppCall>>,
+}
+
+fn failed(error: Error) -> fdo::Error {
+    fdo::Error::Failed(error.to_string())
+}
+
+impl Control {
+    /* Run `func` on the app thread, the bus executor serving other calls until its result */
+    async fn call<R, F>(self: &Self, func: F) -> fdo::Result<R>
+    where R: Send + 'static, F: FnOnce(&App) -> fdo::Result<R> + Send + 'static {
+
+        let (reply, result) = oneshot::channel();
+        self.calls.lock().unwrap()
+            .send(Box::new(move |app: &App| { let _ = reply.send(func(app)); }))
+            .map_err(|_| fdo::Error::Failed("DankCaster is not running.".to_string()))?;
+
+        result.await.map_err(|_| fdo::Error::Failed("DankCaster is not running.".to_string()))?
+
+    }
+}
+
+#[dbus_interface(name = "org.dankcaster.DankCaster1")]
+impl Control {
+    async fn list_scenes(&self) -> fdo::Result<Vec<String>> {
+        self.call(|app| Ok(app.scenes().iter().map(|scene| scene.name().to_string()).collect())).await
+    }
+
+    /// Name of the program scene, empty until one is set.
+    async fn current_scene(&self) -> fdo::Result<String> {
+        self.call(|app| Ok(app.current_scene().map(|scene| scene.name().to_string()).unwrap_or_default())).await
+    }
+
+    async fn switch_scene(&self, name: String) -> fdo::Result<()> {
+        self.call(move |app| app.set_current_scene(&name).map_err(failed)).await
+    }
+
+    async fn list_sources(&self) -> fdo::Result<Vec<String>> {
+        self.call(|app| Ok(app.sources().iter().map(|source| source.name()).collect())).await
+    }
+
+    /// Id, source name and visibility of the items of a scene.
+    async fn list_scene_items(&self, scene: String) -> fdo::Result<Vec<(u32, String, bool)>> {
+        self.call(move |app| {
+            let scene = app.scene(&scene).ok_or_else(|| fdo::Error::Failed(format!("Unknown scene '{}'.", scene)))?;
+            Ok(scene.sources().iter()
+               .map(|wrapped| (wrapped.id() as u32, wrapped.source().name(), wrapped.visible())).collect())
+        }).await
+    }
+
+    async fn set_item_visible(&self, scene: String, item: u32, visible: bool) -> fdo::Result<()> {
+        self.call(move |app| {
+            let wrapped = app.scene(&scene)
+                .and_then(|scene| scene.source_by_id(item as usize))
+                .ok_or_else(|| fdo::Error::Failed(format!("Unknown item {} of scene '{}'.", item, scene)))?;
+            wrapped.set_visible(visible);
+            Ok(())
+        }).await
+    }
+
+    async fn source_muted(&self, source: String) -> fdo::Result<bool> {
+        self.call(move |app| app.source(&source).map(|source| source.muted())
+                  .ok_or_else(|| fdo::Error::Failed(format!("Unknown source '{}'.", source)))).await
+    }
+
+    async fn set_source_muted(&self, source: String, muted: bool) -> fdo::Result<()> {
+        self.call(move |app| app.source(&source).map(|source| source.set_muted(muted))
+                  .ok_or_else(|| fdo::Error::Failed(format!("Unknown source '{}'.", source)))).await
+    }
+
+    async fn start_recording(&self, path: String) -> fdo::Result<()> {
+        self.call(move |app| app.start_recording(Path::new(&path)).map_err(failed)).await
+    }
+
+    async fn stop_recording(&self) -> fdo::Result<()> {
+        self.call(|app| app.stop_recording().map_err(failed)).await
+    }
+
+    /// Path being recorded to, empty when not recording.
+    async fn recording(&self) -> fdo::Result<String> {
+        self.call(|app| Ok(app.recording().map(|path| path.to_string_lossy().into_owned()).unwrap_or_default())).await
+    }
+
+    async fn start_streaming(&self, url: String) -> fdo::Result<()> {
+        self.call(move |app| app.start_streaming(&url).map_err(failed)).await
+    }
+
+    async fn stop_streaming(&self) -> fdo::Result<()> {
+        self.call(|app| app.stop_streaming().map_err(failed)).await
+    }
+
+    /// URL being streamed to, empty when not streaming.
+    async fn streaming(&self) -> fdo::Result<String> {
+        self.call(|app| Ok(app.streaming().unwrap_or_default())).await
+    }
+
+    #[dbus_interface(signal)]
+    async fn scene_changed(ctxt: &SignalContext<'_>, scene: &str) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    async fn item_visibility_changed(ctxt: &SignalContext<'_>, scene: &str, item: u32, source: &str,
+                                     visible: bool) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    async fn source_mute_changed(ctxt: &SignalContext<'_>, source: &str, muted: bool) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    async fn streaming_changed(ctxt: &SignalContext<'_>, active: bool) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    async fn recording_changed(ctxt: &SignalContext<'_>, active: bool) -> zbus::Result<()>;
+}
+
+/* Signals are emitted from their own thread, not to wait on the bus from the app one */
+fn emit_signals(connection: zbus::blocking::Connection, events: mpsc::Receiver<AppEvent>) {
+
+    let ctxt = match SignalContext::new(connection.inner(), OBJECT_PATH) {
+        Ok(ctxt) => ctxt,
+        Err(_) => return,
+    };
+
+    for event in events.iter() {
+        let _ = zbus::block_on(async {
+            match &event {
+                AppEvent::CurrentSceneChanged(scene) => Control::scene_changed(&ctxt, scene).await,
+                AppEvent::SourceVisibilityChanged { scene, item, source, visible } =>
+                    Control::item_visibility_changed(&ctxt, scene, *item as u32, source, *visible).await,
+                AppEvent::SourceMuteChanged { source, muted } => Control::source_mute_changed(&ctxt, source, *muted).await,
+                AppEvent::StreamingChanged(active) => Control::streaming_changed(&ctxt, *active).await,
+                AppEvent::RecordingChanged(active) => Control::recording_changed(&ctxt, *active).await,
+            }
+        });
+    }
+
+}
+
+impl DbusServer {
+    /// Export the control interface of `app` on the bus at `address`, or on the session bus.
+    pub fn start(app: &App, address: Option<&str>) -> Result<DbusServer, Error> {
+
+        let error = |err: zbus::Error| -> Error {
+            DkcError {src_name: BUS_NAME.to_string(),
+                      src_type: "DbusServer".to_string(),
+                      error_msg: format!("Could not export the control interface: {}.", err)}.into()
+        };
+
+        let context = glib::MainContext::ref_thread_default();
+        let (calls, receiver) = glib::MainContext::channel::<AppCall>(glib::PRIORITY_DEFAULT);
+        let call_app = App { app: app.app.clone() };
+        let source_id = receiver.attach(Some(&context), move |call| {
+            call(&call_app);
+            glib::Continue(true)
+        });
+
+        let builder = match address {
+            Some(address) => zbus::blocking::ConnectionBuilder::address(address).map_err(error)?,
+            None => zbus::blocking::ConnectionBuilder::session().map_err(error)?,
+        };
+        let connection = builder.name(BUS_NAME).map_err(error)?
+            .serve_at(OBJECT_PATH, Control { calls: Mutex::new(calls) }).map_err(error)?
+            .build().map_err(error);
+        let connection = match connection {
+            Ok(connection) => connection,
+            Err(err) => {
+                if let Some(source) = context.find_source_by_id(&source_id) {
+                    source.destroy();
+                }
+                return Err(err);
+            },
+        };
+
+        let (events, event_receiver) = mpsc::channel();
+        let signal_connection = connection.clone();
+        thread::spawn(move || emit_signals(signal_connection, event_receiver));
+        let subscription = app.subscribe_events(move |event| {
+            let _ = events.send(event.clone());
+        });
+
+        Ok(DbusServer { app: App { app: app.app.clone() }, context, source_id: Some(source_id), subscription,
+                        _connection: connection })
+
+    }
+}
+
+impl Drop for DbusServer {
+    fn drop(self: &mut Self) {
+
+        /* Unsubscribing ends the signal thread */
+        self.app.unsubscribe_events(self.subscription);
+        if let Some(source) = self.source_id.take().and_then(|source_id| self.context.find_source_by_id(&source_id)) {
+            source.destroy();
+        }
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::time::Duration;
+
+    fn set_up() {
+
+        use std::sync::Once;
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+        });
+
+    }
+
+    /* Bus daemon of its own, with its address, if dbus-daemon is installed */
+    fn private_bus() -> Option<(Child, String)> {
+
+        let mut daemon = Command::new("dbus-daemon")
+            .args(&["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.as_mut().unwrap()).read_line(&mut address)
+            .expect("Could not read the bus address");
+
+        Some((daemon, address.trim().to_string()))
+
+    }
+
+    #[test]
+    fn test_dbus_control() {
+
+        set_up();
+
+        // Without a bus daemon to run, there is nothing to test against.
+        let (mut daemon, address) = match private_bus() {
+            Some(bus) => bus,
+            None => return,
+        };
+
+        let mut app = super::super::make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let source = app.make_source("dummy", Some("mysource")).expect("Could not make source.");
+        app.make_scene(Some("myscene")).expect("Could not make scene.")
+            .add_source(source).expect("Could not add source to scene.");
+        app.make_scene(Some("other")).expect("Could not make scene.");
+
+        let context = glib::MainContext::new();
+        let _ = context.with_thread_default(|| {
+            let _server = DbusServer::start(&app, Some(&address)).expect("Could not start D-Bus server.");
+
+            let client_address = address.clone();
+            let (done_sender, done) = mpsc::channel();
+            let client = thread::spawn(move || {
+                let connection = zbus::blocking::ConnectionBuilder::address(client_address.as_str()).unwrap()
+                    .build().unwrap();
+                let proxy = zbus::blocking::Proxy::new(&connection, BUS_NAME, OBJECT_PATH, INTERFACE).unwrap();
+                let mut scene_changes = proxy.receive_signal("SceneChanged").unwrap();
+
+                let scenes: Vec<String> = proxy.call("ListScenes", &()).unwrap();
+                assert_eq!(scenes, vec!["myscene", "other"]);
+                assert_eq!(proxy.call::<_, _, String>("CurrentScene", &()).unwrap(), "");
+
+                // Switching scenes is signaled, unknown ones are refused.
+                proxy.call::<_, _, ()>("SwitchScene", &("other",)).unwrap();
+                assert_eq!(scene_changes.next().unwrap().body::<String>().unwrap(), "other");
+                assert!(proxy.call::<_, _, ()>("SwitchScene", &("nope",)).is_err());
+
+                proxy.call::<_, _, ()>("SetItemVisible", &("myscene", 1u32, false)).unwrap();
+                assert!(proxy.call::<_, _, ()>("SetItemVisible", &("myscene", 0u32, false)).is_err());
+                let items: Vec<(u32, String, bool)> = proxy.call("ListSceneItems", &("myscene",)).unwrap();
+                assert_eq!(items, vec![(1, "mysource".to_string(), false)]);
+
+                proxy.call::<_, _, ()>("SetSourceMuted", &("mysource", true)).unwrap();
+                assert!(proxy.call::<_, _, bool>("SourceMuted", &("mysource",)).unwrap());
+                assert!(proxy.call::<_, _, ()>("StopRecording", &()).is_err());
+
+                let _ = done_sender.send(());
+            });
+
+            /* Clients failing early drop their sender too */
+            while let Err(mpsc::RecvTimeoutError::Timeout) = done.recv_timeout(Duration::from_millis(5)) {
+                while context.iteration(false) {}
+            }
+            client.join().expect("D-Bus client failed.");
+        });
+
+        let _ = daemon.kill();
+        let _ = daemon.wait();
+
+        assert_eq!(app.current_scene().unwrap().name(), "other");
+        assert!(app.source("mysource").unwrap().muted());
+
+    }
+
+}
@@ -1,8 +1,14 @@
 extern crate gstreamer as gst;
+extern crate gstreamer_app as gst_app;
+extern crate gstreamer_video as gst_video;
 
 use gst::prelude::*;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::sync::{mpsc, Mutex};
 
 extern crate failure;
 use failure::Error;
@@ -10,6 +16,16 @@ use failure::Error;
 #[macro_use]
 extern crate failure_derive;
 
+mod param;
+mod collection;
+pub mod obs;
+pub mod remote;
+pub mod dbus;
+
+pub use param::ParamValue;
+pub use collection::{SceneCollection, CanvasDesc, SourceDesc, SceneDesc, SceneItemDesc, FilterDesc, SinkDesc,
+                     Background, COLLECTION_VERSION};
+
 #[derive(Debug, Fail)]
 #[fail(
     display = "DankCaster error from {} ({}): {}",
@@ -24,22 +40,52 @@ pub struct DkcError {
 pub struct Source {
     app: Rc<AppImpl>,
     element: gst::Element,
+    source_type: String,
+    properties: RefCell<BTreeMap<String, ParamValue>>,
+    video_input: RefCell<Option<String>>,
+    audio_input: RefCell<Option<String>>,
+    /* Input parameters as the scene first set them, see `App::set_current_scene` */
+    defaults: RefCell<BTreeMap<String, ParamValue>>,
+    muted: Cell<bool>,
     id: usize,
 }
 
+/* Input parameters of scene items hiding or muting their source, see `refresh_source` */
+const VISIBILITY_PARAMS: &[&str] = &["alpha", "mute"];
+
 pub struct WrappedSource {
-    source: Rc<Source>
+    source: Rc<Source>,
+    scene: String,
+    visible: Cell<bool>,
+    params: RefCell<BTreeMap<String, ParamValue>>,
+    filters: RefCell<Vec<FilterDesc>>,
+    audio_filters: RefCell<Vec<FilterDesc>>,
+    id: usize,
+}
+
+#[derive(Clone, Copy)]
+enum FilterChain {
+    Video,
+    Audio,
 }
 
 pub struct Scene {
     app: Rc<AppImpl>,
+    name: String,
     wrapped_sources: RefCell<Vec<Rc<WrappedSource>>>,
+    background: RefCell<Background>,
+    next_item_id: Cell<usize>,
     id: usize,
 }
 
 pub struct Sink {
     app: Rc<AppImpl>,
     element: gst::Element,
+    sink_type: String,
+    properties: RefCell<BTreeMap<String, ParamValue>>,
+    video_output: RefCell<Option<String>>,
+    audio_output: RefCell<Option<String>>,
+    output_params: RefCell<BTreeMap<String, ParamValue>>,
     id: usize,
 }
 
@@ -48,13 +94,99 @@ pub struct App {
 }
 
 struct AppImpl {
-    width: u16,
-    height: u16,
+    width: Cell<u16>,
+    height: Cell<u16>,
+    framerate: Cell<(i32, i32)>,
+    format: RefCell<Option<String>>,
+    audio_rate: Cell<i32>,
+    audio_channels: Cell<i32>,
+    audio_tracks: Cell<u32>,
     gst_bin: gst::Pipeline,
     gst_scene: gst::Element,
     sources: RefCell<Vec<Rc<Source>>>,
     scenes: RefCell<Vec<Rc<Scene>>>,
     sinks: RefCell<Vec<Rc<Sink>>>,
+    level_subscribers: RefCell<Vec<(usize, Rc<dyn Fn(&AudioLevels)>)>>,
+    next_subscription: Cell<usize>,
+    monitor: RefCell<Option<(gst::Pad, gst::Element)>>,
+    current_scene: RefCell<Option<String>>,
+    event_subscribers: RefCell<Vec<(usize, Rc<dyn Fn(&AppEvent)>)>>,
+    recording: RefCell<Option<Rc<Sink>>>,
+    streaming: RefCell<Option<Rc<Sink>>>,
+}
+
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+/// Easing curve of an animated scene input parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    fn as_str(self: &Self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "ease-in",
+            Easing::EaseOut => "ease-out",
+            Easing::EaseInOut => "ease-in-out",
+        }
+    }
+}
+
+/// Audio levels of a source or of the master mix, in dBFS per channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioLevels {
+    /// Measured source, `None` for the master mix.
+    pub source: Option<String>,
+    pub peak: Vec<f64>,
+    pub rms: Vec<f64>,
+    pub decay: Vec<f64>,
+}
+
+/// Automatic lowering of a wrapped source while another one is loud (see
+/// `WrappedSource::set_ducking`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ducking {
+    /// Level of the other source over which the wrapped source is lowered, in dBFS.
+    pub threshold: f64,
+    /// How much the level over the threshold is reduced, 4.0 bringing 20 dB over it to 5 dB.
+    pub ratio: f64,
+    pub attack: Duration,
+    pub release: Duration,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Self {
+            threshold: -30.0,
+            ratio: 4.0,
+            attack: Duration::from_millis(50),
+            release: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Change of the app state, see `App::subscribe_events`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppEvent {
+    CurrentSceneChanged(String),
+    /// `item` is the id of the wrapped source in its scene, see `WrappedSource::id`.
+    SourceVisibilityChanged { scene: String, item: usize, source: String, visible: bool },
+    SourceMuteChanged { source: String, muted: bool },
+    StreamingChanged(bool),
+    RecordingChanged(bool),
+}
+
+/// Single video frame grabbed from the scene or from a source, stored as RGBA.
+pub struct Snapshot {
+    sample: gst::Sample,
 }
 
 pub fn init() -> Result<(), Error> {
@@ -76,17 +208,57 @@ pub fn terminate() -> Result<(), Error> {
     unimplemented!();
 }
 
+/* Run `func` from an IDLE probe of a scene output pad (or of the tee pad behind it), once no
+ * data goes through it, and wait up to a second for it to run */
+fn when_idle<F>(pad: &gst::Pad, func: F) -> bool
+where F: FnOnce() + Send + 'static {
+
+    let probe_pad = pad.downcast_ref::<gst::GhostPad>().and_then(|ghost_pad| ghost_pad.target())
+        .unwrap_or_else(|| pad.clone());
+
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let pending = Mutex::new(Some((func, sender)));
+    probe_pad.add_probe(gst::PadProbeType::IDLE, move |_pad, _info| {
+        if let Some((func, sender)) = pending.lock().unwrap().take() {
+            func();
+            let _ = sender.send(());
+        }
+        gst::PadProbeReturn::Remove
+    });
+
+    receiver.recv_timeout(Duration::from_secs(1)).is_ok()
+
+}
+
 pub fn make_app(name: Option<&str>, width : u16, height: u16) -> Result<App, Error> {
 
-    let app = Rc::new( AppImpl { width, height,
+    let app = Rc::new( AppImpl { width: Cell::new(width), height: Cell::new(height),
+                                 framerate: Cell::new((30, 1)),
+                                 format: RefCell::new(None),
+                                 audio_rate: Cell::new(48000),
+                                 audio_channels: Cell::new(2),
+                                 audio_tracks: Cell::new(1),
                                  gst_bin: gst::Pipeline::new(name),
                                  gst_scene: gst::ElementFactory::make("dkcscene", name).unwrap(),
                                  sources: RefCell::new(vec![]),
                                  scenes: RefCell::new(vec![]),
-                                 sinks: RefCell::new(vec![])});
+                                 sinks: RefCell::new(vec![]),
+                                 level_subscribers: RefCell::new(vec![]),
+                                 next_subscription: Cell::new(0),
+                                 monitor: RefCell::new(None),
+                                 current_scene: RefCell::new(None),
+                                 event_subscribers: RefCell::new(vec![]),
+                                 recording: RefCell::new(None),
+                                 streaming: RefCell::new(None)});
 
     app.gst_bin.add(&app.gst_scene).expect("Could not add scene to bin.");
-    Ok(App { app })
+
+    let app = App { app };
+    app.set_resolution(width, height)?;
+    app.set_framerate(30, 1)?;
+    app.set_audio_format(48000, 2)?;
+
+    Ok(app)
 
 }
 
@@ -102,7 +274,13 @@ impl App {
                 let element_name = element.name();
 
                 let source = Rc::new(
-                    Source { app: self.app.clone(), element, id });
+                    Source { app: self.app.clone(), element,
+                             source_type: source_type.to_string(),
+                             properties: RefCell::new(BTreeMap::new()),
+                             video_input: RefCell::new(None),
+                             audio_input: RefCell::new(None),
+                             defaults: RefCell::new(BTreeMap::new()),
+                             muted: Cell::new(false), id });
 
                 self.app.sources.borrow_mut().push(source.clone());
 
@@ -117,7 +295,10 @@ impl App {
                         match self.app.gst_scene.request_pad_simple("video_sink_%u") {
                             Some(video_sink_pad) => {
                                 match video_src_pad.link(&video_sink_pad) {
-                                    Ok(_success) => Ok(()),
+                                    Ok(_success) => {
+                                        *source.video_input.borrow_mut() = Some(video_sink_pad.name().to_string());
+                                        Ok(())
+                                    },
                                     Err(_error) => Err(DkcError {src_name: element_name.to_string(),
                                                                  src_type: "DkcSource".to_string(),
                                                                  error_msg: "Could not link video pads.".to_string()}
@@ -137,7 +318,10 @@ impl App {
                         match self.app.gst_scene.request_pad_simple("audio_sink_%u") {
                             Some(audio_sink_pad) => {
                                 match audio_src_pad.link(&audio_sink_pad) {
-                                    Ok(_success) => Ok(()),
+                                    Ok(_success) => {
+                                        *source.audio_input.borrow_mut() = Some(audio_sink_pad.name().to_string());
+                                        Ok(())
+                                    },
                                     Err(_error) => Err(DkcError {src_name: element_name.to_string(),
                                                                  src_type: "DkcSource".to_string(),
                                                                  error_msg: "Could not link audio pads.".to_string()}
@@ -152,6 +336,8 @@ impl App {
                     None => Ok(()),
                 }; audio_ret?;
 
+                *source.defaults.borrow_mut() = source.input_params();
+
                 Ok(source)
             },
             Err(_) => Err(DkcError {src_name: format!("{:?}", name),
@@ -165,275 +351,2694 @@ impl App {
                      sink_type: &str,
                      name: Option<&str>) -> Result<Rc<Sink>, Error> {
 
-        match gst::ElementFactory::make(&format!("dkc{}sink", sink_type),
-                                        name) {
-            Ok(element) => {
-                let id = self.app.sinks.borrow_mut().len();
-                let element_name = element.name();
+        let sink = self.app.make_sink(sink_type, name)?;
+        self.app.sinks.borrow_mut().push(sink.clone());
 
-                let sink = Rc::new(
-                    Sink { app: self.app.clone(), element, id });
+        Ok(sink)
 
-                self.app.sinks.borrow_mut().push(sink.clone());
+    }
 
-                if self.app.gst_bin.add(&sink.element).is_err() {
-                    return Err(DkcError {src_name: element_name.to_string(),
-                                         src_type: "DkcSink".to_string(),
-                                         error_msg: "Could not add element to bin".to_string()}.into());
-                }
+    pub fn make_scene(self: &Self, name: Option<&str>) -> Result<Rc<Scene>, Error> {
 
-                let video_ret : std::result::Result<(), Error> = match sink.element.static_pad("video_sink") {
-                    Some(video_sink_pad) => {
-                        match self.app.gst_scene.request_pad_simple("video_src_%u") {
-                            Some(video_src_pad) => {
-                                match video_src_pad.link(&video_sink_pad) {
-                                    Ok(_success) => Ok(()),
-                                    Err(_error) => Err(DkcError {src_name: element_name.to_string(),
-                                                                 src_type: "DkcSink".to_string(),
-                                                                 error_msg: "Could not link video pads.".to_string()}
-                                                       .into()),
-                                }
-                            },
-                            None => Err(DkcError {src_name: element_name.to_string(),
-                                                  src_type: "DkcSink".to_string(),
-                                                  error_msg: "Could not find video sink pad.".to_string()}.into())
-                        }
-                    },
-                    None => Ok(()),
-                }; video_ret?;
+        let id = self.app.scenes.borrow_mut().len();
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => format!("scene{}", id),
+        };
 
-                let audio_ret : std::result::Result<(), Error> = match sink.element.static_pad("audio_sink") {
-                    Some(audio_sink_pad) => {
-                        match self.app.gst_scene.request_pad_simple("audio_src_%u") {
-                            Some(audio_src_pad) => {
-                                match audio_src_pad.link(&audio_sink_pad) {
-                                    Ok(_success) => Ok(()),
-                                    Err(_error) => Err(DkcError {src_name: element_name.to_string(),
-                                                                 src_type: "DkcSink".to_string(),
-                                                                 error_msg: "Could not link audio pads.".to_string()}
-                                                       .into()),
-                                }
-                            },
-                            None => Err(DkcError {src_name: element_name.to_string(),
-                                                  src_type: "DkcSink".to_string(),
-                                                  error_msg: "Could not find audio sink pad.".to_string()}.into())
-                        }
-                    },
-                    None => Ok(()),
-                }; audio_ret?;
+        let scene = Rc::new(
+            Scene { app: self.app.clone(), name, wrapped_sources: RefCell::new(vec![]),
+                    background: RefCell::new(Background::default()),
+                    next_item_id: Cell::new(1), id });
 
-                Ok(sink)
-            },
-            Err(_) => Err(DkcError {src_name: format!("{:?}", name),
-                                    src_type: "DkcSink".to_string(),
-                                    error_msg: "Could not make source element.".to_string()}.into()),
-        }
+        self.app.scenes.borrow_mut().push(scene.clone());
+
+        Ok(scene)
 
     }
 
-    pub fn make_scene(self: &Self, _name: Option<&str>) -> Result<Rc<Scene>, Error> {
+    pub fn width(self: &Self) -> u16 {
+        self.app.width.get()
+    }
 
-        let id = self.app.scenes.borrow_mut().len();
+    pub fn height(self: &Self) -> u16 {
+        self.app.height.get()
+    }
 
-        let scene = Rc::new(
-            Scene { app: self.app.clone(), wrapped_sources: RefCell::new(vec![]), id });
+    /// Change the canvas (output) resolution, this can be done while the app is turned on.
+    pub fn set_resolution(self: &Self, width: u16, height: u16) -> Result<(), Error> {
 
-        self.app.scenes.borrow_mut().push(scene.clone());
+        if width == 0 || height == 0 {
+            return Err(DkcError {src_name: self.app.gst_bin.name().to_string(),
+                                 src_type: "DkcApp".to_string(),
+                                 error_msg: format!("Invalid canvas resolution {}x{}.", width, height)}.into());
+        }
 
-        Ok(scene)
+        self.app.gst_scene.set_property("width", width as i32);
+        self.app.gst_scene.set_property("height", height as i32);
+        self.app.width.set(width);
+        self.app.height.set(height);
+
+        Ok(())
 
     }
 
-    pub fn turn_on(self: &mut Self) -> Result<(), Error> {
-        let pipeline = &self.app.gst_bin;
+    pub fn set_framerate(self: &Self, numerator: i32, denominator: i32) -> Result<(), Error> {
 
-        pipeline.set_state(gst::State::Playing).expect("Could not set pipeline state to 'Playing'.");
+        if numerator <= 0 || denominator <= 0 {
+            return Err(DkcError {src_name: self.app.gst_bin.name().to_string(),
+                                 src_type: "DkcApp".to_string(),
+                                 error_msg: format!("Invalid canvas framerate {}/{}.", numerator, denominator)}.into());
+        }
 
-        let bus = pipeline
-            .bus()
-            .expect("Pipeline without bus. Shouldn't happen!");
+        self.app.gst_scene.set_property("framerate", gst::Fraction::new(numerator, denominator));
+        self.app.framerate.set((numerator, denominator));
 
-        for msg in bus.iter_timed(gst::ClockTime::NONE) {
-            use gst::MessageView;
+        Ok(())
 
-            match msg.view() {
-                MessageView::Eos(..) => break,
-                MessageView::Error(_err) => {
-                    pipeline.set_state(gst::State::Null).expect("Could not set pipeline state to 'Null'.");
-                }
-                _ => (),
+    }
+
+    /// Pixel format of the canvas (e.g. "I420"), `None` lets the sinks negotiate it.
+    pub fn set_pixel_format(self: &Self, format: Option<&str>) -> Result<(), Error> {
+
+        if let Some(format) = format {
+            if gst_video::VideoFormat::from_string(format) == gst_video::VideoFormat::Unknown {
+                return Err(DkcError {src_name: self.app.gst_bin.name().to_string(),
+                                     src_type: "DkcApp".to_string(),
+                                     error_msg: format!("Unknown pixel format '{}'.", format)}.into());
             }
         }
 
-        pipeline.set_state(gst::State::Null).expect("Could not set pipeline state to 'Null'.");
+        self.app.gst_scene.set_property("format", format);
+        *self.app.format.borrow_mut() = format.map(String::from);
 
         Ok(())
 
     }
-}
 
-impl Scene {
-    pub fn add_source(self: &Self, source: Rc<Source>)
-                      -> Result<Rc<WrappedSource>, Error> {
+    pub fn set_audio_format(self: &Self, rate: i32, channels: i32) -> Result<(), Error> {
 
-        let _id = self.app.sinks.borrow_mut().len();
-        let wrapped_source = Rc::new(WrappedSource { source: source.clone() });
-        self.wrapped_sources.borrow_mut().push(wrapped_source.clone());
-        Ok(wrapped_source)
+        if rate <= 0 || channels <= 0 {
+            return Err(DkcError {src_name: self.app.gst_bin.name().to_string(),
+                                 src_type: "DkcApp".to_string(),
+                                 error_msg: format!("Invalid audio format {} Hz, {} channels.", rate, channels)}.into());
+        }
+
+        self.app.gst_scene.set_property("audio-rate", rate);
+        self.app.gst_scene.set_property("audio-channels", channels);
+        self.app.audio_rate.set(rate);
+        self.app.audio_channels.set(channels);
+
+        Ok(())
 
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Number of audio tracks sources can be recorded on separately, the first one being the
+    /// program mix (see `WrappedSource::set_tracks` and `Sink::set_audio_track`).
+    pub fn set_audio_tracks(self: &Self, tracks: u32) -> Result<(), Error> {
 
-    use super::*;
+        if tracks == 0 || tracks > 6 {
+            return Err(DkcError {src_name: self.app.gst_bin.name().to_string(),
+                                 src_type: "DkcApp".to_string(),
+                                 error_msg: format!("Invalid number of audio tracks {}.", tracks)}.into());
+        }
 
-    fn set_up() {
+        self.app.gst_scene.set_property("audio-tracks", tracks);
+        self.app.audio_tracks.set(tracks);
 
-        use std::sync::Once;
-        static INIT: Once = Once::new();
+        Ok(())
 
-        INIT.call_once(|| {
-            gst::init().unwrap();
-        });
+    }
 
+    pub fn audio_tracks(self: &Self) -> u32 {
+        self.app.audio_tracks.get()
     }
 
-    fn _tear_down() {
-        unimplemented!();
+    pub fn source(self: &Self, name: &str) -> Option<Rc<Source>> {
+        self.app.sources.borrow().iter().find(|source| source.name() == name).cloned()
     }
 
-    #[test]
-    fn test_make_app() {
+    pub fn scene(self: &Self, name: &str) -> Option<Rc<Scene>> {
+        self.app.scenes.borrow().iter().find(|scene| scene.name == name).cloned()
+    }
 
-        set_up();
+    pub fn sink(self: &Self, name: &str) -> Option<Rc<Sink>> {
+        self.app.sinks.borrow().iter().find(|sink| sink.name() == name).cloned()
+    }
 
-        assert!(
-            match make_app(Some("test"), 1280, 720) {
-                Ok(_el) => true,
-                Err(_err) => false
-            }
-        );
+    pub fn sources(self: &Self) -> Vec<Rc<Source>> {
+        self.app.sources.borrow().clone()
+    }
 
+    pub fn scenes(self: &Self) -> Vec<Rc<Scene>> {
+        self.app.scenes.borrow().clone()
     }
 
-    #[test]
-    fn test_make_source() {
+    /// Call `callback` whenever the program scene, the visibility of a scene item, the mute of a
+    /// source or the streaming and recording states change. Returns an id to unsubscribe with.
+    pub fn subscribe_events<F: Fn(&AppEvent) + 'static>(self: &Self, callback: F) -> usize {
 
-        set_up();
+        let id = self.app.next_subscription.get();
+        self.app.next_subscription.set(id + 1);
+        self.app.event_subscribers.borrow_mut().push((id, Rc::new(callback)));
 
-        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        id
 
-        assert_eq!(app.app.sources.borrow_mut().len(), 0);
+    }
 
-        let source = app.make_source("dummy", None);
+    pub fn unsubscribe_events(self: &Self, id: usize) -> bool {
 
-        assert!(
-            match &source {
-                Ok(_el) => true,
-                Err(_err) => false
-            }
-        );
+        let mut subscribers = self.app.event_subscribers.borrow_mut();
+        let len = subscribers.len();
+        subscribers.retain(|(subscription, _)| *subscription != id);
 
-        let gst_src = &source.unwrap().element; 
+        subscribers.len() != len
 
-        assert!(match gst_src.static_pad("video_src").unwrap().peer() {
-            Some(peer_pad) => peer_pad.name().eq("video_sink_0"),
-            None => false
-        });
+    }
 
-        assert!(match gst_src.static_pad("audio_src").unwrap().peer() {
-            Some(peer_pad) => peer_pad.name().eq("audio_sink_0"),
-            None => false
-        });
+    /// Put a scene on program: its items get their parameters back (the ones they do not set
+    /// going back to their defaults), and sources it does not show are hidden and muted. Until
+    /// a scene is set, every source is shown.
+    pub fn set_current_scene(self: &Self, name: &str) -> Result<(), Error> {
 
-        assert_eq!(app.app.sources.borrow_mut().len(), 1);
+        let scene = self.scene(name)
+            .ok_or_else(|| DkcError {src_name: name.to_string(),
+                                     src_type: "DkcScene".to_string(),
+                                     error_msg: "Unknown scene.".to_string()})?;
 
-        assert!(
-            match app.make_source("IdoNotExist", None) {
-                Ok(_el) => false,
-                Err(_err) => true
+        /* Nothing changes unless every item of the scene can be applied */
+        for wrapped_source in scene.wrapped_sources.borrow().iter() {
+            wrapped_source.check_params(&wrapped_source.params.borrow())?;
+        }
+
+        /* Scene items share the inputs of their source, which keep what the previous program
+         * scene set otherwise */
+        let mut updates = gst::Structure::new_empty("updates");
+        for wrapped_source in scene.wrapped_sources.borrow().iter() {
+            let mut params = wrapped_source.source.defaults.borrow().clone();
+            for (param, value) in wrapped_source.params.borrow().iter() {
+                if let Some(input_value) = wrapped_source.to_input_value(param, value) {
+                    params.insert(param.clone(), input_value);
+                }
             }
-        );
+            params.retain(|param, _| !VISIBILITY_PARAMS.contains(&param.as_str())); // See `refresh_source`
+            wrapped_source.input_updates(&mut updates, &params);
+        }
+        self.app.gst_scene.emit_by_name_with_values("update-inputs", &[updates.to_value()]);
 
-    }
+        *self.app.current_scene.borrow_mut() = Some(name.to_string());
+        scene.apply_background();
+        for source in self.app.sources.borrow().iter() {
+            self.app.refresh_source(source);
+        }
 
-    #[test]
-    fn test_make_sink() {
+        self.app.emit(AppEvent::CurrentSceneChanged(name.to_string()));
+        Ok(())
 
-        set_up();
+    }
 
-        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+    pub fn current_scene(self: &Self) -> Option<Rc<Scene>> {
+        self.app.current_scene.borrow().as_deref().and_then(|name| self.scene(name))
+    }
 
-        assert_eq!(app.app.sinks.borrow_mut().len(), 0);
+    /// Record the program to a Matroska file, which stays readable when stopped abruptly.
+    pub fn start_recording(self: &Self, path: &Path) -> Result<(), Error> {
 
-        let sink = app.make_sink("dummy", None);
+        if self.app.recording.borrow().is_some() {
+            return Err(self.app.error("Already recording.").into());
+        }
 
-        assert!(
-            match &sink {
-                Ok(_el) => true,
-                Err(_err) => false
-            }
-        );
+        let sink = self.app.start_sink("record", &path.to_string_lossy())?;
 
-        let gst_sink = &sink.unwrap().element;
+        *self.app.recording.borrow_mut() = Some(sink);
+        self.app.emit(AppEvent::RecordingChanged(true));
+        Ok(())
 
-        assert!(match gst_sink.static_pad("video_sink").unwrap().peer() {
-            Some(peer_pad) => peer_pad.name().eq("video_src_0"),
-            None => false
-        });
+    }
 
-        assert!(match gst_sink.static_pad("audio_sink").unwrap().peer() {
-            Some(peer_pad) => peer_pad.name().eq("audio_src_0"),
-            None => false
-        });
+    pub fn stop_recording(self: &Self) -> Result<(), Error> {
 
-        assert_eq!(app.app.sinks.borrow_mut().len(), 1);
+        let sink = self.app.recording.borrow_mut().take()
+            .ok_or_else(|| self.app.error("Not recording."))?;
+        self.app.remove_sink(&sink);
 
-        assert!(
-            match app.make_sink("IdoNotExist", None) {
-                Ok(_el) => false,
-                Err(_err) => true
-            }
-        );
+        self.app.emit(AppEvent::RecordingChanged(false));
+        Ok(())
 
     }
 
-    #[test]
-    fn test_make_scene() {
+    /// Path of the file being recorded to, if any.
+    pub fn recording(self: &Self) -> Option<PathBuf> {
+        self.app.recording.borrow().as_ref()
+            .and_then(|sink| sink.location()).map(PathBuf::from)
+    }
 
-        set_up();
+    /// Stream the program to an RTMP server, `url` holding the stream key.
+    pub fn start_streaming(self: &Self, url: &str) -> Result<(), Error> {
 
-        let app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        if self.app.streaming.borrow().is_some() {
+            return Err(self.app.error("Already streaming.").into());
+        }
 
-        let scene = app.make_scene(Some("dummyscene"));
+        let sink = self.app.start_sink("stream", url)?;
 
-        assert!(
-            match &scene {
-                Ok(_el) => true,
-                Err(_err) => false
-            }
-        );
+        *self.app.streaming.borrow_mut() = Some(sink);
+        self.app.emit(AppEvent::StreamingChanged(true));
+        Ok(())
 
     }
 
-    #[test]
-    fn test_scene_add_source() {
+    pub fn stop_streaming(self: &Self) -> Result<(), Error> {
 
-        set_up();
+        let sink = self.app.streaming.borrow_mut().take()
+            .ok_or_else(|| self.app.error("Not streaming."))?;
+        self.app.remove_sink(&sink);
 
-        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        self.app.emit(AppEvent::StreamingChanged(false));
+        Ok(())
 
-        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
-        let source = app.make_source("dummy", None).expect("Could not make source.");
+    }
 
-        assert!(
-            match &scene.add_source(source) {
-                Ok(_wrpd) => true,
-                Err(_err) => false
-            }
-        );
+    /// URL being streamed to, if any.
+    pub fn streaming(self: &Self) -> Option<String> {
+        self.app.streaming.borrow().as_ref()
+            .and_then(|sink| sink.location())
+    }
+
+    /// Call `callback` with the levels of every audio source and of the master mix, as they
+    /// are measured while the app is turned on. Returns an id to unsubscribe with.
+    pub fn subscribe_levels<F: Fn(&AudioLevels) + 'static>(self: &Self, callback: F) -> usize {
+
+        let id = self.app.next_subscription.get();
+        self.app.next_subscription.set(id + 1);
+        self.app.level_subscribers.borrow_mut().push((id, Rc::new(callback)));
+
+        id
+
+    }
+
+    pub fn unsubscribe_levels(self: &Self, id: usize) -> bool {
+
+        let mut subscribers = self.app.level_subscribers.borrow_mut();
+        let len = subscribers.len();
+        subscribers.retain(|(subscription, _)| *subscription != id);
+
+        subscribers.len() != len
+
+    }
+
+    /// Set how often audio levels are measured.
+    pub fn set_level_interval(self: &Self, interval: Duration) {
+        self.app.gst_scene.set_property("level-interval", (interval.as_nanos() as u64).max(1));
+    }
+
+    /// Play the monitor mix on the local audio device, sources are only heard there once
+    /// their monitor mode is set (see `WrappedSource::set_monitor`).
+    pub fn set_monitoring(self: &Self, enabled: bool) -> Result<(), Error> {
+
+        let mut monitor = self.app.monitor.borrow_mut();
+
+        match (enabled, monitor.take()) {
+            (true, None) => {
+                let error = |error_msg: &str| DkcError {src_name: self.app.gst_bin.name().to_string(),
+                                                        src_type: "DkcApp".to_string(),
+                                                        error_msg: error_msg.to_string()};
+
+                let output = gst::parse_bin_from_description("audioconvert ! audioresample ! autoaudiosink", true)
+                    .map_err(|_| error("Could not make monitor output."))?
+                    .upcast::<gst::Element>();
+                let monitor_src_pad = self.app.gst_scene.request_pad_simple("monitor_src_%u")
+                    .ok_or_else(|| error("Could not get monitor pad."))?;
+
+                self.app.gst_bin.add(&output).map_err(|_| error("Could not add monitor output to bin."))?;
+                monitor_src_pad.link(&output.static_pad("sink").unwrap())
+                    .map_err(|_| error("Could not link monitor pads."))?;
+                let _ = output.sync_state_with_parent();
+
+                *monitor = Some((monitor_src_pad, output));
+            },
+            (false, Some((monitor_src_pad, output))) => {
+                let _ = output.set_state(gst::State::Null);
+                let _ = self.app.gst_bin.remove(&output);
+                self.app.gst_scene.release_request_pad(&monitor_src_pad);
+            },
+            (_, current) => *monitor = current,
+        }
+
+        Ok(())
+
+    }
+
+    pub fn monitoring(self: &Self) -> bool {
+        self.app.monitor.borrow().is_some()
+    }
+
+    /// Current `dkcscene` input parameters of every linked source, keyed by source name.
+    pub fn input_params(self: &Self) -> BTreeMap<String, BTreeMap<String, ParamValue>> {
+
+        let inputs = self.app.query_inputs();
+        self.app.sources.borrow().iter()
+            .map(|source| (source.name(), source.input_params_from(&inputs)))
+            .collect()
+
+    }
+
+    pub fn collection(self: &Self) -> SceneCollection {
+
+        let sources = self.app.sources.borrow().iter()
+            .map(|source| SourceDesc { name: source.name(),
+                                       source_type: source.source_type.clone(),
+                                       properties: source.properties.borrow().clone() })
+            .collect();
+
+        let scenes = self.app.scenes.borrow().iter()
+            .map(|scene| SceneDesc {
+                name: scene.name.clone(),
+                items: scene.wrapped_sources.borrow().iter()
+                    .map(|wrapped| SceneItemDesc { source: wrapped.source.name(),
+                                                   visible: wrapped.visible.get(),
+                                                   params: wrapped.params.borrow().clone(),
+                                                   filters: wrapped.filters.borrow().clone(),
+                                                   audio_filters: wrapped.audio_filters.borrow().clone() })
+                    .collect(),
+                background: scene.background.borrow().clone() })
+            .collect();
+
+        let sinks = self.app.sinks.borrow().iter()
+            .map(|sink| SinkDesc { name: sink.name(),
+                                   sink_type: sink.sink_type.clone(),
+                                   properties: sink.properties.borrow().clone(),
+                                   output: sink.output_params.borrow().clone() })
+            .collect();
+
+        SceneCollection { version: COLLECTION_VERSION,
+                          canvas: CanvasDesc { width: self.app.width.get(),
+                                               height: self.app.height.get(),
+                                               framerate: self.app.framerate.get(),
+                                               format: self.app.format.borrow().clone(),
+                                               audio_rate: self.app.audio_rate.get(),
+                                               audio_channels: self.app.audio_channels.get(),
+                                               audio_tracks: self.app.audio_tracks.get() },
+                          current_scene: self.app.current_scene.borrow().clone(),
+                          sources, scenes, sinks }
+
+    }
+
+    pub fn save(self: &Self, path: &Path) -> Result<(), Error> {
+        self.collection().save(path)
+    }
+
+    pub fn load(path: &Path, name: Option<&str>) -> Result<App, Error> {
+        App::from_collection(&SceneCollection::load(path)?, name)
+    }
+
+    pub fn from_collection(collection: &SceneCollection, name: Option<&str>) -> Result<App, Error> {
+
+        let collection_error = |src_name: &str, src_type: &str, error_msg: String| -> Error {
+            DkcError {src_name: src_name.to_string(),
+                      src_type: src_type.to_string(),
+                      error_msg}.into()
+        };
+
+        let mut app = make_app(name, collection.canvas.width, collection.canvas.height)?;
+        app.set_framerate(collection.canvas.framerate.0, collection.canvas.framerate.1)?;
+        app.set_pixel_format(collection.canvas.format.as_deref())?;
+        app.set_audio_format(collection.canvas.audio_rate, collection.canvas.audio_channels)?;
+        app.set_audio_tracks(collection.canvas.audio_tracks)?;
+
+        for source_desc in collection.sources.iter() {
+            if gst::ElementFactory::find(&format!("dkc{}source", source_desc.source_type)).is_none() {
+                return Err(collection_error(&source_desc.name, "DkcSource",
+                                            format!("Unknown source type '{}'.", source_desc.source_type)));
+            }
+
+            let source = app.make_source(&source_desc.source_type, Some(&source_desc.name))?;
+            for (property, value) in source_desc.properties.iter() {
+                source.set_property(property, value.clone())?;
+            }
+        }
+
+        for sink_desc in collection.sinks.iter() {
+            if gst::ElementFactory::find(&format!("dkc{}sink", sink_desc.sink_type)).is_none() {
+                return Err(collection_error(&sink_desc.name, "DkcSink",
+                                            format!("Unknown sink type '{}'.", sink_desc.sink_type)));
+            }
+
+            let sink = app.make_sink(&sink_desc.sink_type, Some(&sink_desc.name))?;
+            for (property, value) in sink_desc.properties.iter() {
+                sink.set_property(property, value.clone())?;
+            }
+            for (param, value) in sink_desc.output.iter() {
+                sink.update_output(param, value.clone())?;
+            }
+        }
+
+        for scene_desc in collection.scenes.iter() {
+            let scene = app.make_scene(Some(&scene_desc.name))?;
+            scene.set_background(scene_desc.background.clone())?;
+
+            for item in scene_desc.items.iter() {
+                let source = app.source(&item.source)
+                    .ok_or_else(|| collection_error(&scene_desc.name, "DkcScene",
+                                                    format!("Unknown source '{}'.", item.source)))?;
+
+                /* Scene items only drive the inputs once their scene is on program */
+                let wrapped_source = scene.add_source(source)?;
+                wrapped_source.check_params(&item.params)?;
+                wrapped_source.params.borrow_mut().extend(item.params.clone());
+                wrapped_source.visible.set(item.visible);
+                for filter in item.filters.iter() {
+                    wrapped_source.add_filter(&filter.name, &filter.kind, None)?;
+                    for (param, value) in filter.params.iter() {
+                        wrapped_source.update_filter(&filter.name, param, value.clone())?;
+                    }
+                }
+                for filter in item.audio_filters.iter() {
+                    wrapped_source.add_audio_filter(&filter.name, &filter.kind, None)?;
+                    for (param, value) in filter.params.iter() {
+                        wrapped_source.update_audio_filter(&filter.name, param, value.clone())?;
+                    }
+                }
+            }
+        }
+
+        if let Some(current_scene) = &collection.current_scene {
+            app.set_current_scene(current_scene)?;
+        }
+
+        Ok(app)
+
+    }
+
+    pub fn make_replay_buffer(self: &mut Self,
+                              seconds: u32,
+                              name: Option<&str>) -> Result<Rc<Sink>, Error> {
+
+        let sink = self.make_sink("replay", name)?;
+        sink.set_property("duration", ParamValue::UInt(seconds))?;
+
+        Ok(sink)
+
+    }
+
+    pub fn save_replay(self: &Self, path: &Path) -> Result<(), Error> {
+
+        let replay_sink = self.app.sinks.borrow().iter()
+            .find(|sink| sink.element.factory().map_or(false, |factory| factory.name() == "dkcreplaysink"))
+            .cloned();
+
+        match replay_sink {
+            Some(sink) => {
+                let saved = sink.element.emit_by_name_with_values("save", &[path.display().to_string().into()])
+                    .and_then(|ret| ret.get::<bool>().ok())
+                    .unwrap_or(false);
+
+                if saved {
+                    Ok(())
+                } else {
+                    Err(DkcError {src_name: sink.element.name().to_string(),
+                                  src_type: "DkcSink".to_string(),
+                                  error_msg: "Nothing to save from replay buffer.".to_string()}.into())
+                }
+            },
+            None => Err(DkcError {src_name: self.app.gst_bin.name().to_string(),
+                                  src_type: "DkcApp".to_string(),
+                                  error_msg: "No replay buffer in this app.".to_string()}.into()),
+        }
+
+    }
+
+    /// Grab the next composited frame, waiting for it up to a second.
+    pub fn snapshot(self: &Self) -> Result<Snapshot, Error> {
+
+        let scene_name = self.app.gst_scene.name().to_string();
+        let make_error = |error_msg: &str| -> Error {
+            DkcError {src_name: scene_name.clone(),
+                      src_type: "DkcScene".to_string(),
+                      error_msg: error_msg.to_string()}.into()
+        };
+
+        /* scene video_src_%u ! queue ! videoconvert ! capsfilter (RGBA) ! appsink */
+        let queue = gst::ElementFactory::make("queue", None)
+            .map_err(|_| make_error("Could not make snapshot queue."))?;
+        let convert = gst::ElementFactory::make("videoconvert", None)
+            .map_err(|_| make_error("Could not make snapshot video converter."))?;
+        let capsfilter = gst::ElementFactory::make("capsfilter", None)
+            .map_err(|_| make_error("Could not make snapshot caps filter."))?;
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .map_err(|_| make_error("Could not make snapshot app sink."))?
+            .dynamic_cast::<gst_app::AppSink>()
+            .expect("appsink element is not an AppSink");
+
+        capsfilter.set_property("caps", &Snapshot::rgba_caps());
+
+        /* Never hold the pipeline back: keep a single frame and drop the rest */
+        appsink.set_property("sync", false);
+        appsink.set_property("async", false);
+        appsink.set_property("drop", true);
+        appsink.set_property("max-buffers", 1u32);
+        appsink.set_property("enable-last-sample", false);
+
+        let elements = [&queue, &convert, &capsfilter, appsink.upcast_ref::<gst::Element>()];
+
+        self.app.gst_bin.add_many(&elements)
+            .map_err(|_| make_error("Could not add snapshot elements to bin."))?;
+
+        /* The branch only lives for one frame, nothing is kept around once it is taken. It is
+         * running before frames reach it, and they stop reaching it before it is torn down */
+        let video_src_pad = self.app.gst_scene.request_pad_simple("video_src_%u");
+        let sample = match video_src_pad.as_ref() {
+            Some(video_src_pad) if gst::Element::link_many(&elements).is_ok() => {
+                for element in elements.iter().rev() {
+                    let _ = element.sync_state_with_parent();
+                }
+
+                let (src_pad, sink_pad) = (video_src_pad.clone(), queue.static_pad("sink").unwrap());
+                when_idle(video_src_pad, move || { let _ = src_pad.link(&sink_pad); });
+                if video_src_pad.is_linked() {
+                    appsink.try_pull_sample(gst::ClockTime::SECOND)
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        if let Some(video_src_pad) = video_src_pad {
+            let src_pad = video_src_pad.clone();
+            when_idle(&video_src_pad, move || {
+                if let Some(peer) = src_pad.peer() {
+                    let _ = src_pad.unlink(&peer);
+                }
+            });
+            self.app.gst_scene.release_request_pad(&video_src_pad);
+        }
+        for element in elements.iter() {
+            let _ = element.set_state(gst::State::Null);
+            let _ = self.app.gst_bin.remove(*element);
+        }
+
+        match sample {
+            Some(sample) => Snapshot::from_sample(&sample),
+            None => Err(make_error("No frame available for snapshot.")),
+        }
+
+    }
+
+    pub fn turn_on(self: &mut Self) -> Result<(), Error> {
+        let pipeline = &self.app.gst_bin;
+
+        pipeline.set_state(gst::State::Playing).expect("Could not set pipeline state to 'Playing'.");
+
+        let bus = pipeline
+            .bus()
+            .expect("Pipeline without bus. Shouldn't happen!");
+
+        /* Bus messages are watched from the main context, which also serves remote control requests */
+        let context = gst::glib::MainContext::ref_thread_default();
+        let main_loop = gst::glib::MainLoop::new(Some(&context), false);
+
+        let app = self.app.clone();
+        let loop_ref = main_loop.clone();
+        bus.add_watch_local(move |_bus, msg| {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => loop_ref.quit(),
+                MessageView::Error(_err) => {
+                    app.gst_bin.set_state(gst::State::Null).expect("Could not set pipeline state to 'Null'.");
+                    loop_ref.quit();
+                }
+                MessageView::Element(..) => app.handle_levels(msg),
+                _ => (),
+            }
+
+            gst::glib::Continue(true)
+        }).expect("Could not watch pipeline bus.");
+
+        main_loop.run();
+        let _ = bus.remove_watch();
+
+        pipeline.set_state(gst::State::Null).expect("Could not set pipeline state to 'Null'.");
+
+        Ok(())
+
+    }
+}
+
+impl AppImpl {
+    fn error(self: &Self, error_msg: &str) -> DkcError {
+        DkcError {src_name: self.gst_bin.name().to_string(),
+                  src_type: "DkcApp".to_string(),
+                  error_msg: error_msg.to_string()}
+    }
+
+    fn emit(self: &Self, event: AppEvent) {
+
+        /* Subscribers may unsubscribe from their callback */
+        let subscribers: Vec<_> = self.event_subscribers.borrow().iter()
+            .map(|(_, callback)| callback.clone()).collect();
+        for callback in subscribers.iter() {
+            callback(&event);
+        }
+
+    }
+
+    /* Scene items drive the inputs of their source while their scene is on program, or while no
+     * scene is */
+    fn drives_inputs(self: &Self, scene: &str) -> bool {
+        self.current_scene.borrow().as_deref().map_or(true, |current| current == scene)
+    }
+
+    /* Sources outside of the program scene, or hidden in it, are neither seen nor heard. Their
+     * inputs go through the filters of the program scene item, if any */
+    fn refresh_source(self: &Self, source: &Source) {
+
+        let scene = self.current_scene.borrow().as_ref()
+            .and_then(|name| self.scenes.borrow().iter().find(|scene| scene.name == *name).cloned());
+        let wrapped = scene.as_ref()
+            .and_then(|scene| scene.wrapped_sources.borrow().iter()
+                      .find(|wrapped| std::ptr::eq(&*wrapped.source, source)).cloned());
+
+        let chain = wrapped.as_ref().map(|wrapped| wrapped.scene.clone()).unwrap_or_default();
+        for input in [source.video_input.borrow().clone(), source.audio_input.borrow().clone()].iter().flatten() {
+            self.gst_scene.emit_by_name_with_values("select-filter-chain", &[input.to_value(), chain.to_value()]);
+        }
+        let shown = scene.is_none() || wrapped.as_ref().map_or(false, |wrapped| wrapped.visible.get());
+        let param = |name: &str| wrapped.as_ref().and_then(|wrapped| wrapped.params.borrow().get(name).cloned());
+
+        let mut params = gst::Structure::new_empty("params");
+        if scene.is_some() {
+            let alpha = match param("alpha") {
+                _ if !shown => 0.0,
+                Some(ParamValue::Double(alpha)) => alpha,
+                _ => 1.0,
+            };
+            params.set("alpha", alpha);
+        }
+        params.set("mute", !shown || source.muted.get() || param("mute") == Some(ParamValue::Bool(true)));
+
+        let mut updates = gst::Structure::new_empty("updates");
+        for input in [source.video_input.borrow().clone(), source.audio_input.borrow().clone()].iter().flatten() {
+            updates.set(input.as_str(), params.clone());
+        }
+        self.gst_scene.emit_by_name_with_values("update-inputs", &[updates.to_value()]);
+
+    }
+
+    /* Sink element linked to new scene outputs, left for the caller to keep track of */
+    fn make_sink(self: &Rc<Self>,
+                 sink_type: &str,
+                 name: Option<&str>) -> Result<Rc<Sink>, Error> {
+
+        match gst::ElementFactory::make(&format!("dkc{}sink", sink_type),
+                                        name) {
+            Ok(element) => {
+                let id = self.sinks.borrow().len();
+                let element_name = element.name();
+
+                let sink = Rc::new(
+                    Sink { app: self.clone(), element,
+                           sink_type: sink_type.to_string(),
+                           properties: RefCell::new(BTreeMap::new()),
+                           video_output: RefCell::new(None),
+                           audio_output: RefCell::new(None),
+                           output_params: RefCell::new(BTreeMap::new()), id });
+
+                if self.gst_bin.add(&sink.element).is_err() {
+                    return Err(DkcError {src_name: element_name.to_string(),
+                                         src_type: "DkcSink".to_string(),
+                                         error_msg: "Could not add element to bin".to_string()}.into());
+                }
+
+                let video_ret : std::result::Result<(), Error> = match sink.element.static_pad("video_sink") {
+                    Some(video_sink_pad) => {
+                        match self.gst_scene.request_pad_simple("video_src_%u") {
+                            Some(video_src_pad) => {
+                                match video_src_pad.link(&video_sink_pad) {
+                                    Ok(_success) => {
+                                        *sink.video_output.borrow_mut() = Some(video_src_pad.name().to_string());
+                                        Ok(())
+                                    },
+                                    Err(_error) => Err(DkcError {src_name: element_name.to_string(),
+                                                                 src_type: "DkcSink".to_string(),
+                                                                 error_msg: "Could not link video pads.".to_string()}
+                                                       .into()),
+                                }
+                            },
+                            None => Err(DkcError {src_name: element_name.to_string(),
+                                                  src_type: "DkcSink".to_string(),
+                                                  error_msg: "Could not find video sink pad.".to_string()}.into())
+                        }
+                    },
+                    None => Ok(()),
+                }; video_ret?;
+
+                let audio_ret : std::result::Result<(), Error> = match sink.element.static_pad("audio_sink") {
+                    Some(audio_sink_pad) => {
+                        match self.gst_scene.request_pad_simple("audio_src_%u") {
+                            Some(audio_src_pad) => {
+                                match audio_src_pad.link(&audio_sink_pad) {
+                                    Ok(_success) => {
+                                        *sink.audio_output.borrow_mut() = Some(audio_src_pad.name().to_string());
+                                        Ok(())
+                                    },
+                                    Err(_error) => Err(DkcError {src_name: element_name.to_string(),
+                                                                 src_type: "DkcSink".to_string(),
+                                                                 error_msg: "Could not link audio pads.".to_string()}
+                                                       .into()),
+                                }
+                            },
+                            None => Err(DkcError {src_name: element_name.to_string(),
+                                                  src_type: "DkcSink".to_string(),
+                                                  error_msg: "Could not find audio sink pad.".to_string()}.into())
+                        }
+                    },
+                    None => Ok(()),
+                }; audio_ret?;
+
+                Ok(sink)
+            },
+            Err(_) => Err(DkcError {src_name: format!("{:?}", name),
+                                    src_type: "DkcSink".to_string(),
+                                    error_msg: "Could not make source element.".to_string()}.into()),
+        }
+
+    }
+
+    /* Sink made while the app may be on, started once configured and removed when done */
+    fn start_sink(self: &Rc<Self>, sink_type: &str, location: &str) -> Result<Rc<Sink>, Error> {
+
+        let sink = self.make_sink(sink_type, None)?;
+        let started = sink.set_property("location", ParamValue::from(location))
+            .and_then(|_| sink.element.sync_state_with_parent()
+                      .map_err(|_| DkcError {src_name: sink.name(),
+                                             src_type: "DkcSink".to_string(),
+                                             error_msg: "Could not start sink.".to_string()}.into()));
+
+        if let Err(error) = started {
+            self.remove_sink(&sink);
+            return Err(error);
+        }
+
+        Ok(sink)
+
+    }
+
+    /* Sink cut off from the scene and drained, so muxers finish the file or stream it writes */
+    fn remove_sink(self: &Self, sink: &Sink) {
+
+        let outputs = [sink.video_output.borrow().clone(), sink.audio_output.borrow().clone()];
+        let playing = sink.element.current_state() == gst::State::Playing;
+
+        /* Every sink element inside reports its EOS, the sink being drained once they all have */
+        let (sender, receiver) = mpsc::channel();
+        let inner_sinks = match sink.element.downcast_ref::<gst::Bin>() {
+            Some(bin) if playing => bin.iterate_sinks().into_iter().flatten().collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        let probes = inner_sinks.iter().filter_map(|element| {
+            let pad = element.static_pad("sink")?;
+            let sender = Mutex::new(sender.clone());
+            let probe = pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, move |_pad, info| {
+                if let Some(gst::PadProbeData::Event(ref event)) = info.data {
+                    if event.type_() == gst::EventType::Eos {
+                        let _ = sender.lock().unwrap().send(());
+                    }
+                }
+                gst::PadProbeReturn::Ok
+            })?;
+            Some((pad, probe))
+        }).collect::<Vec<_>>();
+
+        for output in outputs.iter().flatten() {
+            let pad = match self.gst_scene.static_pad(output) {
+                Some(pad) => pad,
+                None => continue,
+            };
+            let peer = match pad.peer() {
+                Some(peer) => peer,
+                None => continue,
+            };
+
+            let (src_pad, sink_pad) = (pad.clone(), peer.clone());
+            when_idle(&pad, move || { let _ = src_pad.unlink(&sink_pad); });
+            if playing {
+                peer.send_event(gst::event::Eos::new());
+            }
+        }
+
+        for _ in probes.iter() {
+            if receiver.recv_timeout(Duration::from_secs(5)).is_err() {
+                break;
+            }
+        }
+        for (pad, probe) in probes {
+            pad.remove_probe(probe);
+        }
+
+        let _ = sink.element.set_state(gst::State::Null);
+        let _ = self.gst_bin.remove(&sink.element);
+        for output in outputs.iter().flatten() {
+            if let Some(pad) = self.gst_scene.static_pad(output) {
+                self.gst_scene.release_request_pad(&pad);
+            }
+        }
+
+    }
+
+    fn handle_levels(self: &Self, msg: &gst::Message) {
+
+        let structure = match msg.structure() {
+            Some(structure) if structure.name() == "dkc-audio-level" => structure,
+            _ => return,
+        };
+
+        let input = structure.get::<String>("input").unwrap_or_default();
+        let source = self.sources.borrow().iter()
+            .find(|source| source.audio_input.borrow().as_deref() == Some(input.as_str()))
+            .map(|source| source.name());
+        if source.is_none() && input != "master" {
+            return; // Input of a source that was removed since
+        }
+
+        let values = |field: &str| structure.get::<gst::Array>(field)
+            .map(|values| values.as_slice().iter().filter_map(|value| value.get::<f64>().ok()).collect())
+            .unwrap_or_default();
+        let levels = AudioLevels { source, peak: values("peak"), rms: values("rms"), decay: values("decay") };
+
+        /* Subscribers may unsubscribe from their callback */
+        let subscribers: Vec<_> = self.level_subscribers.borrow().iter()
+            .map(|(_, callback)| callback.clone()).collect();
+        for callback in subscribers.iter() {
+            callback(&levels);
+        }
+
+    }
+
+    fn query_inputs(self: &Self) -> gst::Structure {
+        self.gst_scene.emit_by_name_with_values("query-inputs", &[])
+            .and_then(|ret| ret.get::<gst::Structure>().ok())
+            .unwrap_or_else(|| gst::Structure::new_empty("inputs"))
+    }
+}
+
+impl Source {
+    pub fn name(self: &Self) -> String {
+        self.element.name().to_string()
+    }
+
+    pub fn source_type(self: &Self) -> &str {
+        &self.source_type
+    }
+
+    pub fn set_property(self: &Self, name: &str, value: ParamValue) -> Result<(), Error> {
+
+        param::set_element_property(&self.element, name, &value)
+            .map_err(|error_msg| DkcError {src_name: self.name(),
+                                           src_type: "DkcSource".to_string(),
+                                           error_msg})?;
+        self.properties.borrow_mut().insert(name.to_string(), value);
+
+        Ok(())
+
+    }
+
+    pub fn properties(self: &Self) -> BTreeMap<String, ParamValue> {
+        self.properties.borrow().clone()
+    }
+
+    /// Mute the source in every scene, over the "mute" parameter of its scene items.
+    pub fn set_muted(self: &Self, muted: bool) {
+
+        self.muted.set(muted);
+        self.app.refresh_source(self);
+        self.app.emit(AppEvent::SourceMuteChanged { source: self.name(), muted });
+
+    }
+
+    pub fn muted(self: &Self) -> bool {
+        self.muted.get()
+    }
+
+    /// Current `dkcscene` input parameters of the source, as read back from the scene.
+    pub fn input_params(self: &Self) -> BTreeMap<String, ParamValue> {
+        self.input_params_from(&self.app.query_inputs())
+    }
+
+    fn input_params_from(self: &Self, inputs: &gst::Structure) -> BTreeMap<String, ParamValue> {
+
+        let mut params = BTreeMap::new();
+        let input_names = [self.video_input.borrow().clone(),
+                            self.audio_input.borrow().clone()];
+
+        for input_name in input_names.iter().flatten() {
+            if let Ok(input) = inputs.get::<gst::Structure>(input_name.as_str()) {
+                for (param, value) in input.iter() {
+                    if let Some(value) = ParamValue::from_value(value) {
+                        params.insert(param.to_string(), value);
+                    }
+                }
+            }
+        }
+
+        params
+
+    }
+
+    /// Grab the next frame of the source, waiting for it up to a second.
+    pub fn snapshot(self: &Self) -> Result<Snapshot, Error> {
+
+        let element_name = self.element.name().to_string();
+        let make_error = |error_msg: &str| -> Error {
+            DkcError {src_name: element_name.clone(),
+                      src_type: "DkcSource".to_string(),
+                      error_msg: error_msg.to_string()}.into()
+        };
+
+        let video_src_pad = self.element.static_pad("video_src")
+            .ok_or_else(|| make_error("Source has no video src pad."))?;
+        if video_src_pad.current_caps().is_none() {
+            return Err(make_error("Source video caps are not negotiated yet."));
+        }
+
+        /* Only the next outgoing frame is taken, nothing is kept around in between */
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let sender = Mutex::new(Some(sender));
+        let probe = video_src_pad.add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+            if let (Some(gst::PadProbeData::Buffer(ref buffer)), Some(caps)) = (&info.data, pad.current_caps()) {
+                if let Some(sender) = sender.lock().unwrap().take() {
+                    let _ = sender.send(gst::Sample::builder().buffer(buffer).caps(&caps).build());
+                }
+            }
+            gst::PadProbeReturn::Ok
+        });
+
+        let sample = receiver.recv_timeout(Duration::from_secs(1));
+        if let Some(probe) = probe {
+            video_src_pad.remove_probe(probe);
+        }
+
+        match sample {
+            Ok(sample) => Snapshot::from_sample(&sample),
+            Err(_) => Err(make_error("No frame available for snapshot.")),
+        }
+
+    }
+}
+
+impl Snapshot {
+    fn rgba_caps() -> gst::Caps {
+        gst::Caps::new_simple(
+            "video/x-raw",
+            &[("format", &"RGBA")],
+        )
+    }
+
+    fn from_sample(sample: &gst::Sample) -> Result<Snapshot, Error> {
+
+        let info = sample.caps().and_then(|caps| gst_video::VideoInfo::from_caps(caps).ok());
+
+        let sample = match info {
+            Some(ref info) if info.format() == gst_video::VideoFormat::Rgba => sample.clone(),
+            _ => gst_video::convert_sample(sample, &Self::rgba_caps(), gst::ClockTime::SECOND)
+                .map_err(|err| DkcError {src_name: "snapshot".to_string(),
+                                         src_type: "Snapshot".to_string(),
+                                         error_msg: format!("Could not convert frame to RGBA: {}", err)})?,
+        };
+
+        Ok(Snapshot { sample })
+
+    }
+
+    fn video_info(self: &Self) -> gst_video::VideoInfo {
+        gst_video::VideoInfo::from_caps(self.sample.caps().expect("Snapshot sample without caps"))
+            .expect("Snapshot sample with invalid caps")
+    }
+
+    pub fn width(self: &Self) -> u32 {
+        self.video_info().width()
+    }
+
+    pub fn height(self: &Self) -> u32 {
+        self.video_info().height()
+    }
+
+    /// Tightly packed RGBA pixels, row after row.
+    pub fn rgba(self: &Self) -> Vec<u8> {
+
+        let info = self.video_info();
+        let buffer = self.sample.buffer().expect("Snapshot sample without buffer");
+        let frame = gst_video::VideoFrameRef::from_buffer_ref_readable(buffer, &info)
+            .expect("Could not map snapshot frame");
+
+        let row_size = info.width() as usize * 4;
+        let stride = frame.plane_stride()[0] as usize;
+        let data = frame.plane_data(0).expect("Could not get snapshot plane");
+
+        let mut rgba = Vec::with_capacity(row_size * info.height() as usize);
+        for row in 0..info.height() as usize {
+            rgba.extend_from_slice(&data[row * stride..row * stride + row_size]);
+        }
+
+        rgba
+
+    }
+
+    pub fn save(self: &Self, path: &Path, format: ImageFormat) -> Result<(), Error> {
+
+        let caps = match format {
+            ImageFormat::Png => gst::Caps::new_simple("image/png", &[]),
+            ImageFormat::Jpeg => gst::Caps::new_simple("image/jpeg", &[]),
+        };
+
+        let encoded = gst_video::convert_sample(&self.sample, &caps, gst::ClockTime::from_seconds(5))
+            .map_err(|err| DkcError {src_name: path.display().to_string(),
+                                     src_type: "Snapshot".to_string(),
+                                     error_msg: format!("Could not encode frame: {}", err)})?;
+
+        let buffer = encoded.buffer().expect("Encoded snapshot without buffer");
+        let map = buffer.map_readable()?;
+        std::fs::write(path, map.as_slice())?;
+
+        Ok(())
+
+    }
+}
+
+impl Sink {
+    pub fn name(self: &Self) -> String {
+        self.element.name().to_string()
+    }
+
+    pub fn sink_type(self: &Self) -> &str {
+        &self.sink_type
+    }
+
+    pub fn set_property(self: &Self, name: &str, value: ParamValue) -> Result<(), Error> {
+
+        param::set_element_property(&self.element, name, &value)
+            .map_err(|error_msg| DkcError {src_name: self.name(),
+                                           src_type: "DkcSink".to_string(),
+                                           error_msg})?;
+        self.properties.borrow_mut().insert(name.to_string(), value);
+
+        Ok(())
+
+    }
+
+    pub fn properties(self: &Self) -> BTreeMap<String, ParamValue> {
+        self.properties.borrow().clone()
+    }
+
+    fn location(self: &Self) -> Option<String> {
+        match self.properties.borrow().get("location") {
+            Some(ParamValue::String(location)) => Some(location.clone()),
+            _ => None,
+        }
+    }
+
+    /// Update the video output format of this sink (width, height, framerate, scaling-filter),
+    /// the canvas and the other sinks are left untouched.
+    pub fn update_output(self: &Self, param: &str, value: ParamValue) -> Result<(), Error> {
+
+        /* The recorded track is the only audio output parameter */
+        let output = match param {
+            "track" => &self.audio_output,
+            _ => &self.video_output,
+        };
+
+        let updated = match output.borrow().as_ref() {
+            Some(output) => self.app.gst_scene.emit_by_name_with_values(
+                "update-output", &[output.to_value(), param.to_value(), value.to_variant().to_value()])
+                .and_then(|ret| ret.get::<bool>().ok())
+                .unwrap_or(false),
+            None => false,
+        };
+
+        if !updated {
+            return Err(DkcError {src_name: self.name(),
+                                 src_type: "DkcSink".to_string(),
+                                 error_msg: format!("Could not update output parameter '{}'.", param)}.into());
+        }
+
+        self.output_params.borrow_mut().insert(param.to_string(), value);
+
+        Ok(())
+
+    }
+
+    /// Output resolution of this sink, `None` follows the canvas resolution.
+    pub fn set_output_resolution(self: &Self, resolution: Option<(u16, u16)>) -> Result<(), Error> {
+        let (width, height) = resolution.unwrap_or((0, 0));
+        self.update_output("width", ParamValue::Int(width as i32))?;
+        self.update_output("height", ParamValue::Int(height as i32))
+    }
+
+    /// Output framerate of this sink, `None` follows the canvas framerate.
+    pub fn set_output_framerate(self: &Self, framerate: Option<(i32, i32)>) -> Result<(), Error> {
+        let framerate = match framerate {
+            Some((numerator, denominator)) => format!("{}/{}", numerator, denominator),
+            None => String::new(),
+        };
+        self.update_output("framerate", ParamValue::String(framerate))
+    }
+
+    /// One of "point", "bilinear", "bicubic" or "lanczos".
+    pub fn set_scaling_filter(self: &Self, filter: &str) -> Result<(), Error> {
+        self.update_output("scaling-filter", ParamValue::from(filter))
+    }
+
+    /// Audio track recorded by this sink, 0 being the program mix.
+    pub fn set_audio_track(self: &Self, track: u32) -> Result<(), Error> {
+        self.update_output("track", ParamValue::UInt(track))
+    }
+
+    pub fn output_params(self: &Self) -> BTreeMap<String, ParamValue> {
+        self.output_params.borrow().clone()
+    }
+}
+
+impl WrappedSource {
+    pub fn source(self: &Self) -> Rc<Source> {
+        self.source.clone()
+    }
+
+    /* Ducking sources are known by name here, and by their audio input in the scene */
+    fn to_input_value(self: &Self, param: &str, value: &ParamValue) -> Option<ParamValue> {
+        match (param, value) {
+            ("duck-source", ParamValue::String(name)) if !name.is_empty() =>
+                self.source.app.sources.borrow().iter()
+                    .find(|source| source.name() == *name)
+                    .and_then(|source| source.audio_input.borrow().clone())
+                    .map(ParamValue::String),
+            _ => Some(value.clone()),
+        }
+    }
+
+    fn from_input_value(self: &Self, param: &str, value: ParamValue) -> ParamValue {
+        match (param, &value) {
+            ("duck-source", ParamValue::String(input)) if !input.is_empty() =>
+                self.source.app.sources.borrow().iter()
+                    .find(|source| source.audio_input.borrow().as_deref() == Some(input.as_str()))
+                    .map(|source| ParamValue::String(source.name()))
+                    .unwrap_or(value),
+            _ => value,
+        }
+    }
+
+    /* Whether an input of the wrapped source would take the update, without applying it */
+    fn check(self: &Self, param: &str, value: &ParamValue) -> bool {
+
+        let variant = match self.to_input_value(param, value) {
+            Some(input_value) => input_value.to_variant(),
+            None => return false,
+        };
+
+        [self.source.video_input.borrow().clone(), self.source.audio_input.borrow().clone()]
+            .iter().flatten().any(|input| {
+                self.source.app.gst_scene.emit_by_name_with_values(
+                    "check-input", &[input.to_value(), param.to_value(), variant.to_value()])
+                    .and_then(|ret| ret.get::<bool>().ok())
+                    .unwrap_or(false)
+            })
+
+    }
+
+    fn check_params(self: &Self, params: &BTreeMap<String, ParamValue>) -> Result<(), Error> {
+
+        let failed: Vec<_> = params.iter()
+            .filter(|(param, value)| !self.check(param, value))
+            .map(|(param, _)| format!("'{}'", param))
+            .collect();
+
+        if !failed.is_empty() {
+            return Err(DkcError {src_name: self.source.name(),
+                                 src_type: "DkcSource".to_string(),
+                                 error_msg: format!("Could not update input parameters {}.", failed.join(", "))}.into());
+        }
+
+        Ok(())
+
+    }
+
+    /* One structure of `update-inputs` parameters per input, in their input form */
+    fn input_updates(self: &Self, updates: &mut gst::Structure, input_params: &BTreeMap<String, ParamValue>) {
+
+        for input in [self.source.video_input.borrow().clone(), self.source.audio_input.borrow().clone()].iter().flatten() {
+            let mut params = gst::Structure::new_empty("params");
+            for (param, value) in input_params.iter() {
+                params.set_value(param, value.to_send_value());
+            }
+            updates.set(input.as_str(), params);
+        }
+
+    }
+
+    /* Once a scene is on program, hiding and muting go through `refresh_source`, which knows
+     * about the visibility of the item */
+    fn gates_visibility(self: &Self, param: &str) -> bool {
+        VISIBILITY_PARAMS.contains(&param) && self.source.app.current_scene.borrow().is_some()
+    }
+
+    /// Update a `dkcscene` input parameter (position, size, volume...) of the wrapped source,
+    /// which takes effect while its scene is on program.
+    pub fn update(self: &Self, param: &str, value: ParamValue) -> Result<(), Error> {
+
+        if !self.check(param, &value) {
+            return Err(DkcError {src_name: self.source.name(),
+                                 src_type: "DkcSource".to_string(),
+                                 error_msg: format!("Could not update input parameter '{}'.", param)}.into());
+        }
+
+        let input_value = self.to_input_value(param, &value);
+        self.params.borrow_mut().insert(param.to_string(), value);
+
+        let app = &self.source.app;
+        if !app.drives_inputs(&self.scene) {
+            return Ok(()); // Applied when the scene goes on program
+        }
+        if self.gates_visibility(param) {
+            app.refresh_source(&self.source);
+            return Ok(());
+        }
+
+        /* Every input gets the update, which only one of them takes */
+        if let Some(input_value) = input_value {
+            let variant = input_value.to_variant();
+            for input in [self.source.video_input.borrow().clone(), self.source.audio_input.borrow().clone()].iter().flatten() {
+                app.gst_scene.emit_by_name_with_values(
+                    "update-input", &[input.to_value(), param.to_value(), variant.to_value()]);
+            }
+        }
+
+        Ok(())
+
+    }
+
+    /// Update several `dkcscene` input parameters of the wrapped source at once, so that they
+    /// all take effect on the same frame, while its scene is on program. None is updated if any
+    /// is invalid.
+    pub fn update_many(self: &Self, params: &BTreeMap<String, ParamValue>) -> Result<(), Error> {
+
+        self.check_params(params)?;
+        self.params.borrow_mut().extend(params.clone());
+
+        let app = &self.source.app;
+        if !app.drives_inputs(&self.scene) {
+            return Ok(()); // Applied when the scene goes on program
+        }
+
+        let input_params = params.iter()
+            .filter(|(param, _)| !self.gates_visibility(param))
+            .filter_map(|(param, value)| Some((param.clone(), self.to_input_value(param, value)?)))
+            .collect();
+        let mut updates = gst::Structure::new_empty("updates");
+        self.input_updates(&mut updates, &input_params);
+        app.gst_scene.emit_by_name_with_values("update-inputs", &[updates.to_value()]);
+
+        if params.keys().any(|param| self.gates_visibility(param)) {
+            app.refresh_source(&self.source);
+        }
+
+        Ok(())
+
+    }
+
+    /// Crop the video of the wrapped source, in source pixels, before it is placed in the scene.
+    /// Crops are positive and leave some of the frame, as far as its size is known yet.
+    pub fn set_crop(self: &Self, top: i32, bottom: i32, left: i32, right: i32) -> Result<(), Error> {
+
+        let size = self.source.element.static_pad("video_src")
+            .and_then(|pad| pad.current_caps())
+            .and_then(|caps| caps.structure(0).and_then(|structure| {
+                Some((structure.get::<i32>("width").ok()?, structure.get::<i32>("height").ok()?))
+            }));
+        let too_large = size.map_or(false, |(width, height)| left + right >= width || top + bottom >= height);
+
+        if top < 0 || bottom < 0 || left < 0 || right < 0 || too_large {
+            return Err(DkcError {src_name: self.source.name(),
+                                 src_type: "DkcSource".to_string(),
+                                 error_msg: format!("Invalid crop {}, {}, {}, {} (top, bottom, left, right).",
+                                                    top, bottom, left, right)}.into());
+        }
+
+        let params = [("crop-top", top), ("crop-bottom", bottom), ("crop-left", left), ("crop-right", right)]
+            .iter().map(|(param, crop)| (param.to_string(), ParamValue::Int(*crop))).collect();
+        self.update_many(&params)
+
+    }
+
+    /// Scale the wrapped source into its bounding box (width and height): "stretch", "fit",
+    /// "fill" or "none" (natural size).
+    pub fn set_scale_mode(self: &Self, mode: &str) -> Result<(), Error> {
+        self.update("scale-mode", ParamValue::from(mode))
+    }
+
+    /// Rotate the wrapped source clockwise by 0, 90, 180 or 270 degrees.
+    pub fn set_rotation(self: &Self, degrees: u32) -> Result<(), Error> {
+        self.update("rotation", ParamValue::UInt(degrees))
+    }
+
+    /// Flip the wrapped source ("none", "horizontal" or "vertical"), before rotating it.
+    pub fn set_flip(self: &Self, flip: &str) -> Result<(), Error> {
+        self.update("flip", ParamValue::from(flip))
+    }
+
+    /// Blend the wrapped source with what is below it: "normal", "additive", "multiply",
+    /// "screen" or "subtract".
+    pub fn set_blend_mode(self: &Self, mode: &str) -> Result<(), Error> {
+        self.update("blend-mode", ParamValue::from(mode))
+    }
+
+    /// Route the audio of the wrapped source to the monitor mix: "off", "monitor-only" (not
+    /// heard on the program) or "monitor-and-output".
+    pub fn set_monitor(self: &Self, mode: &str) -> Result<(), Error> {
+        self.update("monitor", ParamValue::from(mode))
+    }
+
+    /// Lower the program volume of the wrapped source while `source` is louder than the
+    /// ducking threshold, `None` stops ducking.
+    pub fn set_ducking(self: &Self, source: Option<&Source>, ducking: &Ducking) -> Result<(), Error> {
+
+        let mut params = BTreeMap::new();
+        params.insert("duck-source".to_string(), ParamValue::String(source.map(|source| source.name()).unwrap_or_default()));
+        params.insert("duck-threshold".to_string(), ParamValue::Double(ducking.threshold));
+        params.insert("duck-ratio".to_string(), ParamValue::Double(ducking.ratio));
+        params.insert("duck-attack".to_string(), ParamValue::UInt(ducking.attack.as_millis().min(u32::MAX as u128) as u32));
+        params.insert("duck-release".to_string(), ParamValue::UInt(ducking.release.as_millis().min(u32::MAX as u128) as u32));
+
+        self.update_many(&params)
+
+    }
+
+    /// Record the wrapped source on the given audio tracks (from 1, see `App::set_audio_tracks`)
+    /// on top of the program mix, at unity gain.
+    pub fn set_tracks(self: &Self, tracks: &[u32]) -> Result<(), Error> {
+        let tracks = tracks.iter().map(|track| track.to_string()).collect::<Vec<String>>().join(",");
+        self.update("tracks", ParamValue::String(tracks))
+    }
+
+    /// Delay the video of the wrapped source (up to 10 seconds, millisecond precision), to get
+    /// it back in sync with its audio or with other sources.
+    pub fn set_video_offset(self: &Self, offset: Duration) -> Result<(), Error> {
+        self.update("video-offset", ParamValue::UInt(offset.as_millis().min(u32::MAX as u128) as u32))
+    }
+
+    /// Delay the audio of the wrapped source (up to 10 seconds, millisecond precision), to get
+    /// it back in sync with its video or with other sources.
+    pub fn set_audio_offset(self: &Self, offset: Duration) -> Result<(), Error> {
+        self.update("audio-offset", ParamValue::UInt(offset.as_millis().min(u32::MAX as u128) as u32))
+    }
+
+    /// Animate a `dkcscene` input parameter (position, size, alpha or volume) of the wrapped
+    /// source from its current value to `target`, following the pipeline clock.
+    pub fn animate(self: &Self, param: &str, target: ParamValue,
+                   duration: Duration, easing: Easing) -> Result<(), Error> {
+
+        /* Off program, or hidden, there is nothing to see: the item gets the target right away */
+        if !self.source.app.drives_inputs(&self.scene) || (self.gates_visibility(param) && !self.visible.get()) {
+            return self.update(param, target);
+        }
+
+        let variant = target.to_variant();
+        let inputs = [self.source.video_input.borrow().clone(),
+                      self.source.audio_input.borrow().clone()];
+
+        let animated = inputs.iter().flatten().any(|input| {
+            self.source.app.gst_scene.emit_by_name_with_values(
+                "animate-input", &[input.to_value(), param.to_value(), variant.to_value(),
+                                   (duration.as_nanos() as u64).to_value(), easing.as_str().to_value()])
+                .and_then(|ret| ret.get::<bool>().ok())
+                .unwrap_or(false)
+        });
+
+        if !animated {
+            return Err(DkcError {src_name: self.source.name(),
+                                 src_type: "DkcSource".to_string(),
+                                 error_msg: format!("Could not animate input parameter '{}'.", param)}.into());
+        }
+
+        self.params.borrow_mut().insert(param.to_string(), target);
+
+        Ok(())
+
+    }
+
+    /// Stop animating a parameter, leaving it at its current value. Returns whether an
+    /// animation was running.
+    pub fn cancel_animation(self: &Self, param: &str) -> bool {
+
+        /* Animations of the inputs belong to the program scene items */
+        if !self.source.app.drives_inputs(&self.scene) {
+            return false;
+        }
+
+        let inputs = [self.source.video_input.borrow().clone(),
+                      self.source.audio_input.borrow().clone()];
+
+        let cancelled = inputs.iter().flatten().fold(false, |cancelled, input| {
+            self.source.app.gst_scene.emit_by_name_with_values(
+                "cancel-animation", &[input.to_value(), param.to_value()])
+                .and_then(|ret| ret.get::<bool>().ok())
+                .unwrap_or(false) || cancelled
+        });
+
+        /* Keep the value the animation stopped at */
+        if cancelled {
+            self.keep_value(param);
+        }
+
+        cancelled
+
+    }
+
+    /// Stop every running animation of the wrapped source, leaving the parameters at their current
+    /// values. Returns whether an animation was running.
+    pub fn cancel_animations(self: &Self) -> bool {
+
+        if !self.source.app.drives_inputs(&self.scene) {
+            return false;
+        }
+
+        let inputs = [self.source.video_input.borrow().clone(),
+                      self.source.audio_input.borrow().clone()];
+
+        let cancelled: Vec<String> = inputs.iter().flatten().flat_map(|input| {
+            self.source.app.gst_scene.emit_by_name_with_values("cancel-animations", &[input.to_value()])
+                .and_then(|ret| ret.get::<Vec<String>>().ok())
+                .unwrap_or_default()
+        }).collect();
+
+        for param in cancelled.iter() {
+            self.keep_value(param);
+        }
+
+        !cancelled.is_empty()
+
+    }
+
+    fn keep_value(self: &Self, param: &str) {
+        if let Ok(value) = self.query(param) {
+            self.params.borrow_mut().insert(param.to_string(), value);
+        }
+    }
+
+    /// Read back the current value of a `dkcscene` input parameter of the wrapped source.
+    pub fn query(self: &Self, param: &str) -> Result<ParamValue, Error> {
+
+        let inputs = [self.source.video_input.borrow().clone(),
+                      self.source.audio_input.borrow().clone()];
+
+        let value = inputs.iter().flatten().find_map(|input| {
+            self.source.app.gst_scene.emit_by_name_with_values(
+                "query-input", &[input.to_value(), param.to_value()])
+                .and_then(|ret| ret.get::<Option<gst::glib::Variant>>().ok().flatten())
+                .and_then(|variant| ParamValue::from_variant(&variant))
+        }).map(|value| self.from_input_value(param, value));
+
+        value.ok_or_else(|| DkcError {src_name: self.source.name(),
+                                      src_type: "DkcSource".to_string(),
+                                      error_msg: format!("Could not query input parameter '{}'.", param)}.into())
+
+    }
+
+    pub fn params(self: &Self) -> BTreeMap<String, ParamValue> {
+        self.params.borrow().clone()
+    }
+
+    /* Emit a filter chain signal on the video or audio input of the wrapped source, scene items
+     * having their own chain of the input, named after their scene */
+    fn emit_filter_signal(self: &Self, chain: FilterChain, signal: &str, args: &[gst::glib::Value]) -> bool {
+
+        let input = match chain {
+            FilterChain::Video => self.source.video_input.borrow().clone(),
+            FilterChain::Audio => self.source.audio_input.borrow().clone(),
+        };
+
+        match input {
+            Some(input) => {
+                let mut values = vec![input.to_value(), self.scene.to_value()];
+                values.extend_from_slice(args);
+                self.source.app.gst_scene.emit_by_name_with_values(signal, &values)
+                    .and_then(|ret| ret.get::<bool>().ok())
+                    .unwrap_or(false)
+            },
+            None => false
+        }
+
+    }
+
+    fn chain_filters(self: &Self, chain: FilterChain) -> &RefCell<Vec<FilterDesc>> {
+        match chain {
+            FilterChain::Video => &self.filters,
+            FilterChain::Audio => &self.audio_filters,
+        }
+    }
+
+    fn filter_error(self: &Self, error_msg: String) -> Error {
+        DkcError {src_name: self.source.name(),
+                  src_type: "DkcSource".to_string(),
+                  error_msg}.into()
+    }
+
+    fn add_chain_filter(self: &Self, chain: FilterChain, name: &str, kind: &str,
+                        position: Option<usize>) -> Result<(), Error> {
+
+        let position = position.map_or(-1, |position| position as i32);
+        if !self.emit_filter_signal(chain, "add-filter", &[name.to_value(), kind.to_value(), position.to_value()]) {
+            return Err(self.filter_error(format!("Could not add filter '{}' of kind '{}'.", name, kind)));
+        }
+
+        let filter = FilterDesc { name: name.to_string(), kind: kind.to_string(), params: BTreeMap::new() };
+        let mut filters = self.chain_filters(chain).borrow_mut();
+        let index = if position < 0 { filters.len() } else { (position as usize).min(filters.len()) };
+        filters.insert(index, filter);
+
+        Ok(())
+
+    }
+
+    fn remove_chain_filter(self: &Self, chain: FilterChain, name: &str) -> Result<(), Error> {
+
+        if !self.emit_filter_signal(chain, "remove-filter", &[name.to_value()]) {
+            return Err(self.filter_error(format!("Could not remove filter '{}'.", name)));
+        }
+        self.chain_filters(chain).borrow_mut().retain(|filter| filter.name != name);
+
+        Ok(())
+
+    }
+
+    fn move_chain_filter(self: &Self, chain: FilterChain, name: &str, position: usize) -> Result<(), Error> {
+
+        if !self.emit_filter_signal(chain, "move-filter", &[name.to_value(), (position as i32).to_value()]) {
+            return Err(self.filter_error(format!("Could not move filter '{}'.", name)));
+        }
+
+        let mut filters = self.chain_filters(chain).borrow_mut();
+        if let Some(index) = filters.iter().position(|filter| filter.name == name) {
+            let filter = filters.remove(index);
+            let position = position.min(filters.len());
+            filters.insert(position, filter);
+        }
+
+        Ok(())
+
+    }
+
+    fn update_chain_filter(self: &Self, chain: FilterChain, name: &str, param: &str,
+                           value: ParamValue) -> Result<(), Error> {
+
+        if !self.emit_filter_signal(chain, "update-filter", &[name.to_value(), param.to_value(), value.to_variant().to_value()]) {
+            return Err(self.filter_error(format!("Could not update parameter '{}' of filter '{}'.", param, name)));
+        }
+
+        if let Some(filter) = self.chain_filters(chain).borrow_mut().iter_mut().find(|filter| filter.name == name) {
+            filter.params.insert(param.to_string(), value);
+        }
+
+        Ok(())
+
+    }
+
+    /// Add a filter ("color-balance", "gamma", "blur", "sharpen", "lut", "chroma-key",
+    /// "luma-key", "alpha-mask"...) to the video filter chain of the wrapped source, at
+    /// the given position or last. Filters of a scene item apply while its scene is on program.
+    pub fn add_filter(self: &Self, name: &str, kind: &str, position: Option<usize>) -> Result<(), Error> {
+        self.add_chain_filter(FilterChain::Video, name, kind, position)
+    }
+
+    pub fn remove_filter(self: &Self, name: &str) -> Result<(), Error> {
+        self.remove_chain_filter(FilterChain::Video, name)
+    }
+
+    pub fn move_filter(self: &Self, name: &str, position: usize) -> Result<(), Error> {
+        self.move_chain_filter(FilterChain::Video, name, position)
+    }
+
+    /// Set a property of the element behind a filter.
+    pub fn update_filter(self: &Self, name: &str, param: &str, value: ParamValue) -> Result<(), Error> {
+        self.update_chain_filter(FilterChain::Video, name, param, value)
+    }
+
+    pub fn filters(self: &Self) -> Vec<FilterDesc> {
+        self.filters.borrow().clone()
+    }
+
+    /// Add a filter ("gain", "noise-gate", "compressor", "limiter", "equalizer" or
+    /// "high-pass") to the audio filter chain of the wrapped source, at the given position
+    /// or last. Equalizer bands are set as "band<n>::freq", "band<n>::bandwidth" and
+    /// "band<n>::gain" parameters.
+    pub fn add_audio_filter(self: &Self, name: &str, kind: &str, position: Option<usize>) -> Result<(), Error> {
+        self.add_chain_filter(FilterChain::Audio, name, kind, position)
+    }
+
+    pub fn remove_audio_filter(self: &Self, name: &str) -> Result<(), Error> {
+        self.remove_chain_filter(FilterChain::Audio, name)
+    }
+
+    pub fn move_audio_filter(self: &Self, name: &str, position: usize) -> Result<(), Error> {
+        self.move_chain_filter(FilterChain::Audio, name, position)
+    }
+
+    pub fn update_audio_filter(self: &Self, name: &str, param: &str, value: ParamValue) -> Result<(), Error> {
+        self.update_chain_filter(FilterChain::Audio, name, param, value)
+    }
+
+    pub fn audio_filters(self: &Self) -> Vec<FilterDesc> {
+        self.audio_filters.borrow().clone()
+    }
+
+    /// Show or hide the wrapped source, which takes effect while its scene is on program.
+    pub fn set_visible(self: &Self, visible: bool) {
+
+        self.visible.set(visible);
+        self.source.app.refresh_source(&self.source);
+
+        self.source.app.emit(AppEvent::SourceVisibilityChanged { scene: self.scene.clone(), item: self.id,
+                                                                 source: self.source.name(), visible });
+
+    }
+
+    pub fn visible(self: &Self) -> bool {
+        self.visible.get()
+    }
+
+    /// Id of the wrapped source in its scene, starting at 1 and never reused by the scene.
+    pub fn id(self: &Self) -> usize {
+        self.id
+    }
+}
+
+impl Scene {
+    pub fn name(self: &Self) -> &str {
+        &self.name
+    }
+
+    pub fn add_source(self: &Self, source: Rc<Source>)
+                      -> Result<Rc<WrappedSource>, Error> {
+
+        let id = self.next_item_id.get();
+        self.next_item_id.set(id + 1);
+
+        let wrapped_source = Rc::new(WrappedSource { source: source.clone(),
+                                                     scene: self.name.clone(),
+                                                     visible: Cell::new(true),
+                                                     params: RefCell::new(BTreeMap::new()),
+                                                     filters: RefCell::new(Vec::new()),
+                                                     audio_filters: RefCell::new(Vec::new()), id });
+        self.wrapped_sources.borrow_mut().push(wrapped_source.clone());
+        self.app.refresh_source(&source);
+        Ok(wrapped_source)
+
+    }
+
+    /// Set what the canvas shows where no scene item covers it, while the scene is on program.
+    pub fn set_background(self: &Self, background: Background) -> Result<(), Error> {
+
+        if let Background::Image(path) = &background {
+            if !path.is_file() {
+                return Err(DkcError {src_name: self.name.clone(),
+                                     src_type: "DkcScene".to_string(),
+                                     error_msg: format!("Background image {} does not exist.", path.display())}.into());
+            }
+        }
+
+        *self.background.borrow_mut() = background;
+        if self.app.current_scene.borrow().as_deref() == Some(self.name.as_str()) {
+            self.apply_background();
+        }
+        Ok(())
+
+    }
+
+    /* The canvas background is shared by every scene, the program one sets it */
+    fn apply_background(self: &Self) {
+
+        let (name, color, location) = match &*self.background.borrow() {
+            Background::Checker => ("checker", None, None),
+            Background::Black => ("black", None, None),
+            Background::White => ("white", None, None),
+            Background::Transparent => ("transparent", None, None),
+            Background::Color(color) => ("color", Some(*color), None),
+            Background::Image(path) => ("image", None, Some(path.to_string_lossy().into_owned())),
+        };
+
+        if let Some(color) = color {
+            self.app.gst_scene.set_property("background-color", color);
+        }
+        self.app.gst_scene.set_property("background-location", location);
+        self.app.gst_scene.set_property("background", name);
+
+    }
+
+    pub fn background(self: &Self) -> Background {
+        self.background.borrow().clone()
+    }
+
+    /// Scene items, in the order they were added.
+    pub fn sources(self: &Self) -> Vec<Rc<WrappedSource>> {
+        self.wrapped_sources.borrow().clone()
+    }
+
+    /// Scene item of the given id, see `WrappedSource::id`.
+    pub fn source_by_id(self: &Self, id: usize) -> Option<Rc<WrappedSource>> {
+        self.wrapped_sources.borrow().iter().find(|wrapped| wrapped.id == id).cloned()
+    }
+
+    /// Stop every running animation of the scene items.
+    pub fn cancel_animations(self: &Self) {
+
+        for wrapped_source in self.wrapped_sources.borrow().iter() {
+            wrapped_source.cancel_animations();
+        }
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn set_up() {
+
+        use std::sync::Once;
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+        });
+
+    }
+
+    fn _tear_down() {
+        unimplemented!();
+    }
+
+    /* Canvas outputs go to fake sinks and the pipeline is put in PLAYING, without running
+     * the bus loop. Returns the program audio output pad */
+    fn play(app: &App) -> gst::Pad {
+
+        let mut pads = Vec::new();
+        for template in ["video_src_%u", "audio_src_%u"].iter() {
+            let sink = gst::ElementFactory::make("fakesink", None).expect("Could not make fake sink.");
+            sink.set_property("sync", true);
+            app.app.gst_bin.add(&sink).expect("Could not add fake sink to bin.");
+            let pad = app.app.gst_scene.request_pad_simple(template).expect("Could not request output pad.");
+            pad.link(&sink.static_pad("sink").unwrap()).expect("Could not link fake sink.");
+            pads.push(pad);
+        }
+
+        app.app.gst_bin.set_state(gst::State::Playing).expect("Could not play pipeline.");
+        let (result, _, _) = app.app.gst_bin.state(gst::ClockTime::from_seconds(5));
+        result.expect("Pipeline did not start playing.");
+
+        pads.pop().unwrap()
+
+    }
+
+    /* Buffers going through a pad from now on */
+    fn count_buffers(pad: &gst::Pad) -> Arc<AtomicUsize> {
+
+        let buffers = Arc::new(AtomicUsize::new(0));
+        let counter = buffers.clone();
+        pad.add_probe(gst::PadProbeType::BUFFER, move |_, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            gst::PadProbeReturn::Ok
+        });
+
+        buffers
+
+    }
+
+    #[test]
+    fn test_make_app() {
+
+        set_up();
+
+        assert!(
+            match make_app(Some("test"), 1280, 720) {
+                Ok(_el) => true,
+                Err(_err) => false
+            }
+        );
+
+    }
+
+    #[test]
+    fn test_canvas() {
+
+        set_up();
+
+        let app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        assert_eq!(app.app.gst_scene.property::<i32>("width"), 1280);
+        assert_eq!(app.app.gst_scene.property::<i32>("height"), 720);
+
+        app.set_resolution(1920, 1080).expect("Could not change resolution.");
+        app.set_framerate(60, 1).expect("Could not change framerate.");
+        app.set_pixel_format(Some("I420")).expect("Could not change pixel format.");
+
+        assert_eq!((app.width(), app.height()), (1920, 1080));
+        assert_eq!(app.app.gst_scene.property::<i32>("width"), 1920);
+        assert_eq!(app.app.gst_scene.property::<i32>("height"), 1080);
+        assert_eq!(app.app.gst_scene.property::<gst::Fraction>("framerate"), gst::Fraction::new(60, 1));
+
+        assert!(app.set_resolution(0, 1080).is_err());
+        assert!(app.set_framerate(0, 1).is_err());
+        assert!(app.set_pixel_format(Some("NOPE")).is_err());
+
+    }
+
+    #[test]
+    fn test_make_source() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        assert_eq!(app.app.sources.borrow_mut().len(), 0);
+
+        let source = app.make_source("dummy", None);
+
+        assert!(
+            match &source {
+                Ok(_el) => true,
+                Err(_err) => false
+            }
+        );
+
+        let gst_src = &source.unwrap().element; 
+
+        assert!(match gst_src.static_pad("video_src").unwrap().peer() {
+            Some(peer_pad) => peer_pad.name().eq("video_sink_0"),
+            None => false
+        });
+
+        assert!(match gst_src.static_pad("audio_src").unwrap().peer() {
+            Some(peer_pad) => peer_pad.name().eq("audio_sink_0"),
+            None => false
+        });
+
+        assert_eq!(app.app.sources.borrow_mut().len(), 1);
+
+        assert!(
+            match app.make_source("IdoNotExist", None) {
+                Ok(_el) => false,
+                Err(_err) => true
+            }
+        );
+
+    }
+
+    #[test]
+    fn test_make_sink() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        assert_eq!(app.app.sinks.borrow_mut().len(), 0);
+
+        let sink = app.make_sink("dummy", None);
+
+        assert!(
+            match &sink {
+                Ok(_el) => true,
+                Err(_err) => false
+            }
+        );
+
+        let gst_sink = &sink.unwrap().element;
+
+        assert!(match gst_sink.static_pad("video_sink").unwrap().peer() {
+            Some(peer_pad) => peer_pad.name().eq("video_src_0"),
+            None => false
+        });
+
+        assert!(match gst_sink.static_pad("audio_sink").unwrap().peer() {
+            Some(peer_pad) => peer_pad.name().eq("audio_src_0"),
+            None => false
+        });
+
+        assert_eq!(app.app.sinks.borrow_mut().len(), 1);
+
+        assert!(
+            match app.make_sink("IdoNotExist", None) {
+                Ok(_el) => false,
+                Err(_err) => true
+            }
+        );
+
+    }
+
+    #[test]
+    fn test_sink_output() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1920, 1080).expect("Could not make app.");
+        let sink = app.make_sink("dummy", None).expect("Could not make sink.");
+
+        sink.set_output_resolution(Some((1280, 720))).expect("Could not set output resolution.");
+        sink.set_output_framerate(Some((30, 1))).expect("Could not set output framerate.");
+        sink.set_scaling_filter("bicubic").expect("Could not set scaling filter.");
+        assert!(sink.set_scaling_filter("blurry").is_err());
+
+        assert_eq!(sink.output_params().get("width"), Some(&ParamValue::Int(1280)));
+        assert_eq!(sink.output_params().get("framerate"), Some(&ParamValue::from("30/1")));
+
+        // The canvas is left untouched.
+        assert_eq!(app.app.gst_scene.property::<i32>("width"), 1920);
+
+        let collection = app.collection();
+        assert_eq!(collection.sinks[0].output, sink.output_params());
+
+    }
+
+    #[test]
+    fn test_ducking() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let mic = app.make_source("dummy", Some("mic")).expect("Could not make source.");
+        let music = app.make_source("dummy", Some("music")).expect("Could not make source.");
+        let _wrapped_mic = scene.add_source(mic.clone()).expect("Could not add source.");
+        let wrapped_music = scene.add_source(music).expect("Could not add source.");
+
+        wrapped_music.set_ducking(Some(&mic), &Ducking { threshold: -40.0, ..Ducking::default() })
+            .expect("Could not duck source.");
+        assert_eq!(wrapped_music.query("duck-source").unwrap(), ParamValue::from("mic"));
+        assert_eq!(wrapped_music.query("duck-threshold").unwrap(), ParamValue::Double(-40.0));
+        assert_eq!(wrapped_music.params().get("duck-source"), Some(&ParamValue::from("mic")));
+
+        assert!(wrapped_music.set_ducking(Some(&mic), &Ducking { ratio: 0.5, ..Ducking::default() }).is_err());
+        assert!(wrapped_music.update("duck-source", ParamValue::from("nobody")).is_err());
+
+        // Ducking sources are saved by name.
+        let loaded = App::from_collection(&app.collection(), Some("loaded")).expect("Could not rebuild app.");
+        assert_eq!(loaded.collection(), app.collection());
+
+        wrapped_music.set_ducking(None, &Ducking::default()).expect("Could not stop ducking.");
+        assert_eq!(wrapped_music.query("duck-source").unwrap(), ParamValue::from(""));
+
+    }
+
+    #[test]
+    fn test_audio_tracks() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let wrapped = scene.add_source(source).expect("Could not add source.");
+        let sink = app.make_sink("dummy", None).expect("Could not make sink.");
+
+        assert!(wrapped.set_tracks(&[1]).is_err());
+        app.set_audio_tracks(3).expect("Could not set audio tracks.");
+        assert!(app.set_audio_tracks(7).is_err());
+        assert_eq!(app.audio_tracks(), 3);
+
+        wrapped.set_tracks(&[1, 2]).expect("Could not assign tracks.");
+        assert_eq!(wrapped.query("tracks").unwrap(), ParamValue::from("1,2"));
+        sink.set_audio_track(2).expect("Could not record track.");
+        assert!(sink.set_audio_track(3).is_err());
+
+        let collection = app.collection();
+        assert_eq!(collection.canvas.audio_tracks, 3);
+        assert_eq!(collection.sinks[0].output.get("track"), Some(&ParamValue::UInt(2)));
+
+    }
+
+    #[test]
+    fn test_make_scene() {
+
+        set_up();
+
+        let app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        let scene = app.make_scene(Some("dummyscene"));
+
+        assert!(
+            match &scene {
+                Ok(_el) => true,
+                Err(_err) => false
+            }
+        );
+
+    }
+
+    #[test]
+    fn test_collection_round_trip() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        let source = app.make_source("dummy", Some("mysource")).expect("Could not make source.");
+        app.make_sink("dummy", Some("mysink")).expect("Could not make sink.");
+        let scene = app.make_scene(Some("myscene")).expect("Could not make scene.");
+        let wrapped_source = scene.add_source(source).expect("Could not add source to scene.");
+
+        wrapped_source.update("xpos", ParamValue::Int(100)).expect("Could not update xpos.");
+        wrapped_source.update("volume", ParamValue::Double(0.5)).expect("Could not update volume.");
+        assert!(wrapped_source.update("what", ParamValue::Int(1)).is_err());
+
+        assert_eq!(app.collection().canvas, CanvasDesc { width: 1280, height: 720, ..CanvasDesc::default() });
+        app.set_pixel_format(Some("I420")).expect("Could not change pixel format.");
+
+        let collection = app.collection();
+        assert_eq!(collection.canvas, CanvasDesc { width: 1280, height: 720, format: Some("I420".to_string()),
+                                                   ..CanvasDesc::default() });
+        assert_eq!(collection.sources.len(), 1);
+        assert_eq!(collection.scenes[0].items[0].params.len(), 2);
+
+        let json = collection.to_json().expect("Could not serialize collection.");
+        let parsed = SceneCollection::from_json(&json).expect("Could not parse collection.");
+        assert_eq!(parsed, collection);
+
+        let loaded = App::from_collection(&parsed, Some("loaded")).expect("Could not rebuild app.");
+        assert_eq!(loaded.collection(), collection);
+
+        // The program scene and the item visibility are kept too, the program scene items
+        // driving the inputs.
+        let other_scene = app.make_scene(Some("otherscene")).expect("Could not make scene.");
+        other_scene.add_source(app.source("mysource").unwrap()).expect("Could not add source to scene.")
+            .update("xpos", ParamValue::Int(200)).expect("Could not update xpos.");
+        wrapped_source.set_visible(false);
+        app.set_current_scene("myscene").expect("Could not set current scene.");
+
+        let collection = app.collection();
+        assert_eq!(collection.current_scene.as_deref(), Some("myscene"));
+        assert!(!collection.scenes[0].items[0].visible);
+
+        let loaded = App::from_collection(&collection, Some("reloaded")).expect("Could not rebuild app.");
+        assert_eq!(loaded.collection(), collection);
+        let loaded_source = loaded.source("mysource").unwrap();
+        assert_eq!(loaded_source.input_params().get("xpos"), Some(&ParamValue::Int(100)));
+        assert_eq!(loaded_source.input_params().get("alpha"), Some(&ParamValue::Double(0.0)));
+
+    }
+
+    #[test]
+    fn test_scene_background() {
+
+        set_up();
+
+        let app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let scene = app.make_scene(Some("myscene")).expect("Could not make scene.");
+        let other_scene = app.make_scene(Some("otherscene")).expect("Could not make scene.");
+        app.set_current_scene("myscene").expect("Could not set current scene.");
+
+        assert_eq!(scene.background(), Background::Black);
+        scene.set_background(Background::Color(0xff336699)).expect("Could not set background color.");
+        assert_eq!(app.app.gst_scene.property::<String>("background"), "color");
+        assert_eq!(app.app.gst_scene.property::<u32>("background-color"), 0xff336699);
+        assert!(scene.set_background(Background::Image(Path::new("/nope.png").to_path_buf())).is_err());
+        assert_eq!(scene.background(), Background::Color(0xff336699));
+
+        // Scenes off program keep their own background for when they are switched to.
+        other_scene.set_background(Background::White).expect("Could not set background.");
+        assert_eq!(app.app.gst_scene.property::<String>("background"), "color");
+        app.set_current_scene("otherscene").expect("Could not set current scene.");
+        assert_eq!(app.app.gst_scene.property::<String>("background"), "white");
+        app.set_current_scene("myscene").expect("Could not set current scene.");
+        assert_eq!(app.app.gst_scene.property::<String>("background"), "color");
+        assert_eq!(other_scene.background(), Background::White);
+
+        let collection = app.collection();
+        assert_eq!(collection.scenes[0].background, Background::Color(0xff336699));
+        let loaded = App::from_collection(&collection, Some("loaded")).expect("Could not rebuild app.");
+        assert_eq!(loaded.scene("myscene").unwrap().background(), Background::Color(0xff336699));
+        assert_eq!(loaded.scene("otherscene").unwrap().background(), Background::White);
+
+    }
+
+    #[test]
+    fn test_program_scene() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let source = app.make_source("dummy", Some("mysource")).expect("Could not make source.");
+        let other_source = app.make_source("dummy", Some("othersource")).expect("Could not make source.");
+        let scene = app.make_scene(Some("myscene")).expect("Could not make scene.");
+        let wrapped_source = scene.add_source(source.clone()).expect("Could not add source to scene.");
+        wrapped_source.update("alpha", ParamValue::Double(0.5)).expect("Could not update alpha.");
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let events_ref = events.clone();
+        app.subscribe_events(move |event| events_ref.borrow_mut().push(event.clone()));
+
+        // Sources out of the program scene are hidden, the others get their parameters back.
+        assert!(app.current_scene().is_none());
+        assert!(app.set_current_scene("nope").is_err());
+        app.set_current_scene("myscene").expect("Could not set current scene.");
+        assert_eq!(app.current_scene().unwrap().name(), "myscene");
+        assert_eq!(source.input_params().get("alpha"), Some(&ParamValue::Double(0.5)));
+        assert_eq!(other_source.input_params().get("alpha"), Some(&ParamValue::Double(0.0)));
+        assert_eq!(other_source.input_params().get("mute"), Some(&ParamValue::Bool(true)));
+
+        assert_eq!(wrapped_source.id(), 1);
+        assert!(Rc::ptr_eq(&scene.source_by_id(1).unwrap(), &wrapped_source));
+        assert!(scene.source_by_id(0).is_none());
+
+        wrapped_source.set_visible(false);
+        assert_eq!(source.input_params().get("alpha"), Some(&ParamValue::Double(0.0)));
+        wrapped_source.set_visible(true);
+        source.set_muted(true);
+        assert!(source.muted());
+        assert_eq!(source.input_params().get("mute"), Some(&ParamValue::Bool(true)));
+
+        assert_eq!(*events.borrow(), vec![
+            AppEvent::CurrentSceneChanged("myscene".to_string()),
+            AppEvent::SourceVisibilityChanged { scene: "myscene".to_string(), item: 1,
+                                                source: "mysource".to_string(), visible: false },
+            AppEvent::SourceVisibilityChanged { scene: "myscene".to_string(), item: 1,
+                                                source: "mysource".to_string(), visible: true },
+            AppEvent::SourceMuteChanged { source: "mysource".to_string(), muted: true },
+        ]);
+
+        assert!(app.stop_recording().is_err());
+        assert!(app.stop_streaming().is_err());
+
+    }
+
+    #[test]
+    fn test_stop_recording() {
+
+        set_up();
+
+        let app = make_app(Some("test"), 320, 240).expect("Could not make app.");
+        let pad = play(&app);
+        let path = Path::new("/tmp/dkc-record-test.mkv");
+        let _ = std::fs::remove_file(path);
+
+        app.start_recording(path).expect("Could not start recording.");
+        std::thread::sleep(Duration::from_millis(500));
+        app.stop_recording().expect("Could not stop recording.");
+        assert!(app.recording().is_none());
+        assert!(std::fs::metadata(path).expect("Recording was not written.").len() > 0);
+
+        // The rest of the program keeps flowing once the sink is drained and removed.
+        let buffers = count_buffers(&pad);
+        std::thread::sleep(Duration::from_millis(500));
+        assert!(buffers.load(Ordering::SeqCst) > 0);
+
+        app.app.gst_bin.set_state(gst::State::Null).expect("Could not stop pipeline.");
+        let _ = std::fs::remove_file(path);
+
+    }
+
+    #[test]
+    fn test_program_scene_items() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let source = app.make_source("dummy", Some("mysource")).expect("Could not make source.");
+        let scene = app.make_scene(Some("myscene")).expect("Could not make scene.");
+        let other_scene = app.make_scene(Some("otherscene")).expect("Could not make scene.");
+        let wrapped = scene.add_source(source.clone()).expect("Could not add source to scene.");
+        let other_wrapped = other_scene.add_source(source.clone()).expect("Could not add source to scene.");
+        let default_width = source.defaults.borrow().get("width").cloned();
+
+        wrapped.update("xpos", ParamValue::Int(100)).expect("Could not update xpos.");
+        app.set_current_scene("myscene").expect("Could not set current scene.");
+
+        // Items off program only keep their parameters.
+        other_wrapped.update("xpos", ParamValue::Int(50)).expect("Could not update xpos.");
+        other_wrapped.update("width", ParamValue::Int(320)).expect("Could not update width.");
+        assert_eq!(other_wrapped.params().get("xpos"), Some(&ParamValue::Int(50)));
+        assert_eq!(source.input_params().get("xpos"), Some(&ParamValue::Int(100)));
+        assert!(other_wrapped.update("xpos", ParamValue::Double(1.0)).is_err());
+
+        // Hidden items stay hidden.
+        wrapped.set_visible(false);
+        wrapped.update("alpha", ParamValue::Double(1.0)).expect("Could not update alpha.");
+        assert_eq!(source.input_params().get("alpha"), Some(&ParamValue::Double(0.0)));
+        wrapped.set_visible(true);
+        assert_eq!(source.input_params().get("alpha"), Some(&ParamValue::Double(1.0)));
+
+        // Nothing is updated when a parameter is invalid.
+        let mut params = BTreeMap::new();
+        params.insert("ypos".to_string(), ParamValue::Int(10));
+        params.insert("rotation".to_string(), ParamValue::UInt(30));
+        assert!(wrapped.update_many(&params).is_err());
+        assert!(!wrapped.params().contains_key("ypos"));
+
+        // Switching scenes resets what the new one does not set.
+        app.set_current_scene("otherscene").expect("Could not set current scene.");
+        assert_eq!(source.input_params().get("xpos"), Some(&ParamValue::Int(50)));
+        assert_eq!(source.input_params().get("width"), Some(&ParamValue::Int(320)));
+        app.set_current_scene("myscene").expect("Could not set current scene.");
+        assert_eq!(source.input_params().get("xpos"), Some(&ParamValue::Int(100)));
+        assert_eq!(source.input_params().get("width"), default_width.as_ref());
+
+    }
+
+    #[test]
+    fn test_collection_errors() {
+
+        set_up();
+
+        let json = r#"{"version": 1, "canvas": {"width": 1280, "height": 720},
+                       "sources": [{"name": "mysource", "type": "IdoNotExist"}]}"#;
+        let collection = SceneCollection::from_json(json).expect("Could not parse collection.");
+
+        match App::from_collection(&collection, None) {
+            Ok(_app) => panic!("Unknown source type should not load."),
+            Err(err) => assert!(format!("{}", err).contains("Unknown source type 'IdoNotExist'")),
+        }
+
+        let json = r#"{"version": 9999, "canvas": {"width": 1280, "height": 720}}"#;
+        assert!(SceneCollection::from_json(json).is_err());
+
+    }
+
+    #[test]
+    fn test_replay_buffer() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        // There is no replay buffer to save from yet.
+        assert!(app.save_replay(Path::new("/tmp/dkc-replay-test.mkv")).is_err());
+
+        let sink = app.make_replay_buffer(10, None).expect("Could not make replay buffer.");
+        assert_eq!(sink.element.property::<u32>("duration"), 10);
+
+        // Nothing was recorded yet.
+        assert!(app.save_replay(Path::new("/tmp/dkc-replay-test.mkv")).is_err());
+
+    }
+
+    #[test]
+    fn test_snapshot_not_playing() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let source = app.make_source("dummy", None).expect("Could not make source.");
+
+        // No frame went through the pipeline yet.
+        assert!(source.snapshot().is_err());
+        assert!(app.snapshot().is_err());
+
+        // The snapshot branch is gone with the snapshot.
+        assert!(app.app.gst_scene.src_pads().is_empty());
+
+    }
+
+    #[test]
+    fn test_snapshot_while_playing() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 320, 240).expect("Could not make app.");
+        let source = app.make_source("dummy", None).expect("Could not make source.");
+        play(&app);
+
+        let snapshot = app.snapshot().expect("Could not take a snapshot of the canvas.");
+        assert_eq!((snapshot.width(), snapshot.height()), (320, 240));
+        assert_eq!(snapshot.rgba().len(), 320 * 240 * 4);
+        assert!(source.snapshot().is_ok());
+
+        // Snapshots leave no output behind, the two canvas outputs being the test ones.
+        assert_eq!(app.app.gst_scene.src_pads().len(), 2);
+
+        app.app.gst_bin.set_state(gst::State::Null).expect("Could not stop pipeline.");
+
+    }
+
+    #[test]
+    fn test_scene_add_source() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", None).expect("Could not make source.");
+
+        assert!(
+            match &scene.add_source(source) {
+                Ok(_wrpd) => true,
+                Err(_err) => false
+            }
+        );
+
+    }
+
+    #[test]
+    fn test_query_input_params() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let wrapped = scene.add_source(source.clone()).expect("Could not add source.");
+
+        wrapped.update("xpos", ParamValue::Int(42)).expect("Could not update xpos.");
+        wrapped.update("volume", ParamValue::Double(0.5)).expect("Could not update volume.");
+
+        assert_eq!(wrapped.query("xpos").unwrap(), ParamValue::Int(42));
+        assert_eq!(wrapped.query("volume").unwrap(), ParamValue::Double(0.5));
+        assert!(wrapped.query("what").is_err());
+
+        let params = source.input_params();
+        assert_eq!(params.get("xpos"), Some(&ParamValue::Int(42)));
+        assert_eq!(params.get("mute"), Some(&ParamValue::Bool(false)));
+
+        let app_params = app.input_params();
+        assert_eq!(app_params.get("dummy0"), Some(&params));
+
+    }
+
+    #[test]
+    fn test_update_many_input_params() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let wrapped = scene.add_source(source).expect("Could not add source.");
+
+        let mut params = BTreeMap::new();
+        params.insert("xpos".to_string(), ParamValue::Int(10));
+        params.insert("width".to_string(), ParamValue::Int(640));
+        params.insert("volume".to_string(), ParamValue::Double(0.5));
+        wrapped.update_many(&params).expect("Could not update input parameters.");
+
+        assert_eq!(wrapped.query("width").unwrap(), ParamValue::Int(640));
+        assert_eq!(wrapped.query("volume").unwrap(), ParamValue::Double(0.5));
+        assert_eq!(wrapped.params(), params);
+
+        params.insert("alpha".to_string(), ParamValue::Int(1));
+        assert!(wrapped.update_many(&params).is_err());
+        assert!(!wrapped.params().contains_key("alpha"));
+
+        wrapped.set_scale_mode("fill").expect("Could not set scale mode.");
+        wrapped.set_rotation(270).expect("Could not rotate source.");
+        wrapped.set_flip("vertical").expect("Could not flip source.");
+        assert!(wrapped.set_rotation(30).is_err());
+        assert_eq!(wrapped.query("scale-mode").unwrap(), ParamValue::from("fill"));
+
+        wrapped.set_blend_mode("additive").expect("Could not set blend mode.");
+        assert!(wrapped.set_blend_mode("overlay").is_err());
+        assert_eq!(wrapped.query("blend-mode").unwrap(), ParamValue::from("additive"));
+
+        wrapped.set_audio_offset(Duration::from_millis(120)).expect("Could not delay audio.");
+        assert!(wrapped.set_video_offset(Duration::from_secs(60)).is_err());
+        assert_eq!(wrapped.query("audio-offset").unwrap(), ParamValue::UInt(120));
+        assert_eq!(wrapped.query("video-offset").unwrap(), ParamValue::UInt(0));
+
+        wrapped.set_monitor("monitor-only").expect("Could not monitor source.");
+        assert!(wrapped.set_monitor("cue").is_err());
+        assert_eq!(wrapped.query("monitor").unwrap(), ParamValue::from("monitor-only"));
+
+        wrapped.set_crop(10, 0, 20, 0).expect("Could not crop source.");
+        assert_eq!(wrapped.query("crop-left").unwrap(), ParamValue::Int(20));
+        assert_eq!(wrapped.params().get("crop-top"), Some(&ParamValue::Int(10)));
+
+        // Negative crops are refused, leaving the previous ones.
+        assert!(wrapped.set_crop(-10, 0, 0, 0).is_err());
+        assert_eq!(wrapped.params().get("crop-top"), Some(&ParamValue::Int(10)));
+
+    }
+
+    #[test]
+    fn test_crop_bounds() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let wrapped = scene.add_source(source.clone()).expect("Could not add source.");
+
+        play(&app);
+        let caps = source.element.static_pad("video_src").unwrap().current_caps().expect("Source not negotiated.");
+        let width = caps.structure(0).unwrap().get::<i32>("width").unwrap();
+
+        // Crops have to leave some of the frame once its size is known.
+        assert!(wrapped.set_crop(0, 0, width / 2, width / 2).is_err());
+        wrapped.set_crop(0, 0, width / 4, width / 4).expect("Could not crop source.");
+        assert_eq!(wrapped.params().get("crop-right"), Some(&ParamValue::Int(width / 4)));
+
+        app.app.gst_bin.set_state(gst::State::Null).expect("Could not stop pipeline.");
+
+    }
+
+    fn level_message(input: &str, peak: &[f64]) -> gst::Message {
+        let peak = gst::Array::from_owned(peak.iter().map(|value| value.to_send_value()).collect::<Vec<_>>());
+        gst::message::Element::new(gst::Structure::builder("dkc-audio-level")
+                                   .field("input", input)
+                                   .field("peak", peak)
+                                   .build())
+    }
+
+    #[test]
+    fn test_audio_levels() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let _wrapped = scene.add_source(source.clone()).expect("Could not add source.");
+        let audio_input = source.audio_input.borrow().clone().expect("Source has no audio input.");
+
+        let received = Rc::new(RefCell::new(vec![]));
+        let received_clone = received.clone();
+        let id = app.subscribe_levels(move |levels| received_clone.borrow_mut().push(levels.clone()));
+
+        app.app.handle_levels(&level_message(&audio_input, &[-6.0, -12.0]));
+        app.app.handle_levels(&level_message("master", &[-3.0]));
+        app.app.handle_levels(&level_message("audio_sink_42", &[-3.0]));
+
+        assert_eq!(*received.borrow(), vec![
+            AudioLevels { source: Some("dummy0".to_string()), peak: vec![-6.0, -12.0], rms: vec![], decay: vec![] },
+            AudioLevels { source: None, peak: vec![-3.0], rms: vec![], decay: vec![] },
+        ]);
+
+        assert!(app.unsubscribe_levels(id));
+        assert!(!app.unsubscribe_levels(id));
+        app.app.handle_levels(&level_message("master", &[-3.0]));
+        assert_eq!(received.borrow().len(), 2);
+
+        app.set_level_interval(Duration::from_millis(50));
+        assert_eq!(app.app.gst_scene.property::<u64>("level-interval"), 50_000_000);
+
+    }
+
+    #[test]
+    fn test_monitoring() {
+
+        set_up();
+
+        let app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        assert!(!app.monitoring());
+        app.set_monitoring(true).expect("Could not enable monitoring.");
+        app.set_monitoring(true).expect("Could not enable monitoring twice.");
+        assert!(app.monitoring());
+        assert!(app.app.gst_scene.static_pad("monitor_src_0").is_some());
+
+        app.set_monitoring(false).expect("Could not disable monitoring.");
+        assert!(!app.monitoring());
+        assert!(app.app.gst_scene.static_pad("monitor_src_0").is_none());
+
+    }
+
+    #[test]
+    fn test_monitoring_while_playing() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 320, 240).expect("Could not make app.");
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let wrapped = scene.add_source(source).expect("Could not add source.");
+        wrapped.set_monitor("monitor-and-output").expect("Could not monitor source.");
+
+        let program = play(&app);
+        app.set_monitoring(true).expect("Could not enable monitoring.");
+
+        // The monitor branch has to drain the input queues, which hold a second of audio.
+        std::thread::sleep(Duration::from_secs(2));
+        let buffers = count_buffers(&program);
+        std::thread::sleep(Duration::from_millis(500));
+        assert!(buffers.load(Ordering::SeqCst) > 0);
+
+        app.set_monitoring(false).expect("Could not disable monitoring.");
+        app.app.gst_bin.set_state(gst::State::Null).expect("Could not stop pipeline.");
+
+    }
+
+    #[test]
+    fn test_animate_input_params() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let wrapped = scene.add_source(source).expect("Could not add source.");
+
+        wrapped.animate("xpos", ParamValue::Int(100), Duration::from_secs(1), Easing::EaseOut)
+            .expect("Could not animate xpos.");
+        wrapped.animate("volume", ParamValue::Double(0.0), Duration::from_secs(1), Easing::Linear)
+            .expect("Could not animate volume.");
+        assert!(wrapped.animate("mute", ParamValue::Bool(true), Duration::from_secs(1), Easing::Linear).is_err());
+        assert_eq!(wrapped.params().get("xpos"), Some(&ParamValue::Int(100)));
+
+        assert!(wrapped.cancel_animation("xpos"));
+        assert!(!wrapped.cancel_animation("xpos"));
+
+        // Cancelled animations leave the value they reached (none while stopped).
+        scene.cancel_animations();
+        assert_eq!(wrapped.params().get("volume"), Some(&ParamValue::Double(1.0)));
+        assert!(!wrapped.cancel_animation("volume"));
+        assert!(!wrapped.cancel_animations());
+
+    }
+
+    #[test]
+    fn test_filters() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let wrapped = scene.add_source(source).expect("Could not add source.");
+
+        wrapped.add_filter("balance", "color-balance", None).expect("Could not add filter.");
+        wrapped.add_filter("gamma", "gamma", Some(0)).expect("Could not add filter.");
+        assert!(wrapped.add_filter("what", "what", None).is_err());
+
+        wrapped.update_filter("balance", "brightness", ParamValue::Double(0.2)).expect("Could not update filter.");
+        assert!(wrapped.update_filter("balance", "what", ParamValue::Double(0.2)).is_err());
+
+        wrapped.move_filter("gamma", 1).expect("Could not move filter.");
+        assert_eq!(wrapped.filters().iter().map(|filter| filter.name.as_str()).collect::<Vec<_>>(),
+                   vec!["balance", "gamma"]);
+        assert_eq!(wrapped.filters()[0].params.get("brightness"), Some(&ParamValue::Double(0.2)));
+
+        let collection = app.collection();
+        assert_eq!(collection.scenes[0].items[0].filters, wrapped.filters());
+
+        let loaded = App::from_collection(&collection, Some("loaded")).expect("Could not load collection.");
+        assert_eq!(loaded.collection().scenes, collection.scenes);
+
+        wrapped.remove_filter("gamma").expect("Could not remove filter.");
+        assert!(wrapped.remove_filter("gamma").is_err());
+        assert_eq!(wrapped.filters().len(), 1);
+
+    }
+
+    /* Filter elements linked after the queue of a scene input */
+    fn linked_filters(app: &App, input: &str) -> Vec<String> {
+
+        let mut element = app.app.gst_scene.static_pad(input).unwrap()
+            .downcast::<gst::GhostPad>().unwrap().target().unwrap().parent_element().unwrap();
+        let mut filters = Vec::new();
+
+        loop {
+            element = element.static_pad("src").unwrap().peer().unwrap().parent_element().unwrap();
+            match element.downcast_ref::<gst::Bin>() {
+                Some(bin) => filters.push(bin.iterate_elements().into_iter()
+                                          .map(|element| element.unwrap().factory().unwrap().name().to_string())
+                                          .find(|name| name != "videoconvert" && name != "audioconvert").unwrap()),
+                None => return filters
+            }
+        }
+
+    }
+
+    #[test]
+    fn test_scene_filters() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let scene = app.make_scene(Some("scene")).expect("Could not make scene.");
+        let other_scene = app.make_scene(Some("otherscene")).expect("Could not make scene.");
+        let wrapped = scene.add_source(source.clone()).expect("Could not add source.");
+        let other_wrapped = other_scene.add_source(source.clone()).expect("Could not add source.");
+        let input = source.video_input.borrow().clone().unwrap();
+
+        // Items of a source in different scenes have their own filters, even with the same names.
+        wrapped.add_filter("look", "gamma", None).expect("Could not add filter.");
+        other_wrapped.add_filter("look", "lut", None).expect("Could not add filter.");
+        other_wrapped.add_filter("blur", "blur", None).expect("Could not add filter.");
+        assert!(linked_filters(&app, &input).is_empty());
+
+        app.set_current_scene("scene").expect("Could not set current scene.");
+        assert_eq!(linked_filters(&app, &input), vec!["gamma"]);
+        app.set_current_scene("otherscene").expect("Could not set current scene.");
+        assert_eq!(linked_filters(&app, &input), vec!["dkclut", "gaussianblur"]);
+
+        // Off program items are edited without touching the program.
+        wrapped.remove_filter("look").expect("Could not remove filter.");
+        assert_eq!(linked_filters(&app, &input), vec!["dkclut", "gaussianblur"]);
+
+        let loaded = App::from_collection(&app.collection(), Some("loaded")).expect("Could not load collection.");
+        assert_eq!(loaded.collection().scenes, app.collection().scenes);
+
+    }
+
+    #[test]
+    fn test_audio_filters() {
+
+        set_up();
+
+        let mut app = make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        let scene = app.make_scene(Some("dummyscene")).expect("Could not make scene.");
+        let source = app.make_source("dummy", Some("dummy0")).expect("Could not make source.");
+        let wrapped = scene.add_source(source).expect("Could not add source.");
+
+        wrapped.add_audio_filter("gate", "noise-gate", None).expect("Could not add audio filter.");
+        wrapped.add_audio_filter("eq", "equalizer", None).expect("Could not add audio filter.");
+        wrapped.add_audio_filter("gain", "gain", Some(0)).expect("Could not add audio filter.");
+        assert!(wrapped.add_audio_filter("gamma", "gamma", None).is_err());
+        assert!(wrapped.add_filter("gain", "gain", None).is_err());
+
+        wrapped.update_audio_filter("gain", "volume", ParamValue::Double(2.0)).expect("Could not update audio filter.");
+        wrapped.update_audio_filter("eq", "band1::gain", ParamValue::Double(-3.0)).expect("Could not update audio filter.");
+        assert!(wrapped.update_audio_filter("eq", "band42::gain", ParamValue::Double(-3.0)).is_err());
+
+        wrapped.move_audio_filter("gain", 2).expect("Could not move audio filter.");
+        assert_eq!(wrapped.audio_filters().iter().map(|filter| filter.name.as_str()).collect::<Vec<_>>(),
+                   vec!["gate", "eq", "gain"]);
+        assert!(wrapped.filters().is_empty());
+
+        let collection = app.collection();
+        assert_eq!(collection.scenes[0].items[0].audio_filters, wrapped.audio_filters());
+
+        let loaded = App::from_collection(&collection, Some("loaded")).expect("Could not load collection.");
+        assert_eq!(loaded.collection().scenes, collection.scenes);
+
+        wrapped.remove_audio_filter("eq").expect("Could not remove audio filter.");
+        assert_eq!(wrapped.audio_filters().len(), 2);
 
     }
 
@@ -0,0 +1,477 @@
+//! Import of OBS Studio scene collections (the JSON files found in `obs-studio/basic/scenes/`).
+//!
+//! OBS source kinds are mapped to DankCaster source types as follows:
+//! image → `image`, media → `media`, color → `color`, text → `text`,
+//! screen capture → `screen` and audio input capture → `audio`.
+//! Anything else is reported in `ObsImport::issues` instead of being converted.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use failure::Error;
+use gst::glib;
+use serde_json::Value;
+
+use super::{DkcError, ParamValue, SceneCollection, CanvasDesc, SourceDesc, SceneDesc, SceneItemDesc, Background,
+            COLLECTION_VERSION};
+
+/// Part of the OBS collection that could not be converted, or only partially.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportIssue {
+    pub item: String,
+    pub reason: String,
+}
+
+pub struct ObsImport {
+    pub collection: SceneCollection,
+    pub issues: Vec<ImportIssue>,
+}
+
+struct SourceKind {
+    source_type: &'static str,
+    has_video: bool,
+    has_audio: bool,
+}
+
+fn source_kind(obs_id: &str) -> Option<SourceKind> {
+    let (source_type, has_video, has_audio) = match obs_id {
+        "image_source" => ("image", true, false),
+        "ffmpeg_source" => ("media", true, true),
+        "color_source" | "color_source_v2" | "color_source_v3" => ("color", true, false),
+        "text_ft2_source" | "text_ft2_source_v2" |
+        "text_gdiplus" | "text_gdiplus_v2" | "text_gdiplus_v3" => ("text", true, false),
+        "xshm_input" | "xshm_input_v2" | "pipewire-desktop-capture-source" |
+        "monitor_capture" | "display_capture" | "screen_capture" => ("screen", true, false),
+        "pulse_input_capture" | "alsa_input_capture" |
+        "wasapi_input_capture" | "coreaudio_input_capture" => ("audio", false, true),
+        _ => return None,
+    };
+
+    Some(SourceKind { source_type, has_video, has_audio })
+}
+
+/* OBS stores colors as 0xAABBGGRR, DankCaster as 0xAARRGGBB */
+fn convert_color(obs_color: u64) -> u32 {
+    let color = obs_color as u32;
+    (color & 0xff00ff00) | ((color & 0xff) << 16) | ((color >> 16) & 0xff)
+}
+
+fn convert_settings(kind: &SourceKind, settings: &Value) -> BTreeMap<String, ParamValue> {
+
+    let mut properties = BTreeMap::new();
+
+    match kind.source_type {
+        "image" => {
+            if let Some(file) = settings["file"].as_str() {
+                properties.insert("location".to_string(), ParamValue::from(file));
+            }
+        },
+        "media" => {
+            let uri = if settings["is_local_file"].as_bool().unwrap_or(true) {
+                settings["local_file"].as_str()
+                    .and_then(|file| glib::filename_to_uri(file, None).ok())
+                    .map(|uri| uri.to_string())
+            } else {
+                settings["input"].as_str().map(|input| input.to_string())
+            };
+
+            if let Some(uri) = uri {
+                properties.insert("uri".to_string(), ParamValue::from(uri));
+            }
+        },
+        "color" => {
+            if let Some(color) = settings["color"].as_u64() {
+                properties.insert("color".to_string(), ParamValue::UInt(convert_color(color)));
+            }
+            for dimension in ["width", "height"] {
+                if let Some(value) = settings[dimension].as_i64() {
+                    properties.insert(dimension.to_string(), ParamValue::Int(value as i32));
+                }
+            }
+        },
+        "text" => {
+            if let Some(text) = settings["text"].as_str() {
+                properties.insert("text".to_string(), ParamValue::from(text));
+            }
+            if let (Some(face), Some(size)) = (settings["font"]["face"].as_str(), settings["font"]["size"].as_i64()) {
+                properties.insert("font-desc".to_string(), ParamValue::from(format!("{} {}", face, size)));
+            }
+            if let Some(color) = settings["color1"].as_u64().or_else(|| settings["color"].as_u64()) {
+                properties.insert("color".to_string(), ParamValue::UInt(convert_color(color)));
+            }
+        },
+        "screen" => {
+            if let Some(show_pointer) = settings["show_cursor"].as_bool().or_else(|| settings["capture_cursor"].as_bool()) {
+                properties.insert("show-pointer".to_string(), ParamValue::Bool(show_pointer));
+            }
+        },
+        "audio" => {
+            match settings["device_id"].as_str() {
+                Some("default") | None => (),
+                Some(device) => { properties.insert("device".to_string(), ParamValue::from(device)); },
+            }
+        },
+        _ => (),
+    }
+
+    properties
+
+}
+
+/* OBS alignment flags, items being centered on an axis when neither of its sides is set */
+const ALIGN_LEFT: i64 = 1;
+const ALIGN_RIGHT: i64 = 2;
+const ALIGN_TOP: i64 = 4;
+const ALIGN_BOTTOM: i64 = 8;
+const ALIGN_TOP_LEFT: i64 = ALIGN_TOP | ALIGN_LEFT;
+
+/* Share of the item size between its top left corner and the point OBS positions */
+fn align_offset(align: i64) -> (f64, f64) {
+    let offset = |start: i64, end: i64| if align & start != 0 { 0.0 } else if align & end != 0 { 1.0 } else { 0.5 };
+    (offset(ALIGN_LEFT, ALIGN_RIGHT), offset(ALIGN_TOP, ALIGN_BOTTOM))
+}
+
+/* Native size of the source, when the collection tells it */
+fn native_size(source: &SourceDesc) -> Option<(f64, f64)> {
+    match (source.properties.get("width"), source.properties.get("height")) {
+        (Some(ParamValue::Int(width)), Some(ParamValue::Int(height))) => Some((*width as f64, *height as f64)),
+        _ => None,
+    }
+}
+
+fn convert_item(scene_name: &str, zorder: u32, item: &Value, source: &SourceDesc, kind: &SourceKind,
+                obs_source: &Value, issues: &mut Vec<ImportIssue>) -> SceneItemDesc {
+
+    let item_name = format!("{} / {}", scene_name, source.name);
+    let mut params = BTreeMap::new();
+
+    if kind.has_video {
+        params.insert("zorder".to_string(), ParamValue::UInt(zorder));
+
+        /* OBS flips sources with negative scales */
+        let scale = (item["scale"]["x"].as_f64().unwrap_or(1.0), item["scale"]["y"].as_f64().unwrap_or(1.0));
+        let flip = match (scale.0 < 0.0, scale.1 < 0.0) {
+            (true, false) => Some("horizontal"),
+            (false, true) => Some("vertical"),
+            _ => None, // Flipping both ways is a half turn
+        };
+        let half_turn = if scale.0 < 0.0 && scale.1 < 0.0 { 180.0 } else { 0.0 };
+        let scale = (scale.0.abs(), scale.1.abs());
+
+        let rot = (item["rot"].as_f64().unwrap_or(0.0) + half_turn).rem_euclid(360.0);
+        let rotation = if rot % 90.0 == 0.0 {
+            rot as u32
+        } else {
+            issues.push(ImportIssue { item: item_name.clone(),
+                                      reason: "Only right angle rotations are supported.".to_string() });
+            0
+        };
+
+        let crop = |side: &str| item[format!("crop_{}", side).as_str()].as_i64().unwrap_or(0).max(0) as i32;
+        let (crop_left, crop_top, crop_right, crop_bottom) = (crop("left"), crop("top"), crop("right"), crop("bottom"));
+        let bounds_type = item["bounds_type"].as_i64().unwrap_or(0);
+
+        let size = if bounds_type != 0 {
+            let scale_mode = match bounds_type {
+                1 => "stretch",
+                2 => "fit",
+                3 => {
+                    issues.push(ImportIssue { item: item_name.clone(),
+                                              reason: "Outer scaling is cropped to the bounding box.".to_string() });
+                    "fill"
+                },
+                _ => {
+                    issues.push(ImportIssue { item: item_name.clone(),
+                                              reason: "Bounding box type is not supported, fitted to bounds instead.".to_string() });
+                    "fit"
+                },
+            };
+            params.insert("scale-mode".to_string(), ParamValue::from(scale_mode));
+
+            /* Sources keeping their aspect ratio are centered in the bounding box */
+            if bounds_type != 1 && item["bounds_align"].as_i64().unwrap_or(0) != 0 {
+                issues.push(ImportIssue { item: item_name.clone(),
+                                          reason: "Alignment in the bounding box is not supported, centered instead.".to_string() });
+            }
+            Some((item["bounds"]["x"].as_f64().unwrap_or(0.0), item["bounds"]["y"].as_f64().unwrap_or(0.0)))
+        } else if scale != (1.0, 1.0) {
+            match native_size(source) {
+                Some((width, height)) => {
+                    let (width, height) = (width - (crop_left + crop_right) as f64, height - (crop_top + crop_bottom) as f64);
+                    let (width, height) = if rotation % 180 == 90 { (height, width) } else { (width, height) };
+                    Some((width * scale.0, height * scale.1))
+                },
+                None => {
+                    issues.push(ImportIssue { item: item_name.clone(),
+                                              reason: "Scale could not be converted, native size is unknown.".to_string() });
+                    None
+                },
+            }
+        } else {
+            None
+        };
+
+        if let Some((width, height)) = size {
+            params.insert("width".to_string(), ParamValue::Int(width.round() as i32));
+            params.insert("height".to_string(), ParamValue::Int(height.round() as i32));
+        }
+
+        /* OBS positions the aligned point of the item, DankCaster its top left corner */
+        let align = item["align"].as_i64().unwrap_or(ALIGN_TOP_LEFT);
+        let displayed_size = size.or_else(|| native_size(source).map(|(width, height)| {
+            (width - (crop_left + crop_right) as f64, height - (crop_top + crop_bottom) as f64)
+        }));
+        let (mut xpos, mut ypos) = (item["pos"]["x"].as_f64().unwrap_or(0.0), item["pos"]["y"].as_f64().unwrap_or(0.0));
+        if align != ALIGN_TOP_LEFT {
+            match displayed_size {
+                Some((width, height)) if rotation == 0 => {
+                    let (x_offset, y_offset) = align_offset(align);
+                    xpos -= width * x_offset;
+                    ypos -= height * y_offset;
+                },
+                _ => issues.push(ImportIssue { item: item_name.clone(),
+                                               reason: "Alignment could not be converted, placed from the top left corner.".to_string() }),
+            }
+        }
+        params.insert("xpos".to_string(), ParamValue::Int(xpos.round() as i32));
+        params.insert("ypos".to_string(), ParamValue::Int(ypos.round() as i32));
+
+        for (param, crop) in [("crop-left", crop_left), ("crop-top", crop_top),
+                              ("crop-right", crop_right), ("crop-bottom", crop_bottom)].iter() {
+            if *crop != 0 {
+                params.insert(param.to_string(), ParamValue::Int(*crop));
+            }
+        }
+
+        if rotation != 0 {
+            params.insert("rotation".to_string(), ParamValue::UInt(rotation));
+        }
+
+        if let Some(flip) = flip {
+            params.insert("flip".to_string(), ParamValue::from(flip));
+        }
+
+        match item["blend_type"].as_str().unwrap_or("normal") {
+            "normal" => (),
+            blend_mode @ ("additive" | "multiply" | "screen" | "subtract") =>
+                { params.insert("blend-mode".to_string(), ParamValue::from(blend_mode)); },
+            blend_mode => issues.push(ImportIssue { item: item_name.clone(),
+                                                    reason: format!("Blend mode '{}' is not supported.", blend_mode) }),
+        }
+    }
+
+    if kind.has_audio {
+        let volume = obs_source["volume"].as_f64().unwrap_or(1.0);
+        if volume != 1.0 {
+            params.insert("volume".to_string(), ParamValue::Double(volume));
+        }
+
+        if obs_source["muted"].as_bool().unwrap_or(false) {
+            params.insert("mute".to_string(), ParamValue::Bool(true));
+        }
+
+        /* OBS stores the audio sync offset in nanoseconds, audio can only be delayed here */
+        match obs_source["sync"].as_i64().unwrap_or(0) / 1_000_000 {
+            0 => (),
+            offset @ 1..=10_000 => { params.insert("audio-offset".to_string(), ParamValue::UInt(offset as u32)); },
+            offset => issues.push(ImportIssue { item: item_name.clone(),
+                                                reason: format!("Audio sync offset of {} ms is not supported.", offset) }),
+        }
+    }
+
+    SceneItemDesc { source: source.name.clone(), visible: item["visible"].as_bool().unwrap_or(true),
+                    params, filters: Vec::new(), audio_filters: Vec::new() }
+
+}
+
+/// Convert an OBS scene collection, OBS does not store the canvas size along with it.
+pub fn import(json: &str, width: u16, height: u16) -> Result<ObsImport, Error> {
+
+    let obs: Value = serde_json::from_str(json)?;
+
+    let obs_sources = match obs["sources"].as_array() {
+        Some(obs_sources) => obs_sources,
+        None => return Err(DkcError {src_name: obs["name"].as_str().unwrap_or("collection").to_string(),
+                                     src_type: "ObsCollection".to_string(),
+                                     error_msg: "No sources found in OBS scene collection.".to_string()}.into()),
+    };
+
+    let mut issues = vec![];
+    let mut sources = vec![];
+    let mut converted: HashMap<String, (usize, SourceKind, &Value)> = HashMap::new();
+    let mut obs_scenes = vec![];
+
+    for obs_source in obs_sources.iter() {
+        let name = obs_source["name"].as_str().unwrap_or_default().to_string();
+        let id = obs_source["id"].as_str().unwrap_or_default();
+
+        if id == "scene" {
+            obs_scenes.push(obs_source);
+            continue;
+        }
+
+        match source_kind(id) {
+            Some(kind) => {
+                if obs_source["filters"].as_array().map_or(false, |filters| !filters.is_empty()) {
+                    issues.push(ImportIssue { item: name.clone(), reason: "Source filters are not imported.".to_string() });
+                }
+
+                let properties = convert_settings(&kind, &obs_source["settings"]);
+                sources.push(SourceDesc { name: name.clone(), source_type: kind.source_type.to_string(), properties });
+                converted.insert(name, (sources.len() - 1, kind, obs_source));
+            },
+            None => issues.push(ImportIssue { item: name, reason: format!("Source kind '{}' is not supported.", id) }),
+        }
+    }
+
+    /* Follow the scene order of the OBS user interface when there is one */
+    if let Some(scene_order) = obs["scene_order"].as_array() {
+        let position = |scene: &Value| scene_order.iter()
+            .position(|ordered| ordered["name"] == scene["name"])
+            .unwrap_or(scene_order.len());
+        obs_scenes.sort_by_key(|scene| position(scene));
+    }
+
+    let scene_names: Vec<&str> = obs_scenes.iter().filter_map(|scene| scene["name"].as_str()).collect();
+
+    let mut scenes = vec![];
+    for obs_scene in obs_scenes.iter() {
+        let scene_name = obs_scene["name"].as_str().unwrap_or_default().to_string();
+        let mut items = vec![];
+
+        /* OBS lists scene items from bottom to top */
+        for (zorder, item) in obs_scene["settings"]["items"].as_array().into_iter().flatten().enumerate() {
+            let item_source = item["name"].as_str().unwrap_or_default();
+
+            match converted.get(item_source) {
+                Some((index, kind, obs_source)) =>
+                    items.push(convert_item(&scene_name, zorder as u32, item, &sources[*index], kind,
+                                            obs_source, &mut issues)),
+                None => {
+                    let reason = if scene_names.contains(&item_source) {
+                        "Nested scenes are not supported.".to_string()
+                    } else {
+                        format!("Source '{}' could not be converted.", item_source)
+                    };
+                    issues.push(ImportIssue { item: format!("{} / {}", scene_name, item_source), reason });
+                },
+            }
+        }
+
+        scenes.push(SceneDesc { name: scene_name, items, background: Background::default() });
+    }
+
+    let current_scene = obs["current_scene"].as_str()
+        .filter(|name| scene_names.contains(name))
+        .map(|name| name.to_string());
+
+    let collection = SceneCollection { version: COLLECTION_VERSION,
+                                       canvas: CanvasDesc { width, height, ..CanvasDesc::default() },
+                                       current_scene, sources, scenes, sinks: vec![] };
+
+    Ok(ObsImport { collection, issues })
+
+}
+
+pub fn import_file(path: &Path, width: u16, height: u16) -> Result<ObsImport, Error> {
+    import(&std::fs::read_to_string(path)?, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const OBS_COLLECTION: &str = r#"{
+        "name": "Untitled",
+        "current_scene": "Scene",
+        "scene_order": [{"name": "Scene"}, {"name": "Other"}],
+        "sources": [
+            {"id": "scene", "name": "Other", "settings": {"items": [{"name": "Scene"}]}},
+            {"id": "color_source_v3", "name": "Background",
+             "settings": {"color": 4278190335, "width": 1920, "height": 1080}},
+            {"id": "pulse_input_capture", "name": "Mic", "volume": 0.5, "muted": true, "sync": 150000000,
+             "settings": {"device_id": "default"}},
+            {"id": "color_source_v3", "name": "Logo",
+             "settings": {"color": 4294967295, "width": 200, "height": 100}},
+            {"id": "browser_source", "name": "Browser", "settings": {}},
+            {"id": "scene", "name": "Scene", "settings": {"items": [
+                {"name": "Background", "visible": true, "pos": {"x": 10.0, "y": 20.0},
+                 "scale": {"x": -0.5, "y": 0.5}, "rot": 90.0, "bounds_type": 0,
+                 "crop_left": 20, "crop_right": 20, "crop_top": 0, "crop_bottom": 0, "blend_type": "screen"},
+                {"name": "Mic", "visible": false},
+                {"name": "Logo", "visible": true, "pos": {"x": 1900.0, "y": 1060.0}, "align": 10},
+                {"name": "Logo", "visible": true, "pos": {"x": 960.0, "y": 540.0}, "align": 0, "rot": 90.0},
+                {"name": "Browser", "visible": true}
+            ]}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_color_conversion() {
+        assert_eq!(convert_color(0xff0000ff), 0xffff0000);
+        assert_eq!(convert_color(0x80ff8000), 0x800080ff);
+    }
+
+    #[test]
+    fn test_import() {
+
+        let import = import(OBS_COLLECTION, 1920, 1080).expect("Could not import OBS collection.");
+        let collection = import.collection;
+
+        assert_eq!(collection.canvas, CanvasDesc { width: 1920, height: 1080, ..CanvasDesc::default() });
+
+        assert_eq!(collection.sources.len(), 3);
+        assert_eq!(collection.sources[0].source_type, "color");
+        assert_eq!(collection.sources[0].properties["color"], ParamValue::UInt(0xffff0000));
+        assert_eq!(collection.sources[1].source_type, "audio");
+        assert!(collection.sources[1].properties.is_empty());
+
+        assert_eq!(collection.scenes.iter().map(|scene| scene.name.as_str()).collect::<Vec<_>>(),
+                   vec!["Scene", "Other"]);
+        assert_eq!(collection.current_scene.as_deref(), Some("Scene"));
+
+        let items = &collection.scenes[0].items;
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[0].params["xpos"], ParamValue::Int(10));
+        assert_eq!(items[0].params["width"], ParamValue::Int(540));
+        assert_eq!(items[0].params["height"], ParamValue::Int(940));
+        assert_eq!(items[0].params["crop-left"], ParamValue::Int(20));
+        assert!(!items[0].params.contains_key("crop-top"));
+        assert_eq!(items[0].params["rotation"], ParamValue::UInt(90));
+        assert_eq!(items[0].params["flip"], ParamValue::from("horizontal"));
+        assert_eq!(items[0].params["blend-mode"], ParamValue::from("screen"));
+        assert_eq!(items[1].params["volume"], ParamValue::Double(0.5));
+        assert_eq!(items[1].params["mute"], ParamValue::Bool(true));
+        assert_eq!(items[1].params["audio-offset"], ParamValue::UInt(150));
+
+        // Hidden items keep their parameters, their visibility is imported on its own.
+        assert!(items[0].visible);
+        assert!(!items[1].visible);
+        assert!(!items[1].params.contains_key("alpha"));
+
+        // Items aligned to their bottom right corner are placed from their top left one.
+        assert_eq!(items[2].params["xpos"], ParamValue::Int(1700));
+        assert_eq!(items[2].params["ypos"], ParamValue::Int(960));
+        assert_eq!(items[3].params["xpos"], ParamValue::Int(960));
+
+        assert_eq!(import.issues, vec![
+            ImportIssue { item: "Browser".to_string(),
+                          reason: "Source kind 'browser_source' is not supported.".to_string() },
+            ImportIssue { item: "Scene / Logo".to_string(),
+                          reason: "Alignment could not be converted, placed from the top left corner.".to_string() },
+            ImportIssue { item: "Scene / Browser".to_string(),
+                          reason: "Source 'Browser' could not be converted.".to_string() },
+            ImportIssue { item: "Other / Scene".to_string(),
+                          reason: "Nested scenes are not supported.".to_string() },
+        ]);
+
+    }
+
+    #[test]
+    fn test_import_invalid() {
+        assert!(import("{}", 1920, 1080).is_err());
+        assert!(import("not json", 1920, 1080).is_err());
+    }
+
+}
@@ -0,0 +1,146 @@
+use gst::glib;
+use gst::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+/// Typed value of a source/sink property or of a scene input parameter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum ParamValue {
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    Int64(i64),
+    UInt64(u64),
+    Double(f64),
+    String(String),
+}
+
+impl ParamValue {
+    pub fn to_variant(&self) -> glib::Variant {
+        match self {
+            ParamValue::Bool(value) => value.to_variant(),
+            ParamValue::Int(value) => value.to_variant(),
+            ParamValue::UInt(value) => value.to_variant(),
+            ParamValue::Int64(value) => value.to_variant(),
+            ParamValue::UInt64(value) => value.to_variant(),
+            ParamValue::Double(value) => value.to_variant(),
+            ParamValue::String(value) => value.to_variant(),
+        }
+    }
+
+    pub fn to_value(&self) -> glib::Value {
+        match self {
+            ParamValue::Bool(value) => value.to_value(),
+            ParamValue::Int(value) => value.to_value(),
+            ParamValue::UInt(value) => value.to_value(),
+            ParamValue::Int64(value) => value.to_value(),
+            ParamValue::UInt64(value) => value.to_value(),
+            ParamValue::Double(value) => value.to_value(),
+            ParamValue::String(value) => value.to_value(),
+        }
+    }
+
+    pub fn to_send_value(&self) -> glib::SendValue {
+        match self {
+            ParamValue::Bool(value) => value.to_send_value(),
+            ParamValue::Int(value) => value.to_send_value(),
+            ParamValue::UInt(value) => value.to_send_value(),
+            ParamValue::Int64(value) => value.to_send_value(),
+            ParamValue::UInt64(value) => value.to_send_value(),
+            ParamValue::Double(value) => value.to_send_value(),
+            ParamValue::String(value) => value.to_send_value(),
+        }
+    }
+
+    pub fn from_variant(variant: &glib::Variant) -> Option<ParamValue> {
+        match variant.type_().as_str() {
+            "b" => variant.get::<bool>().map(ParamValue::Bool),
+            "i" => variant.get::<i32>().map(ParamValue::Int),
+            "u" => variant.get::<u32>().map(ParamValue::UInt),
+            "x" => variant.get::<i64>().map(ParamValue::Int64),
+            "t" => variant.get::<u64>().map(ParamValue::UInt64),
+            "d" => variant.get::<f64>().map(ParamValue::Double),
+            "s" => variant.get::<String>().map(ParamValue::String),
+            _ => None,
+        }
+    }
+
+    pub fn from_value(value: &glib::Value) -> Option<ParamValue> {
+        if let Ok(value) = value.get::<bool>() {
+            Some(ParamValue::Bool(value))
+        } else if let Ok(value) = value.get::<i32>() {
+            Some(ParamValue::Int(value))
+        } else if let Ok(value) = value.get::<u32>() {
+            Some(ParamValue::UInt(value))
+        } else if let Ok(value) = value.get::<i64>() {
+            Some(ParamValue::Int64(value))
+        } else if let Ok(value) = value.get::<u64>() {
+            Some(ParamValue::UInt64(value))
+        } else if let Ok(value) = value.get::<f64>() {
+            Some(ParamValue::Double(value))
+        } else if let Ok(value) = value.get::<String>() {
+            Some(ParamValue::String(value))
+        } else {
+            None
+        }
+    }
+}
+
+impl From<bool> for ParamValue {
+    fn from(value: bool) -> Self { ParamValue::Bool(value) }
+}
+
+impl From<i32> for ParamValue {
+    fn from(value: i32) -> Self { ParamValue::Int(value) }
+}
+
+impl From<u32> for ParamValue {
+    fn from(value: u32) -> Self { ParamValue::UInt(value) }
+}
+
+impl From<i64> for ParamValue {
+    fn from(value: i64) -> Self { ParamValue::Int64(value) }
+}
+
+impl From<u64> for ParamValue {
+    fn from(value: u64) -> Self { ParamValue::UInt64(value) }
+}
+
+impl From<f64> for ParamValue {
+    fn from(value: f64) -> Self { ParamValue::Double(value) }
+}
+
+impl From<&str> for ParamValue {
+    fn from(value: &str) -> Self { ParamValue::String(value.to_string()) }
+}
+
+impl From<String> for ParamValue {
+    fn from(value: String) -> Self { ParamValue::String(value) }
+}
+
+/// Set an element property from a `ParamValue`, converting it to the property type when possible.
+pub(crate) fn set_element_property(element: &gst::Element, name: &str, value: &ParamValue)
+                                   -> Result<(), String> {
+
+    let pspec = match element.find_property(name) {
+        Some(pspec) => pspec,
+        None => return Err(format!("Unknown property '{}'.", name)),
+    };
+
+    if !pspec.flags().contains(glib::ParamFlags::WRITABLE) {
+        return Err(format!("Property '{}' is not writable.", name));
+    }
+
+    match value {
+        /* Enums, flags, caps... are set from their string representation */
+        ParamValue::String(string) if pspec.value_type() != String::static_type() =>
+            element.set_property_from_str(name, string),
+        _ => match value.to_value().transform_with_type(pspec.value_type()) {
+            Ok(value) => element.set_property_from_value(name, &value),
+            Err(_) => return Err(format!("Invalid value type for property '{}'.", name)),
+        },
+    }
+
+    Ok(())
+}
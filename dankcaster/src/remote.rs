@@ -0,0 +1,735 @@
+//! Remote control server speaking a subset of the obs-websocket 5 protocol, so that stream decks
+//! and web dashboards made for OBS Studio can drive the switcher.
+//!
+//! Supported requests are listed in `REQUESTS`. Scene items are identified by their id in the
+//! scene (see `WrappedSource::id`), and inputs by source name. Events are sent for program
+//! scene switches (`CurrentProgramSceneChanged`), scene item visibility
+//! (`SceneItemEnableStateChanged`), source mutes (`InputMuteStateChanged`) and outputs
+//! (`StreamStateChanged`, `RecordStateChanged`).
+//!
+//! Sockets are served from their own threads, while requests are handled on the thread owning
+//! the `App`, from its thread default glib main context: it has to be running or iterated, as
+//! `App::turn_on` does.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use failure::Error;
+use gst::glib;
+use rand::Rng;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use tungstenite::Message;
+
+use super::{App, AppEvent, DkcError};
+
+const OBS_WEBSOCKET_VERSION: &str = "5.0.0";
+const RPC_VERSION: u64 = 1;
+const DEFAULT_PORT: u16 = 4455;
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/* Message op codes */
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_REIDENTIFY: u64 = 3;
+const OP_EVENT: u64 = 5;
+const OP_REQUEST: u64 = 6;
+const OP_REQUEST_RESPONSE: u64 = 7;
+
+/* Event subscription bits of the categories events are sent for */
+const EVENT_SCENES: u64 = 1 << 2;
+const EVENT_INPUTS: u64 = 1 << 3;
+const EVENT_OUTPUTS: u64 = 1 << 6;
+const EVENT_SCENE_ITEMS: u64 = 1 << 7;
+const EVENT_ALL: u64 = 0x7ff;
+
+/* Codes the server closes the socket with */
+const CLOSE_MESSAGE_DECODE_ERROR: u16 = 4002;
+const CLOSE_MISSING_DATA_FIELD: u16 = 4003;
+const CLOSE_UNKNOWN_OP_CODE: u16 = 4006;
+const CLOSE_NOT_IDENTIFIED: u16 = 4007;
+const CLOSE_ALREADY_IDENTIFIED: u16 = 4008;
+const CLOSE_AUTHENTICATION_FAILED: u16 = 4009;
+const CLOSE_UNSUPPORTED_RPC_VERSION: u16 = 4010;
+
+/* Request status codes */
+const STATUS_SUCCESS: u64 = 100;
+const STATUS_UNKNOWN_REQUEST_TYPE: u64 = 204;
+const STATUS_MISSING_REQUEST_FIELD: u64 = 300;
+const STATUS_INVALID_REQUEST_FIELD: u64 = 400;
+const STATUS_INVALID_REQUEST_FIELD_TYPE: u64 = 401;
+const STATUS_OUTPUT_RUNNING: u64 = 500;
+const STATUS_OUTPUT_NOT_RUNNING: u64 = 501;
+const STATUS_RESOURCE_NOT_FOUND: u64 = 600;
+const STATUS_INVALID_RESOURCE_STATE: u64 = 604;
+const STATUS_REQUEST_PROCESSING_FAILED: u64 = 702;
+
+/// Request types the server answers to.
+pub const REQUESTS: &[&str] = &[
+    "GetVersion",
+    "GetSceneList", "GetCurrentProgramScene", "SetCurrentProgramScene",
+    "GetInputList", "GetInputMute", "SetInputMute", "ToggleInputMute",
+    "GetSceneItemList", "GetSceneItemEnabled", "SetSceneItemEnabled",
+    "GetStreamStatus", "StartStream", "StopStream", "ToggleStream", "SetStreamServiceSettings",
+    "GetRecordStatus", "StartRecord", "StopRecord", "ToggleRecord",
+];
+
+/// Settings of the remote control server.
+#[derive(Debug, Clone)]
+pub struct RemoteConfig {
+    pub address: SocketAddr,
+    /// Clients have to authenticate with it when set.
+    pub password: Option<String>,
+    /// RTMP URL `StartStream` streams to, also set by `SetStreamServiceSettings`.
+    pub stream_url: Option<String>,
+    /// Directory `StartRecord` records to.
+    pub record_directory: PathBuf,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            address: SocketAddr::from(([127, 0, 0, 1], DEFAULT_PORT)),
+            password: None,
+            stream_url: None,
+            record_directory: PathBuf::from("."),
+        }
+    }
+}
+
+/// Running remote control server, stopped when dropped.
+pub struct RemoteServer {
+    app: App,
+    context: glib::MainContext,
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+    source_id: Option<glib::SourceId>,
+    subscription: usize,
+    listener: Option<thread::JoinHandle<()>>,
+}
+
+/* What the socket threads report to the app thread */
+enum ClientMessage {
+    Connected(usize, mpsc::Sender<Message>),
+    Text(usize, String),
+    Disconnected(usize),
+}
+
+struct Session {
+    outgoing: mpsc::Sender<Message>,
+    challenge: String,
+    identified: bool,
+    subscriptions: u64,
+}
+
+struct Server {
+    app: App,
+    config: RefCell<RemoteConfig>,
+    salt: String,
+    sessions: RefCell<BTreeMap<usize, Session>>,
+}
+
+type RequestResult = Result<Value, (u64, String)>;
+
+fn random_string() -> String {
+    base64::encode(rand::thread_rng().gen::<[u8; 32]>())
+}
+
+fn sha256_base64(data: &str) -> String {
+    base64::encode(Sha256::digest(data.as_bytes()))
+}
+
+/// Authentication string of the Identify message, from the password and the Hello message
+/// salt and challenge.
+pub fn authentication(password: &str, salt: &str, challenge: &str) -> String {
+    sha256_base64(&(sha256_base64(&(password.to_string() + salt)) + challenge))
+}
+
+fn field<'a>(data: &'a Value, name: &str) -> Result<&'a Value, (u64, String)> {
+    data.get(name).filter(|value| !value.is_null())
+        .ok_or_else(|| (STATUS_MISSING_REQUEST_FIELD, format!("Your request is missing the `{}` field.", name)))
+}
+
+fn string_field<'a>(data: &'a Value, name: &str) -> Result<&'a str, (u64, String)> {
+    field(data, name)?.as_str()
+        .ok_or_else(|| (STATUS_INVALID_REQUEST_FIELD_TYPE, format!("The field value of `{}` must be a string.", name)))
+}
+
+fn bool_field(data: &Value, name: &str) -> Result<bool, (u64, String)> {
+    field(data, name)?.as_bool()
+        .ok_or_else(|| (STATUS_INVALID_REQUEST_FIELD_TYPE, format!("The field value of `{}` must be a boolean.", name)))
+}
+
+fn u64_field(data: &Value, name: &str) -> Result<u64, (u64, String)> {
+    field(data, name)?.as_u64()
+        .ok_or_else(|| (STATUS_INVALID_REQUEST_FIELD_TYPE, format!("The field value of `{}` must be a number.", name)))
+}
+
+fn processing_failed(error: Error) -> (u64, String) {
+    (STATUS_REQUEST_PROCESSING_FAILED, error.to_string())
+}
+
+/* Serve one client socket, until it or the server closes */
+fn serve(stream: TcpStream, client: usize, sender: glib::Sender<ClientMessage>, stop: Arc<AtomicBool>) {
+
+    let _ = stream.set_nonblocking(false);
+    let mut socket = match tungstenite::accept(stream) {
+        Ok(socket) => socket,
+        Err(_) => return,
+    };
+    let _ = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL));
+
+    let (outgoing, incoming) = mpsc::channel();
+    if sender.send(ClientMessage::Connected(client, outgoing)).is_err() {
+        return;
+    }
+
+    while !stop.load(Ordering::Relaxed) {
+        /* Replies and events queued from the app thread */
+        let mut closing = false;
+        while let Ok(message) = incoming.try_recv() {
+            closing |= message.is_close();
+            if socket.write_message(message).is_err() {
+                closing = true;
+            }
+        }
+        if closing {
+            break;
+        }
+
+        match socket.read_message() {
+            Ok(Message::Text(text)) => if sender.send(ClientMessage::Text(client, text)).is_err() { break },
+            Ok(Message::Close(_)) => break,
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(err)) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+            Err(_) => break,
+        }
+    }
+
+    let _ = sender.send(ClientMessage::Disconnected(client));
+
+}
+
+impl RemoteServer {
+    /// Listen for remote control clients of `app`.
+    pub fn start(app: &App, config: RemoteConfig) -> Result<RemoteServer, Error> {
+
+        let listener = TcpListener::bind(config.address)
+            .map_err(|err| DkcError {src_name: config.address.to_string(),
+                                     src_type: "RemoteServer".to_string(),
+                                     error_msg: format!("Could not listen: {}.", err)})?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+
+        let server = Rc::new(Server { app: App { app: app.app.clone() },
+                                      config: RefCell::new(config),
+                                      salt: random_string(),
+                                      sessions: RefCell::new(BTreeMap::new()) });
+
+        /* The app holds its subscribers, which must not keep the server alive */
+        let weak_server: Weak<Server> = Rc::downgrade(&server);
+        let subscription = app.subscribe_events(move |event| {
+            if let Some(server) = weak_server.upgrade() {
+                server.event(event);
+            }
+        });
+
+        let context = glib::MainContext::ref_thread_default();
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        let source_id = receiver.attach(Some(&context), move |message| {
+            server.handle(message);
+            glib::Continue(true)
+        });
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let listener_stop = stop.clone();
+        let listener = thread::spawn(move || {
+            let mut next_client = 0;
+            while !listener_stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let (sender, stop) = (sender.clone(), listener_stop.clone());
+                        thread::spawn(move || serve(stream, next_client, sender, stop));
+                        next_client += 1;
+                    },
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Ok(RemoteServer { app: App { app: app.app.clone() }, context, address, stop,
+                          source_id: Some(source_id), subscription, listener: Some(listener) })
+
+    }
+
+    /// Address the server listens on, with the actual port when it was started on port 0.
+    pub fn local_addr(self: &Self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for RemoteServer {
+    fn drop(self: &mut Self) {
+
+        self.stop.store(true, Ordering::Relaxed);
+        self.app.unsubscribe_events(self.subscription);
+        if let Some(source) = self.source_id.take().and_then(|source_id| self.context.find_source_by_id(&source_id)) {
+            source.destroy();
+        }
+        if let Some(listener) = self.listener.take() {
+            let _ = listener.join();
+        }
+
+    }
+}
+
+impl Server {
+    fn handle(self: &Self, message: ClientMessage) {
+
+        match message {
+            ClientMessage::Connected(client, outgoing) => {
+                let session = Session { outgoing, challenge: random_string(), identified: false,
+                                        subscriptions: EVENT_ALL };
+                let hello = self.hello(&session);
+                self.sessions.borrow_mut().insert(client, session);
+                self.send(client, OP_HELLO, hello);
+            },
+            ClientMessage::Text(client, text) => self.receive(client, &text),
+            ClientMessage::Disconnected(client) => {
+                self.sessions.borrow_mut().remove(&client);
+            },
+        }
+
+    }
+
+    fn hello(self: &Self, session: &Session) -> Value {
+
+        let mut hello = json!({"obsWebSocketVersion": OBS_WEBSOCKET_VERSION, "rpcVersion": RPC_VERSION});
+        if self.config.borrow().password.is_some() {
+            hello["authentication"] = json!({"challenge": session.challenge, "salt": self.salt});
+        }
+
+        hello
+
+    }
+
+    fn send(self: &Self, client: usize, op: u64, data: Value) {
+
+        if let Some(session) = self.sessions.borrow().get(&client) {
+            let _ = session.outgoing.send(Message::Text(json!({"op": op, "d": data}).to_string()));
+        }
+
+    }
+
+    fn close(self: &Self, client: usize, code: u16, reason: &str) {
+
+        if let Some(session) = self.sessions.borrow_mut().remove(&client) {
+            let _ = session.outgoing.send(Message::Close(Some(CloseFrame { code: CloseCode::from(code),
+                                                                           reason: reason.to_string().into() })));
+        }
+
+    }
+
+    fn receive(self: &Self, client: usize, text: &str) {
+
+        let message = match serde_json::from_str::<Value>(text) {
+            Ok(message) => message,
+            Err(_) => return self.close(client, CLOSE_MESSAGE_DECODE_ERROR, "Message is not valid JSON."),
+        };
+        let (op, data) = match (message["op"].as_u64(), message.get("d").filter(|data| data.is_object())) {
+            (Some(op), Some(data)) => (op, data),
+            _ => return self.close(client, CLOSE_MISSING_DATA_FIELD, "Message is missing `op` or `d`."),
+        };
+        let identified = match self.sessions.borrow().get(&client) {
+            Some(session) => session.identified,
+            None => return,
+        };
+
+        match op {
+            OP_IDENTIFY if identified => self.close(client, CLOSE_ALREADY_IDENTIFIED, "Client is already identified."),
+            OP_IDENTIFY => self.identify(client, data),
+            _ if !identified => self.close(client, CLOSE_NOT_IDENTIFIED, "Client is not identified yet."),
+            OP_REIDENTIFY => self.identify(client, data),
+            OP_REQUEST => {
+                let (request_type, request_id) = match (data["requestType"].as_str(), data.get("requestId")) {
+                    (Some(request_type), Some(request_id)) => (request_type, request_id.clone()),
+                    _ => return self.close(client, CLOSE_MISSING_DATA_FIELD,
+                                           "Request is missing `requestType` or `requestId`."),
+                };
+                let request_data = data.get("requestData").cloned().unwrap_or_else(|| json!({}));
+
+                let mut response = json!({"requestType": request_type, "requestId": request_id});
+                match self.request(request_type, &request_data) {
+                    Ok(response_data) => {
+                        response["requestStatus"] = json!({"result": true, "code": STATUS_SUCCESS});
+                        response["responseData"] = response_data;
+                    },
+                    Err((code, comment)) =>
+                        response["requestStatus"] = json!({"result": false, "code": code, "comment": comment}),
+                }
+                self.send(client, OP_REQUEST_RESPONSE, response);
+            },
+            _ => self.close(client, CLOSE_UNKNOWN_OP_CODE, &format!("Unknown op code {}.", op)),
+        }
+
+    }
+
+    fn identify(self: &Self, client: usize, data: &Value) {
+
+        if let Some(rpc_version) = data.get("rpcVersion") {
+            if rpc_version.as_u64() != Some(RPC_VERSION) {
+                return self.close(client, CLOSE_UNSUPPORTED_RPC_VERSION,
+                                  &format!("Only RPC version {} is supported.", RPC_VERSION));
+            }
+        }
+
+        let authenticated = {
+            let mut sessions = self.sessions.borrow_mut();
+            let session = match sessions.get_mut(&client) {
+                Some(session) => session,
+                None => return,
+            };
+
+            let authenticated = session.identified || match self.config.borrow().password.as_deref() {
+                Some(password) => data["authentication"].as_str()
+                    == Some(authentication(password, &self.salt, &session.challenge).as_str()),
+                None => true,
+            };
+            if authenticated {
+                session.identified = true;
+                session.subscriptions = data["eventSubscriptions"].as_u64().unwrap_or(session.subscriptions);
+            }
+
+            authenticated
+        };
+
+        if authenticated {
+            self.send(client, OP_IDENTIFIED, json!({"negotiatedRpcVersion": RPC_VERSION}));
+        } else {
+            self.close(client, CLOSE_AUTHENTICATION_FAILED, "Authentication failed.");
+        }
+
+    }
+
+    fn request(self: &Self, request_type: &str, data: &Value) -> RequestResult {
+
+        let app = &self.app;
+        let scene = |data: &Value| {
+            let name = string_field(data, "sceneName")?;
+            app.scene(name).ok_or_else(|| (STATUS_RESOURCE_NOT_FOUND, format!("No scene was found by the name of `{}`.", name)))
+        };
+        let source = |data: &Value| {
+            let name = string_field(data, "inputName")?;
+            app.source(name).ok_or_else(|| (STATUS_RESOURCE_NOT_FOUND, format!("No input was found by the name of `{}`.", name)))
+        };
+        let scene_item = |data: &Value| {
+            let scene = scene(data)?;
+            let id = u64_field(data, "sceneItemId")?;
+            scene.source_by_id(id as usize)
+                .ok_or_else(|| (STATUS_RESOURCE_NOT_FOUND, format!("No scene item was found by the id of `{}`.", id)))
+        };
+        let current_scene_name = || app.current_scene().map(|scene| scene.name().to_string());
+
+        match request_type {
+            "GetVersion" => Ok(json!({"obsVersion": env!("CARGO_PKG_VERSION"),
+                                      "obsWebSocketVersion": OBS_WEBSOCKET_VERSION,
+                                      "rpcVersion": RPC_VERSION,
+                                      "availableRequests": REQUESTS,
+                                      "supportedImageFormats": [],
+                                      "platform": std::env::consts::OS})),
+            "GetSceneList" => Ok(json!({
+                "currentProgramSceneName": current_scene_name(),
+                "currentPreviewSceneName": null,
+                "scenes": app.scenes().iter().enumerate()
+                    .map(|(index, scene)| json!({"sceneName": scene.name(), "sceneIndex": index}))
+                    .collect::<Vec<_>>()})),
+            "GetCurrentProgramScene" => Ok(json!({"currentProgramSceneName": current_scene_name()})),
+            "SetCurrentProgramScene" => {
+                app.set_current_scene(scene(data)?.name()).map_err(processing_failed)?;
+                Ok(json!({}))
+            },
+            "GetInputList" => Ok(json!({
+                "inputs": app.sources().iter()
+                    .map(|source| json!({"inputName": source.name(), "inputKind": source.source_type(),
+                                         "unversionedInputKind": source.source_type()}))
+                    .collect::<Vec<_>>()})),
+            "GetInputMute" => Ok(json!({"inputMuted": source(data)?.muted()})),
+            "SetInputMute" => {
+                let (source, muted) = (source(data)?, bool_field(data, "inputMuted")?);
+                source.set_muted(muted);
+                Ok(json!({}))
+            },
+            "ToggleInputMute" => {
+                let source = source(data)?;
+                source.set_muted(!source.muted());
+                Ok(json!({"inputMuted": source.muted()}))
+            },
+            "GetSceneItemList" => Ok(json!({
+                "sceneItems": scene(data)?.sources().iter().enumerate()
+                    .map(|(index, wrapped)| json!({"sceneItemId": wrapped.id(), "sceneItemIndex": index,
+                                                   "sourceName": wrapped.source().name(),
+                                                   "inputKind": wrapped.source().source_type(),
+                                                   "sceneItemEnabled": wrapped.visible()}))
+                    .collect::<Vec<_>>()})),
+            "GetSceneItemEnabled" => Ok(json!({"sceneItemEnabled": scene_item(data)?.visible()})),
+            "SetSceneItemEnabled" => {
+                let (wrapped, visible) = (scene_item(data)?, bool_field(data, "sceneItemEnabled")?);
+                wrapped.set_visible(visible);
+                Ok(json!({}))
+            },
+            "GetStreamStatus" => Ok(json!({"outputActive": app.streaming().is_some()})),
+            "StartStream" | "StopStream" | "ToggleStream" => {
+                let active = app.streaming().is_some();
+                match (request_type, active) {
+                    ("StartStream", true) => Err((STATUS_OUTPUT_RUNNING, "The stream output is already running.".to_string())),
+                    ("StopStream", false) => Err((STATUS_OUTPUT_NOT_RUNNING, "The stream output is not running.".to_string())),
+                    (_, true) => app.stop_streaming().map_err(processing_failed),
+                    (_, false) => {
+                        let url = self.config.borrow().stream_url.clone()
+                            .ok_or_else(|| (STATUS_INVALID_RESOURCE_STATE,
+                                            "No stream URL is set, see SetStreamServiceSettings.".to_string()))?;
+                        app.start_streaming(&url).map_err(processing_failed)
+                    },
+                }?;
+                Ok(if request_type == "ToggleStream" { json!({"outputActive": !active}) } else { json!({}) })
+            },
+            "SetStreamServiceSettings" => {
+                if string_field(data, "streamServiceType")? != "rtmp_custom" {
+                    return Err((STATUS_INVALID_REQUEST_FIELD, "Only the `rtmp_custom` service type is supported.".to_string()));
+                }
+                let settings = field(data, "streamServiceSettings")?;
+                let server = string_field(settings, "server")?;
+                let url = match settings["key"].as_str() {
+                    Some(key) if !key.is_empty() => format!("{}/{}", server.trim_end_matches('/'), key),
+                    _ => server.to_string(),
+                };
+                self.config.borrow_mut().stream_url = Some(url);
+                Ok(json!({}))
+            },
+            "GetRecordStatus" => Ok(json!({"outputActive": app.recording().is_some()})),
+            "StartRecord" | "StopRecord" | "ToggleRecord" => {
+                let path = app.recording();
+                match (request_type, path) {
+                    ("StartRecord", Some(_)) => Err((STATUS_OUTPUT_RUNNING, "The record output is already running.".to_string())),
+                    ("StopRecord", None) => Err((STATUS_OUTPUT_NOT_RUNNING, "The record output is not running.".to_string())),
+                    (_, Some(path)) => {
+                        app.stop_recording().map_err(processing_failed)?;
+                        Ok(if request_type == "ToggleRecord" { json!({"outputActive": false}) }
+                           else { json!({"outputPath": path}) })
+                    },
+                    (_, None) => {
+                        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+                        let path = self.config.borrow().record_directory.join(format!("dankcaster-{}.mkv", seconds));
+                        app.start_recording(&path).map_err(processing_failed)?;
+                        Ok(if request_type == "ToggleRecord" { json!({"outputActive": true}) } else { json!({}) })
+                    },
+                }
+            },
+            _ => Err((STATUS_UNKNOWN_REQUEST_TYPE, format!("Unknown request type `{}`.", request_type))),
+        }
+
+    }
+
+    fn event(self: &Self, event: &AppEvent) {
+
+        let output_state = |active: bool| if active { "OBS_WEBSOCKET_OUTPUT_STARTED" } else { "OBS_WEBSOCKET_OUTPUT_STOPPED" };
+
+        let (event_type, intent, data) = match event {
+            AppEvent::CurrentSceneChanged(scene) =>
+                ("CurrentProgramSceneChanged", EVENT_SCENES, json!({"sceneName": scene})),
+            AppEvent::SourceVisibilityChanged { scene, item, visible, .. } =>
+                ("SceneItemEnableStateChanged", EVENT_SCENE_ITEMS,
+                 json!({"sceneName": scene, "sceneItemId": item, "sceneItemEnabled": visible})),
+            AppEvent::SourceMuteChanged { source, muted } =>
+                ("InputMuteStateChanged", EVENT_INPUTS, json!({"inputName": source, "inputMuted": muted})),
+            AppEvent::StreamingChanged(active) =>
+                ("StreamStateChanged", EVENT_OUTPUTS, json!({"outputActive": active, "outputState": output_state(*active)})),
+            AppEvent::RecordingChanged(active) =>
+                ("RecordStateChanged", EVENT_OUTPUTS, json!({"outputActive": active, "outputState": output_state(*active),
+                                                             "outputPath": self.app.recording()})),
+        };
+
+        let clients: Vec<usize> = self.sessions.borrow().iter()
+            .filter(|(_, session)| session.identified && session.subscriptions & intent != 0)
+            .map(|(client, _)| *client)
+            .collect();
+        for client in clients {
+            self.send(client, OP_EVENT, json!({"eventType": event_type, "eventIntent": intent, "eventData": data}));
+        }
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use tungstenite::WebSocket;
+
+    type Client = WebSocket<tungstenite::stream::MaybeTlsStream<TcpStream>>;
+
+    fn set_up() {
+
+        use std::sync::Once;
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+        });
+
+    }
+
+    /* Clients give up on answers not coming, failing their test instead of hanging it */
+    fn connect(address: SocketAddr) -> Client {
+        let (client, _) = tungstenite::connect(format!("ws://{}", address)).unwrap();
+        if let tungstenite::stream::MaybeTlsStream::Plain(stream) = client.get_ref() {
+            stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        }
+        client
+    }
+
+    fn receive(client: &mut Client) -> Value {
+        serde_json::from_str(&client.read_message().unwrap().into_text().unwrap()).unwrap()
+    }
+
+    fn request(client: &mut Client, request_type: &str, request_data: Value) {
+        client.write_message(Message::Text(json!({"op": OP_REQUEST, "d": {"requestType": request_type,
+                                                                          "requestId": request_type,
+                                                                          "requestData": request_data}})
+                                           .to_string())).unwrap();
+    }
+
+    /* Serve `app` from a main context of this thread until `client` is done */
+    fn run<F: FnOnce(SocketAddr) + Send + 'static>(app: &App, config: RemoteConfig, client: F) {
+
+        let context = glib::MainContext::new();
+        let _ = context.with_thread_default(|| {
+            let server = RemoteServer::start(app, config).expect("Could not start server.");
+            let address = server.local_addr();
+            let (done_sender, done) = mpsc::channel();
+            let client = thread::spawn(move || {
+                client(address);
+                let _ = done_sender.send(());
+            });
+
+            /* Clients failing early drop their sender too */
+            while let Err(mpsc::RecvTimeoutError::Timeout) = done.recv_timeout(Duration::from_millis(5)) {
+                while context.iteration(false) {}
+            }
+            client.join().expect("Remote client failed.");
+        });
+
+    }
+
+    fn config() -> RemoteConfig {
+        RemoteConfig { address: SocketAddr::from(([127, 0, 0, 1], 0)),
+                       password: Some("secret".to_string()), ..RemoteConfig::default() }
+    }
+
+    #[test]
+    fn test_authentication() {
+        // Salt and challenge from the obs-websocket 5 protocol documentation.
+        assert_eq!(authentication("supersecretpassword", "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=",
+                                  "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY="),
+                   "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4=");
+    }
+
+    #[test]
+    fn test_remote_control() {
+
+        set_up();
+
+        let mut app = super::super::make_app(Some("test"), 1280, 720).expect("Could not make app.");
+        let source = app.make_source("dummy", Some("mysource")).expect("Could not make source.");
+        app.make_scene(Some("myscene")).expect("Could not make scene.")
+            .add_source(source).expect("Could not add source to scene.");
+        app.make_scene(Some("other")).expect("Could not make scene.");
+
+        run(&app, config(), |address| {
+            let mut client = connect(address);
+
+            let hello = receive(&mut client);
+            assert_eq!(hello["op"], OP_HELLO);
+            let auth = authentication("secret", hello["d"]["authentication"]["salt"].as_str().unwrap(),
+                                      hello["d"]["authentication"]["challenge"].as_str().unwrap());
+            client.write_message(Message::Text(json!({"op": OP_IDENTIFY, "d": {"rpcVersion": 1, "authentication": auth}})
+                                               .to_string())).unwrap();
+            assert_eq!(receive(&mut client)["op"], OP_IDENTIFIED);
+
+            // Switching scenes is announced, then answered.
+            request(&mut client, "SetCurrentProgramScene", json!({"sceneName": "other"}));
+            let event = receive(&mut client);
+            assert_eq!(event["d"]["eventType"], "CurrentProgramSceneChanged");
+            assert_eq!(event["d"]["eventData"]["sceneName"], "other");
+            assert_eq!(receive(&mut client)["d"]["requestStatus"]["code"], STATUS_SUCCESS);
+
+            request(&mut client, "GetSceneList", json!({}));
+            let response = receive(&mut client);
+            assert_eq!(response["d"]["responseData"]["currentProgramSceneName"], "other");
+            assert_eq!(response["d"]["responseData"]["scenes"][0]["sceneName"], "myscene");
+
+            request(&mut client, "SetSceneItemEnabled", json!({"sceneName": "myscene", "sceneItemId": 1,
+                                                               "sceneItemEnabled": false}));
+            assert_eq!(receive(&mut client)["d"]["eventType"], "SceneItemEnableStateChanged");
+            assert_eq!(receive(&mut client)["d"]["requestStatus"]["result"], true);
+
+            request(&mut client, "ToggleInputMute", json!({"inputName": "mysource"}));
+            assert_eq!(receive(&mut client)["d"]["eventData"]["inputMuted"], true);
+            assert_eq!(receive(&mut client)["d"]["responseData"]["inputMuted"], true);
+
+            // Failed requests are answered with their status.
+            request(&mut client, "GetInputMute", json!({"inputName": "nope"}));
+            assert_eq!(receive(&mut client)["d"]["requestStatus"]["code"], STATUS_RESOURCE_NOT_FOUND);
+            request(&mut client, "StopStream", json!({}));
+            assert_eq!(receive(&mut client)["d"]["requestStatus"]["code"], STATUS_OUTPUT_NOT_RUNNING);
+            request(&mut client, "StartStream", json!({}));
+            assert_eq!(receive(&mut client)["d"]["requestStatus"]["code"], STATUS_INVALID_RESOURCE_STATE);
+            request(&mut client, "OpenVideoMixWindow", json!({}));
+            assert_eq!(receive(&mut client)["d"]["requestStatus"]["code"], STATUS_UNKNOWN_REQUEST_TYPE);
+        });
+
+        assert_eq!(app.current_scene().unwrap().name(), "other");
+        assert!(app.source("mysource").unwrap().muted());
+        assert!(!app.scene("myscene").unwrap().sources()[0].visible());
+
+    }
+
+    #[test]
+    fn test_remote_authentication_failure() {
+
+        set_up();
+
+        let app = super::super::make_app(Some("test"), 1280, 720).expect("Could not make app.");
+
+        run(&app, config(), |address| {
+            let mut client = connect(address);
+            receive(&mut client);
+
+            // Requests are refused until identified, then wrong passwords are.
+            request(&mut client, "GetVersion", json!({}));
+            assert!(matches!(client.read_message().unwrap(),
+                             Message::Close(Some(frame)) if u16::from(frame.code) == CLOSE_NOT_IDENTIFIED));
+
+            let mut client = connect(address);
+            receive(&mut client);
+            client.write_message(Message::Text(json!({"op": OP_IDENTIFY, "d": {"rpcVersion": 1, "authentication": "nope"}})
+                                               .to_string())).unwrap();
+            assert!(matches!(client.read_message().unwrap(),
+                             Message::Close(Some(frame)) if u16::from(frame.code) == CLOSE_AUTHENTICATION_FAILED));
+        });
+
+    }
+
+}
@@ -0,0 +1,372 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::subclass::prelude::*;
+use gst_video::subclass::prelude::*;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::videomixer::{blend_channel, BlendMode};
+
+const DEFAULT_MODE: &str = "alpha";
+
+/* Channels of the mask image the alpha is taken from, or blend modes of the mask colors over
+ * the frame ones */
+const MODES: &[&str] = &["alpha", "luma", "additive", "multiply", "screen", "subtract"];
+
+fn blend_mode(mode: &str) -> Option<BlendMode> {
+    match mode {
+        "additive" => Some(BlendMode::Additive),
+        "multiply" => Some(BlendMode::Multiply),
+        "screen" => Some(BlendMode::Screen),
+        "subtract" => Some(BlendMode::Subtract),
+        _ => None
+    }
+}
+
+/* Mask image decoded to RGBA, stretched over the whole frame */
+pub(crate) struct Mask {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Mask {
+    pub(crate) fn new(width: usize, height: usize, pixels: Vec<u8>) -> Option<Mask> {
+        if width == 0 || height == 0 || pixels.len() != width * height * 4 {
+            return None;
+        }
+
+        Some(Mask { width, height, pixels })
+    }
+
+    /* Nearest image pixel to the given frame position */
+    pub(crate) fn pixel(&self, x: usize, y: usize, frame_width: usize, frame_height: usize) -> &[u8] {
+        let mask_x = (x * self.width / frame_width).min(self.width - 1);
+        let mask_y = (y * self.height / frame_height).min(self.height - 1);
+        &self.pixels[(mask_y * self.width + mask_x) * 4..][..4]
+    }
+
+    /* Alpha factor at the given frame position, picking the nearest mask pixel */
+    pub(super) fn sample(&self, x: usize, y: usize, frame_width: usize, frame_height: usize, mode: &str) -> f32 {
+        let pixel = self.pixel(x, y, frame_width, frame_height);
+
+        match mode {
+            "luma" => (0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32) / 255.0,
+            _ => pixel[3] as f32 / 255.0,
+        }
+    }
+
+    /* Normalized RGB color blended with the nearest mask pixel, as much as the mask is opaque */
+    pub(super) fn blend(&self, x: usize, y: usize, frame_width: usize, frame_height: usize,
+                        mode: BlendMode, invert: bool, rgb: [f32; 3]) -> [f32; 3] {
+        let pixel = self.pixel(x, y, frame_width, frame_height);
+        let opacity = pixel[3] as f32 / 255.0;
+        let opacity = if invert { 1.0 - opacity } else { opacity };
+
+        let mut blended = rgb;
+        for (c, color) in blended.iter_mut().enumerate() {
+            *color += (blend_channel(mode, pixel[c] as f32 / 255.0, *color) - *color) * opacity;
+        }
+
+        blended
+    }
+}
+
+/* Decode the first frame of an image file: filesrc ! decodebin ! videoconvert ! appsink */
+pub(crate) fn decode(location: &str) -> Result<Mask, String> {
+    let pipeline = gst::Pipeline::new(None);
+    let file_elem = gst::ElementFactory::make("filesrc", None).map_err(|err| err.to_string())?;
+    let decode_elem = gst::ElementFactory::make("decodebin", None).map_err(|err| err.to_string())?;
+    let convert_elem = gst::ElementFactory::make("videoconvert", None).map_err(|err| err.to_string())?;
+    let appsink = gst::ElementFactory::make("appsink", None).map_err(|err| err.to_string())?
+        .dynamic_cast::<gst_app::AppSink>()
+        .expect("appsink element is not an AppSink");
+
+    file_elem.set_property("location", location);
+    appsink.set_property("sync", false);
+    appsink.set_caps(Some(&gst::Caps::builder("video/x-raw").field("format", "RGBA").build()));
+
+    pipeline.add_many(&[&file_elem, &decode_elem, &convert_elem, appsink.upcast_ref()])
+        .map_err(|err| err.to_string())?;
+    file_elem.link(&decode_elem).map_err(|err| err.to_string())?;
+    convert_elem.link(&appsink).map_err(|err| err.to_string())?;
+
+    let convert_weak = convert_elem.downgrade();
+    decode_elem.connect_pad_added(move |_decode_elem, pad| {
+        if let Some(sink_pad) = convert_weak.upgrade().and_then(|convert_elem| convert_elem.static_pad("sink")) {
+            if !sink_pad.is_linked() {
+                let _ = pad.link(&sink_pad);
+            }
+        }
+    });
+
+    pipeline.set_state(gst::State::Playing).map_err(|err| err.to_string())?;
+    let sample = appsink.try_pull_sample(gst::ClockTime::from_seconds(5));
+    let _ = pipeline.set_state(gst::State::Null);
+
+    let sample = sample.ok_or_else(|| "Could not decode image".to_string())?;
+    let info = sample.caps().and_then(|caps| gst_video::VideoInfo::from_caps(caps).ok())
+        .ok_or_else(|| "Invalid decoded image caps".to_string())?;
+    let buffer = sample.buffer().ok_or_else(|| "Decoded image has no buffer".to_string())?;
+    let map = buffer.map_readable().map_err(|err| err.to_string())?;
+
+    let (width, height) = (info.width() as usize, info.height() as usize);
+    let stride = info.stride()[0] as usize;
+    let pixels = map.chunks(stride).take(height)
+        .flat_map(|line| line[..width * 4].iter().copied())
+        .collect::<Vec<u8>>();
+
+    Mask::new(width, height, pixels).ok_or_else(|| "Invalid decoded image size".to_string())
+}
+
+struct Settings {
+    location: Option<String>,
+    mode: String,
+    invert: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            location: None,
+            mode: DEFAULT_MODE.to_string(),
+            invert: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DkcAlphaMask {
+    settings: Mutex<Settings>,
+    mask: Mutex<Option<Mask>>,
+    /* Count of the locations set, a decoded mask only being kept if still the latest */
+    loads: Mutex<u64>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcalphamask",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster alpha mask filter"),
+    )
+});
+
+impl DkcAlphaMask {
+    /* Decoding takes a pipeline of its own, it runs in a thread not to stall the caller (or the
+     * streaming thread), frames keeping the previous mask until then */
+    fn load(&self, obj: &super::DkcAlphaMask, location: Option<String>) {
+        let load = {
+            let mut loads = self.loads.lock().unwrap();
+            *loads += 1;
+            *loads
+        };
+
+        let location = match location {
+            Some(location) => location,
+            None => {
+                *self.mask.lock().unwrap() = None;
+                return;
+            }
+        };
+
+        let obj_weak = obj.downgrade();
+        std::thread::spawn(move || {
+            let mask = decode(&location);
+
+            let obj = match obj_weak.upgrade() {
+                Some(obj) => obj,
+                None => return,
+            };
+            let alpha_mask = DkcAlphaMask::from_instance(&obj);
+
+            let loads = alpha_mask.loads.lock().unwrap();
+            if *loads != load {
+                return; // Superseded by a newer location
+            }
+
+            *alpha_mask.mask.lock().unwrap() = match mask {
+                Ok(mask) => {
+                    gst::debug!(CAT, obj: &obj, "Loaded mask image {}", location);
+                    Some(mask)
+                },
+                Err(err) => {
+                    gst::warning!(CAT, obj: &obj, "Could not load mask image {}: {}", location, err);
+                    None
+                },
+            };
+        });
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcAlphaMask {
+    const NAME: &'static str = "DkcAlphaMask";
+    type Type = super::DkcAlphaMask;
+    type ParentType = gst_video::VideoFilter;
+}
+
+impl ObjectImpl for DkcAlphaMask {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::new(
+                    "location",
+                    "Location",
+                    "Path of the mask image, stretched over the whole frame",
+                    None,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecString::new(
+                    "mode",
+                    "Mode",
+                    "Mask channel the frame alpha is multiplied by (alpha, luma), or blend mode of the mask \
+                     colors over the frame (additive, multiply, screen, subtract)",
+                    Some(DEFAULT_MODE),
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecBoolean::new(
+                    "invert",
+                    "Invert",
+                    "Whether to make opaque mask areas transparent instead (or to blend the transparent ones)",
+                    false,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "location" => {
+                let location = value.get::<Option<String>>().expect("type checked upstream");
+                self.settings.lock().unwrap().location = location.clone();
+                self.load(obj, location);
+            },
+            "mode" => {
+                let mode = value.get::<Option<String>>().expect("type checked upstream")
+                    .unwrap_or_else(|| DEFAULT_MODE.to_string());
+                if MODES.contains(&mode.as_str()) {
+                    self.settings.lock().unwrap().mode = mode;
+                } else {
+                    gst::warning!(CAT, obj: obj, "Unknown mask mode {}", mode);
+                }
+            },
+            "invert" => self.settings.lock().unwrap().invert = value.get().expect("type checked upstream"),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "location" => settings.location.to_value(),
+            "mode" => settings.mode.to_value(),
+            "invert" => settings.invert.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcAlphaMask {}
+
+impl ElementImpl for DkcAlphaMask {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Alpha Mask",
+                "Filter/Effect/Video",
+                "Multiplies the alpha of video frames by the one of a mask image, or blends its colors over them",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::builder("video/x-raw")
+                .field("format", gst::List::new(&[&"RGBA", &"BGRA"]))
+                .build();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![sink_pad_template, src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BaseTransformImpl for DkcAlphaMask {
+    const MODE: gst_base::subclass::BaseTransformMode = gst_base::subclass::BaseTransformMode::AlwaysInPlace;
+    const PASSTHROUGH_ON_SAME_CAPS: bool = false;
+    const TRANSFORM_IP_ON_PASSTHROUGH: bool = false;
+}
+
+impl VideoFilterImpl for DkcAlphaMask {
+    fn transform_frame_ip(
+        &self,
+        _element: &Self::Type,
+        frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mask_lock = self.mask.lock().unwrap();
+        let mask = match mask_lock.as_ref() {
+            Some(mask) => mask,
+            None => return Ok(gst::FlowSuccess::Ok), // No mask, leave frames untouched
+        };
+        let (mode, invert) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.mode.clone(), settings.invert)
+        };
+
+        let blending = blend_mode(&mode);
+        let (r, b) = match frame.format() {
+            gst_video::VideoFormat::Bgra => (2, 0),
+            _ => (0, 2),
+        };
+
+        let (width, height) = (frame.width() as usize, frame.height() as usize);
+        let stride = frame.plane_stride()[0] as usize;
+        let data = frame.plane_data_mut(0).map_err(|_| gst::FlowError::Error)?;
+
+        for (y, line) in data.chunks_exact_mut(stride).take(height).enumerate() {
+            for (x, pixel) in line[..width * 4].chunks_exact_mut(4).enumerate() {
+                match blending {
+                    Some(blend_mode) => {
+                        let rgb = [pixel[r] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[b] as f32 / 255.0];
+                        let rgb = mask.blend(x, y, width, height, blend_mode, invert, rgb);
+                        for (channel, color) in [r, 1, b].iter().zip(rgb.iter()) {
+                            pixel[*channel] = (color * 255.0).round() as u8;
+                        }
+                    },
+                    None => {
+                        let alpha = mask.sample(x, y, width, height, &mode);
+                        let alpha = if invert { 1.0 - alpha } else { alpha };
+                        pixel[3] = (pixel[3] as f32 * alpha).round() as u8;
+                    }
+                }
+            }
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
@@ -0,0 +1,132 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+pub(crate) use imp::{decode, Mask};
+
+glib::wrapper! {
+    pub struct DkcAlphaMask(ObjectSubclass<imp::DkcAlphaMask>) @extends gst_video::VideoFilter, gst_base::BaseTransform, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcAlphaMask {}
+unsafe impl Sync for DkcAlphaMask {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkcalphamask",
+        gst::Rank::None,
+        DkcAlphaMask::static_type(),
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    #[test]
+    fn test_sample() {
+        // 2x1 mask: opaque black on the left, transparent white on the right.
+        let mask = alphamask::imp::Mask::new(2, 1, vec![0, 0, 0, 255, 255, 255, 255, 0])
+            .expect("Could not make mask");
+
+        assert_eq!(mask.sample(0, 0, 4, 2, "alpha"), 1.0);
+        assert_eq!(mask.sample(3, 1, 4, 2, "alpha"), 0.0);
+        assert_eq!(mask.sample(1, 1, 4, 2, "luma"), 0.0);
+        assert_eq!(mask.sample(2, 0, 4, 2, "luma"), 1.0);
+
+        assert!(alphamask::imp::Mask::new(2, 2, vec![0; 4]).is_none());
+    }
+
+    #[test]
+    fn test_blend() {
+        // 2x1 mask: opaque white on the left, transparent white on the right.
+        let mask = alphamask::imp::Mask::new(2, 1, vec![255, 255, 255, 255, 255, 255, 255, 0])
+            .expect("Could not make mask");
+        let rgb = [0.5, 0.25, 0.0];
+
+        assert_eq!(mask.blend(0, 0, 2, 1, videomixer::BlendMode::Multiply, false, rgb), rgb);
+        assert_eq!(mask.blend(0, 0, 2, 1, videomixer::BlendMode::Additive, false, rgb), [1.0, 1.0, 1.0]);
+        assert_eq!(mask.blend(1, 0, 2, 1, videomixer::BlendMode::Additive, false, rgb), rgb);
+        assert_eq!(mask.blend(1, 0, 2, 1, videomixer::BlendMode::Subtract, true, rgb), [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_properties() {
+        set_up();
+
+        let mask = gst::ElementFactory::make("dkcalphamask", Some("mask"))
+            .expect("Could not make dkcalphamask element");
+
+        assert_eq!(mask.property::<String>("mode"), "alpha");
+
+        mask.set_property("mode", "luma");
+        mask.set_property("invert", true);
+        assert_eq!(mask.property::<String>("mode"), "luma");
+        assert!(mask.property::<bool>("invert"));
+
+        // Unknown modes are ignored.
+        mask.set_property("mode", "what");
+        assert_eq!(mask.property::<String>("mode"), "luma");
+
+        mask.set_property("mode", "multiply");
+        assert_eq!(mask.property::<String>("mode"), "multiply");
+    }
+
+    #[test]
+    fn test_load() {
+        set_up();
+
+        // Fully transparent mask image.
+        let path = std::env::temp_dir().join("dkcalphamask-test.png");
+        let encoder = gst::parse_launch(&format!(
+            "videotestsrc num-buffers=1 pattern=solid-color foreground-color=0 ! video/x-raw,format=RGBA,width=4,height=4 \
+             ! pngenc ! filesink location={}", path.display()))
+            .expect("Could not make image encoder");
+        encoder.set_state(gst::State::Playing).unwrap();
+        encoder.bus().unwrap().timed_pop_filtered(gst::ClockTime::from_seconds(10), &[gst::MessageType::Eos])
+            .expect("Could not write image file");
+        encoder.set_state(gst::State::Null).unwrap();
+
+        let pipeline = gst::parse_launch(
+            "videotestsrc is-live=true ! video/x-raw,format=RGBA,width=4,height=4 ! dkcalphamask name=mask \
+             ! appsink name=sink sync=false max-buffers=1 drop=true")
+            .expect("Could not make pipeline")
+            .downcast::<gst::Pipeline>().unwrap();
+        let sink = pipeline.by_name("sink").unwrap().downcast::<gst_app::AppSink>().unwrap();
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // The mask applies once decoded, without holding the caller back.
+        pipeline.by_name("mask").unwrap().set_property("location", path.to_str().unwrap());
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let mut masked = false;
+        while !masked && std::time::Instant::now() < deadline {
+            if let Some(sample) = sink.try_pull_sample(gst::ClockTime::from_seconds(1)) {
+                let map = sample.buffer().unwrap().map_readable().unwrap();
+                masked = map.chunks(4).all(|pixel| pixel[3] == 0);
+            }
+        }
+
+        pipeline.set_state(gst::State::Null).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(masked);
+    }
+
+}
@@ -0,0 +1,156 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+#[derive(Default)]
+pub struct DkcAudioSource {
+    device: Mutex<Option<String>>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcaudiosource",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster audio input source element"),
+    )
+});
+
+/* Capture element of the given device, made by the device provider listing it (PulseAudio, ALSA,
+ * WASAPI...), the platform default one being captured otherwise */
+fn make_capture_element(device: Option<&str>) -> gst::Element {
+    let element = device.and_then(|device| {
+        let monitor = gst::DeviceMonitor::new();
+        monitor.add_filter(Some("Audio/Source"), None);
+
+        monitor.devices().into_iter()
+            .filter_map(|provided| provided.create_element(Some("audiosource")).ok())
+            .find(|element| match element.find_property("device") {
+                Some(pspec) if pspec.value_type() == String::static_type() =>
+                    element.property::<Option<String>>("device").as_deref() == Some(device),
+                _ => false,
+            })
+    });
+
+    element.unwrap_or_else(|| {
+        if let Some(device) = device {
+            gst::error!(CAT, "No provider for audio device {}, capturing the default one", device);
+        }
+
+        gst::ElementFactory::make("autoaudiosrc", Some("audiosource"))
+            .expect("Could not create audio capture element.")
+    })
+}
+
+impl DkcAudioSource {
+    fn set_capture_element(&self, obj: &super::DkcAudioSource, audio_elem: gst::Element) {
+        let convert_elem = obj.by_name("audioconverter").unwrap();
+
+        if let Some(old_elem) = obj.by_name("audiosource") {
+            let _ = old_elem.set_state(gst::State::Null);
+            obj.remove(&old_elem).expect("Could not remove audio element from this source");
+        }
+
+        self.add_element(obj, &audio_elem).expect("Could not add audio element to this source");
+        audio_elem.link(&convert_elem).expect("Could not link audio element to converter element.");
+        let _ = audio_elem.sync_state_with_parent();
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcAudioSource {
+    const NAME: &'static str = "DkcAudioSource";
+    type Type = super::DkcAudioSource;
+    type ParentType = gst::Bin;
+}
+
+impl ObjectImpl for DkcAudioSource {
+    fn constructed(&self, obj: &Self::Type) {
+        let convert_elem = gst::ElementFactory::make("audioconvert", Some("audioconverter"))
+            .expect("Could not create audio converter element.");
+
+        self.add_element(obj, &convert_elem).expect("Could not add converter element to this source");
+        self.set_capture_element(obj, make_capture_element(None));
+
+        let audio_pad = convert_elem.static_pad("src").unwrap();
+        let audio_ghost_pad = gst::GhostPad::with_target(Some("audio_src"), &audio_pad).unwrap();
+
+        obj.add_pad(&audio_ghost_pad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::new(
+                    "device",
+                    "Device",
+                    "Audio input device to capture, the default one if unset",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "device" => {
+                let device = value.get::<Option<String>>().expect("type checked upstream");
+                self.set_capture_element(obj, make_capture_element(device.as_deref()));
+                *self.device.lock().unwrap() = device;
+            },
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "device" => self.device.lock().unwrap().to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcAudioSource {}
+
+impl ElementImpl for DkcAudioSource {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Audio Source",
+                "Audio",
+                "DankCaster audio input source element",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // src pad capabilities
+            let audio_caps = gst::Caps::builder("audio/x-raw")
+                .build();
+
+            let audio_src_pad_template = gst::PadTemplate::new(
+                "audio_src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &audio_caps,
+            )
+            .unwrap();
+
+            vec![audio_src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for DkcAudioSource {}
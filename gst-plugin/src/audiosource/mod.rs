@@ -0,0 +1,109 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcAudioSource(ObjectSubclass<imp::DkcAudioSource>) @extends gst::Bin, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcAudioSource {}
+unsafe impl Sync for DkcAudioSource {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkcaudiosource",
+        gst::Rank::None,
+        DkcAudioSource::static_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    /* Play the source into fake sinks, true once every one of them got a first buffer */
+    fn preroll(source: &gst::Element) -> bool {
+        let pipeline = gst::Pipeline::new(None);
+        pipeline.add(source).unwrap();
+
+        for pad in source.src_pads() {
+            let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+            pipeline.add(&sink).unwrap();
+            pad.link(&sink.static_pad("sink").unwrap()).unwrap();
+        }
+
+        let prerolled = pipeline.set_state(gst::State::Playing).is_ok()
+            && pipeline.state(gst::ClockTime::from_seconds(10)).0.is_ok();
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        prerolled
+    }
+
+    /* Audio capture devices known to the device providers */
+    fn capture_devices() -> Vec<gst::Device> {
+        let monitor = gst::DeviceMonitor::new();
+        monitor.add_filter(Some("Audio/Source"), None);
+        monitor.devices().into_iter().collect()
+    }
+
+    #[test]
+    fn test_device() {
+        set_up();
+
+        let source = gst::ElementFactory::make("dkcaudiosource", Some("audio"))
+            .expect("Could not make dkcaudiosource element");
+        let bin = source.downcast_ref::<gst::Bin>().unwrap();
+
+        // The platform default device is captured unless one is set.
+        assert_eq!(source.property::<Option<String>>("device"), None);
+        assert_eq!(bin.by_name("audiosource").unwrap().factory().unwrap().name(), "autoaudiosrc");
+
+        source.set_property("device", "nonexistent");
+        assert_eq!(source.property::<Option<String>>("device").as_deref(), Some("nonexistent"));
+        assert_eq!(bin.by_name("audiosource").unwrap().factory().unwrap().name(), "autoaudiosrc");
+
+        // Devices get the capture element of their provider.
+        for device in capture_devices() {
+            let element = device.create_element(None).unwrap();
+            if element.find_property("device").map(|pspec| pspec.value_type()) != Some(String::static_type()) {
+                continue;
+            }
+
+            let id = element.property::<Option<String>>("device").unwrap();
+            source.set_property("device", &id);
+            assert_eq!(bin.by_name("audiosource").unwrap().factory(), element.factory());
+        }
+    }
+
+    #[test]
+    fn test_preroll() {
+        set_up();
+
+        // Capture needs an audio input device to capture.
+        if capture_devices().is_empty() {
+            return;
+        }
+
+        let source = gst::ElementFactory::make("dkcaudiosource", Some("audio"))
+            .expect("Could not make dkcaudiosource element");
+
+        assert!(preroll(&source));
+    }
+
+}
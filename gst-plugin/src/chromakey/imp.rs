@@ -0,0 +1,227 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::subclass::prelude::*;
+use gst_video::subclass::prelude::*;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+const DEFAULT_KEY_COLOR: u32 = 0x00ff00;
+const DEFAULT_SIMILARITY: f64 = 0.4;
+const DEFAULT_SMOOTHNESS: f64 = 0.08;
+const DEFAULT_SPILL: f64 = 0.1;
+
+/* BT.709 chroma (Cb, Cr) of a normalized RGB color */
+fn chroma(rgb: [f32; 3]) -> [f32; 2] {
+    [-0.1146 * rgb[0] - 0.3854 * rgb[1] + 0.5 * rgb[2],
+     0.5 * rgb[0] - 0.4542 * rgb[1] - 0.0458 * rgb[2]]
+}
+
+fn luma(rgb: [f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct Settings {
+    key_color: u32,
+    similarity: f64,
+    smoothness: f64,
+    spill: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            key_color: DEFAULT_KEY_COLOR,
+            similarity: DEFAULT_SIMILARITY,
+            smoothness: DEFAULT_SMOOTHNESS,
+            spill: DEFAULT_SPILL,
+        }
+    }
+}
+
+impl Settings {
+    /* Alpha factor and color saturation factor of a normalized RGB color */
+    pub(super) fn key(&self, rgb: [f32; 3]) -> (f32, f32) {
+        let key_rgb = [((self.key_color >> 16) & 0xff) as f32 / 255.0,
+                       ((self.key_color >> 8) & 0xff) as f32 / 255.0,
+                       (self.key_color & 0xff) as f32 / 255.0];
+        let (key_chroma, pixel_chroma) = (chroma(key_rgb), chroma(rgb));
+        let distance = ((pixel_chroma[0] - key_chroma[0]).powi(2) + (pixel_chroma[1] - key_chroma[1]).powi(2)).sqrt();
+
+        let base_mask = distance - self.similarity as f32;
+        let ramp = |width: f64| if width > 0.0 {
+            (base_mask / width as f32).clamp(0.0, 1.0).powf(1.5)
+        } else if base_mask > 0.0 { 1.0 } else { 0.0 };
+
+        (ramp(self.smoothness), ramp(self.spill))
+    }
+}
+
+#[derive(Default)]
+pub struct DkcChromaKey {
+    settings: Mutex<Settings>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcChromaKey {
+    const NAME: &'static str = "DkcChromaKey";
+    type Type = super::DkcChromaKey;
+    type ParentType = gst_video::VideoFilter;
+}
+
+impl ObjectImpl for DkcChromaKey {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecUInt::new(
+                    "key-color",
+                    "Key color",
+                    "Color to make transparent, as 0xRRGGBB",
+                    0,
+                    0xffffff,
+                    DEFAULT_KEY_COLOR,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecDouble::new(
+                    "similarity",
+                    "Similarity",
+                    "Chroma distance to the key color under which pixels are transparent",
+                    0.0,
+                    1.0,
+                    DEFAULT_SIMILARITY,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecDouble::new(
+                    "smoothness",
+                    "Smoothness",
+                    "Chroma distance over which transparency fades out",
+                    0.0,
+                    1.0,
+                    DEFAULT_SMOOTHNESS,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecDouble::new(
+                    "spill",
+                    "Spill reduction",
+                    "Chroma distance over which pixels close to the key color are desaturated",
+                    0.0,
+                    1.0,
+                    DEFAULT_SPILL,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "key-color" => settings.key_color = value.get().expect("type checked upstream"),
+            "similarity" => settings.similarity = value.get().expect("type checked upstream"),
+            "smoothness" => settings.smoothness = value.get().expect("type checked upstream"),
+            "spill" => settings.spill = value.get().expect("type checked upstream"),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "key-color" => settings.key_color.to_value(),
+            "similarity" => settings.similarity.to_value(),
+            "smoothness" => settings.smoothness.to_value(),
+            "spill" => settings.spill.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcChromaKey {}
+
+impl ElementImpl for DkcChromaKey {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Chroma Key",
+                "Filter/Effect/Video",
+                "Makes pixels close to a key color transparent, with spill reduction",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::builder("video/x-raw")
+                .field("format", gst::List::new(&[&"RGBA", &"BGRA"]))
+                .build();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![sink_pad_template, src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BaseTransformImpl for DkcChromaKey {
+    const MODE: gst_base::subclass::BaseTransformMode = gst_base::subclass::BaseTransformMode::AlwaysInPlace;
+    const PASSTHROUGH_ON_SAME_CAPS: bool = false;
+    const TRANSFORM_IP_ON_PASSTHROUGH: bool = false;
+}
+
+impl VideoFilterImpl for DkcChromaKey {
+    fn transform_frame_ip(
+        &self,
+        _element: &Self::Type,
+        frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = *self.settings.lock().unwrap();
+
+        let (r, b) = match frame.format() {
+            gst_video::VideoFormat::Bgra => (2, 0),
+            _ => (0, 2),
+        };
+
+        let (width, height) = (frame.width() as usize, frame.height() as usize);
+        let stride = frame.plane_stride()[0] as usize;
+        let data = frame.plane_data_mut(0).map_err(|_| gst::FlowError::Error)?;
+
+        for line in data.chunks_exact_mut(stride).take(height) {
+            for pixel in line[..width * 4].chunks_exact_mut(4) {
+                let rgb = [pixel[r] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[b] as f32 / 255.0];
+                let (alpha, saturation) = settings.key(rgb);
+                let gray = luma(rgb);
+
+                for (channel, c) in [r, 1, b].iter().zip(0..3) {
+                    let value = gray + (rgb[c] - gray) * saturation;
+                    pixel[*channel] = (value * 255.0).round().clamp(0.0, 255.0) as u8;
+                }
+                pixel[3] = (pixel[3] as f32 * alpha).round() as u8;
+            }
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
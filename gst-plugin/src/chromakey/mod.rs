@@ -0,0 +1,72 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcChromaKey(ObjectSubclass<imp::DkcChromaKey>) @extends gst_video::VideoFilter, gst_base::BaseTransform, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcChromaKey {}
+unsafe impl Sync for DkcChromaKey {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkcchromakey",
+        gst::Rank::None,
+        DkcChromaKey::static_type(),
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    #[test]
+    fn test_key() {
+        let settings = chromakey::imp::Settings::default();
+
+        // The key color is transparent and fully desaturated.
+        assert_eq!(settings.key([0.0, 1.0, 0.0]), (0.0, 0.0));
+        assert_eq!(settings.key([0.1, 0.9, 0.1]), (0.0, 0.0));
+
+        // Colors far from it are left untouched.
+        assert_eq!(settings.key([1.0, 0.0, 1.0]), (1.0, 1.0));
+        assert_eq!(settings.key([1.0, 1.0, 1.0]), (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_properties() {
+        set_up();
+
+        let key = gst::ElementFactory::make("dkcchromakey", Some("key"))
+            .expect("Could not make dkcchromakey element");
+
+        key.set_property("key-color", 0x0000ffu32);
+        key.set_property("similarity", 0.2f64);
+        key.set_property("smoothness", 0.1f64);
+        key.set_property("spill", 0.3f64);
+
+        assert_eq!(key.property::<u32>("key-color"), 0x0000ff);
+        assert_eq!(key.property::<f64>("similarity"), 0.2);
+        assert_eq!(key.property::<f64>("smoothness"), 0.1);
+        assert_eq!(key.property::<f64>("spill"), 0.3);
+    }
+
+}
@@ -0,0 +1,180 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+const DEFAULT_COLOR: u32 = 0xffffffff;
+const DEFAULT_WIDTH: i32 = 1280;
+const DEFAULT_HEIGHT: i32 = 720;
+
+struct Settings {
+    width: i32,
+    height: i32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DkcColorSource {
+    settings: Mutex<Settings>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkccolorsource",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster color source element"),
+    )
+});
+
+impl DkcColorSource {
+    fn update_caps(&self, obj: &super::DkcColorSource) {
+        let settings = self.settings.lock().unwrap();
+        let caps = gst::Caps::builder("video/x-raw")
+            .field("width", settings.width)
+            .field("height", settings.height)
+            .build();
+
+        gst::debug!(CAT, obj: obj, "Setting caps to {}", caps);
+        obj.by_name("colorcapsfilter").unwrap().set_property("caps", &caps);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcColorSource {
+    const NAME: &'static str = "DkcColorSource";
+    type Type = super::DkcColorSource;
+    type ParentType = gst::Bin;
+}
+
+impl ObjectImpl for DkcColorSource {
+    fn constructed(&self, obj: &Self::Type) {
+        let video_elem = gst::ElementFactory::make("videotestsrc", Some("colorsource"))
+            .expect("Could not create video source element.");
+        let video_capsf = gst::ElementFactory::make("capsfilter", Some("colorcapsfilter"))
+            .expect("Could not create video capsfilter element.");
+
+        video_elem.set_property_from_str("pattern", "solid-color");
+        video_elem.set_property("foreground-color", DEFAULT_COLOR);
+        video_elem.set_property("is-live", true);
+
+        self.add_element(obj, &video_elem).expect("Could not add video element to this source");
+        self.add_element(obj, &video_capsf).expect("Could not add video caps filter to this source");
+
+        video_elem.link(&video_capsf).expect("Could not link video element to video capsfilter.");
+        self.update_caps(obj);
+
+        let video_pad = video_capsf.static_pad("src").unwrap();
+        let video_ghost_pad = gst::GhostPad::with_target(Some("video_src"), &video_pad).unwrap();
+
+        obj.add_pad(&video_ghost_pad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecUInt::new(
+                    "color",
+                    "Color",
+                    "Color to fill the frame with, as 0xAARRGGBB",
+                    0,
+                    u32::MAX,
+                    DEFAULT_COLOR,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecInt::new(
+                    "width",
+                    "Width",
+                    "Frame width",
+                    1,
+                    i32::MAX,
+                    DEFAULT_WIDTH,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecInt::new(
+                    "height",
+                    "Height",
+                    "Frame height",
+                    1,
+                    i32::MAX,
+                    DEFAULT_HEIGHT,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "color" => obj.by_name("colorsource").unwrap().set_property_from_value("foreground-color", value),
+            "width" => {
+                self.settings.lock().unwrap().width = value.get().expect("type checked upstream");
+                self.update_caps(obj);
+            },
+            "height" => {
+                self.settings.lock().unwrap().height = value.get().expect("type checked upstream");
+                self.update_caps(obj);
+            },
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "color" => obj.by_name("colorsource").unwrap().property_value("foreground-color"),
+            "width" => self.settings.lock().unwrap().width.to_value(),
+            "height" => self.settings.lock().unwrap().height.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcColorSource {}
+
+impl ElementImpl for DkcColorSource {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Color Source",
+                "Video",
+                "DankCaster solid color source element",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // src pad capabilities
+            let video_caps = gst::Caps::builder("video/x-raw")
+                .build();
+
+            let video_src_pad_template = gst::PadTemplate::new(
+                "video_src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &video_caps,
+            )
+            .unwrap();
+
+            vec![video_src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for DkcColorSource {}
@@ -0,0 +1,58 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcColorSource(ObjectSubclass<imp::DkcColorSource>) @extends gst::Bin, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcColorSource {}
+unsafe impl Sync for DkcColorSource {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkccolorsource",
+        gst::Rank::None,
+        DkcColorSource::static_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    #[test]
+    fn test_properties() {
+        set_up();
+
+        let source = gst::ElementFactory::make("dkccolorsource", Some("color"))
+            .expect("Could not make dkccolorsource element");
+
+        assert!(source.static_pad("video_src").is_some());
+
+        source.set_property("color", 0xff00ff00u32);
+        source.set_property("width", 640i32);
+        source.set_property("height", 360i32);
+
+        assert_eq!(source.property::<u32>("color"), 0xff00ff00);
+        assert_eq!(source.property::<i32>("width"), 640);
+        assert_eq!(source.property::<i32>("height"), 360);
+    }
+
+}
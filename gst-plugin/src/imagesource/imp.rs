@@ -0,0 +1,136 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+#[derive(Default)]
+pub struct DkcImageSource {
+
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcimagesource",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster image source element"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcImageSource {
+    const NAME: &'static str = "DkcImageSource";
+    type Type = super::DkcImageSource;
+    type ParentType = gst::Bin;
+}
+
+impl ObjectImpl for DkcImageSource {
+    fn constructed(&self, obj: &Self::Type) {
+        /* filesrc ! decodebin ! videoconvert ! imagefreeze */
+        let file_elem = gst::ElementFactory::make("filesrc", Some("imagefile"))
+            .expect("Could not create file source element.");
+        let decode_elem = gst::ElementFactory::make("decodebin", Some("imagedecoder"))
+            .expect("Could not create decoder element.");
+        let convert_elem = gst::ElementFactory::make("videoconvert", Some("imageconverter"))
+            .expect("Could not create video converter element.");
+        let freeze_elem = gst::ElementFactory::make("imagefreeze", Some("imagefreezer"))
+            .expect("Could not create image freezer element.");
+
+        freeze_elem.set_property("is-live", true);
+
+        self.add_element(obj, &file_elem).expect("Could not add file element to this source");
+        self.add_element(obj, &decode_elem).expect("Could not add decoder element to this source");
+        self.add_element(obj, &convert_elem).expect("Could not add converter element to this source");
+        self.add_element(obj, &freeze_elem).expect("Could not add freezer element to this source");
+
+        file_elem.link(&decode_elem).expect("Could not link file element to decoder element.");
+        convert_elem.link(&freeze_elem).expect("Could not link converter element to freezer element.");
+
+        let convert_weak = convert_elem.downgrade();
+        decode_elem.connect_pad_added(move |_decode_elem, pad| {
+            let convert_elem = match convert_weak.upgrade() {
+                Some(convert_elem) => convert_elem,
+                None => return,
+            };
+
+            let sink_pad = convert_elem.static_pad("sink").unwrap();
+            if !sink_pad.is_linked() && pad.link(&sink_pad).is_err() {
+                gst::error!(CAT, obj: pad, "Could not link decoded image to converter");
+            }
+        });
+
+        let video_pad = freeze_elem.static_pad("src").unwrap();
+        let video_ghost_pad = gst::GhostPad::with_target(Some("video_src"), &video_pad).unwrap();
+
+        obj.add_pad(&video_ghost_pad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::new(
+                    "location",
+                    "Location",
+                    "Path of the image file to display",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "location" => obj.by_name("imagefile").unwrap().set_property_from_value("location", value),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "location" => obj.by_name("imagefile").unwrap().property_value("location"),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcImageSource {}
+
+impl ElementImpl for DkcImageSource {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Image Source",
+                "Video",
+                "DankCaster still image source element",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // src pad capabilities
+            let video_caps = gst::Caps::builder("video/x-raw")
+                .build();
+
+            let video_src_pad_template = gst::PadTemplate::new(
+                "video_src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &video_caps,
+            )
+            .unwrap();
+
+            vec![video_src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for DkcImageSource {}
@@ -1,13 +1,41 @@
 use gst::glib;
 
 mod source;
+mod imagesource;
+mod mediasource;
+mod colorsource;
+mod textsource;
+mod screensource;
+mod audiosource;
 mod sink;
+mod recordsink;
+mod streamsink;
 mod scene;
+mod replay;
+mod lut;
+mod chromakey;
+mod lumakey;
+mod alphamask;
+mod videomixer;
 
 fn plugin_init(plugin: &gst::Plugin)  -> Result<(), glib::BoolError> {
     source::register(plugin)?;
+    imagesource::register(plugin)?;
+    mediasource::register(plugin)?;
+    colorsource::register(plugin)?;
+    textsource::register(plugin)?;
+    screensource::register(plugin)?;
+    audiosource::register(plugin)?;
     sink::register(plugin)?;
+    recordsink::register(plugin)?;
+    streamsink::register(plugin)?;
     scene::register(plugin)?;
+    replay::register(plugin)?;
+    lut::register(plugin)?;
+    chromakey::register(plugin)?;
+    lumakey::register(plugin)?;
+    alphamask::register(plugin)?;
+    videomixer::register(plugin)?;
     Ok(())
 }
 
@@ -0,0 +1,222 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::subclass::prelude::*;
+use gst_video::subclass::prelude::*;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+const DEFAULT_LUMA_MIN: f64 = 0.0;
+const DEFAULT_LUMA_MAX: f64 = 1.0;
+const DEFAULT_SMOOTHNESS: f64 = 0.0;
+
+fn luma(rgb: [f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+/* 0 under edge0, 1 above edge1, with a smooth transition in between */
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    if x < edge0 {
+        0.0
+    } else if x >= edge1 {
+        1.0
+    } else {
+        let t = (x - edge0) / (edge1 - edge0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct Settings {
+    pub(super) luma_min: f64,
+    pub(super) luma_min_smooth: f64,
+    pub(super) luma_max: f64,
+    pub(super) luma_max_smooth: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            luma_min: DEFAULT_LUMA_MIN,
+            luma_min_smooth: DEFAULT_SMOOTHNESS,
+            luma_max: DEFAULT_LUMA_MAX,
+            luma_max_smooth: DEFAULT_SMOOTHNESS,
+        }
+    }
+}
+
+impl Settings {
+    /* Alpha factor of a normalized RGB color, pixels out of the luma range being transparent.
+     * Both bounds are part of the range: the upper edge is the lower one mirrored */
+    pub(super) fn key(&self, rgb: [f32; 3]) -> f32 {
+        let value = luma(rgb);
+        let (min, max) = (self.luma_min as f32, self.luma_max as f32);
+
+        let low = smoothstep(min, min + self.luma_min_smooth as f32, value);
+        let high = smoothstep(-max, -(max - self.luma_max_smooth as f32), -value);
+
+        low * high
+    }
+}
+
+#[derive(Default)]
+pub struct DkcLumaKey {
+    settings: Mutex<Settings>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcLumaKey {
+    const NAME: &'static str = "DkcLumaKey";
+    type Type = super::DkcLumaKey;
+    type ParentType = gst_video::VideoFilter;
+}
+
+impl ObjectImpl for DkcLumaKey {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecDouble::new(
+                    "luma-min",
+                    "Minimum luma",
+                    "Luma under which pixels are transparent",
+                    0.0,
+                    1.0,
+                    DEFAULT_LUMA_MIN,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecDouble::new(
+                    "luma-min-smooth",
+                    "Minimum luma smoothness",
+                    "Luma range above the minimum over which transparency fades out",
+                    0.0,
+                    1.0,
+                    DEFAULT_SMOOTHNESS,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecDouble::new(
+                    "luma-max",
+                    "Maximum luma",
+                    "Luma above which pixels are transparent",
+                    0.0,
+                    1.0,
+                    DEFAULT_LUMA_MAX,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecDouble::new(
+                    "luma-max-smooth",
+                    "Maximum luma smoothness",
+                    "Luma range under the maximum over which transparency fades out",
+                    0.0,
+                    1.0,
+                    DEFAULT_SMOOTHNESS,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "luma-min" => settings.luma_min = value.get().expect("type checked upstream"),
+            "luma-min-smooth" => settings.luma_min_smooth = value.get().expect("type checked upstream"),
+            "luma-max" => settings.luma_max = value.get().expect("type checked upstream"),
+            "luma-max-smooth" => settings.luma_max_smooth = value.get().expect("type checked upstream"),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "luma-min" => settings.luma_min.to_value(),
+            "luma-min-smooth" => settings.luma_min_smooth.to_value(),
+            "luma-max" => settings.luma_max.to_value(),
+            "luma-max-smooth" => settings.luma_max_smooth.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcLumaKey {}
+
+impl ElementImpl for DkcLumaKey {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Luma Key",
+                "Filter/Effect/Video",
+                "Makes pixels out of a luma range transparent",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::builder("video/x-raw")
+                .field("format", gst::List::new(&[&"RGBA", &"BGRA"]))
+                .build();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![sink_pad_template, src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BaseTransformImpl for DkcLumaKey {
+    const MODE: gst_base::subclass::BaseTransformMode = gst_base::subclass::BaseTransformMode::AlwaysInPlace;
+    const PASSTHROUGH_ON_SAME_CAPS: bool = false;
+    const TRANSFORM_IP_ON_PASSTHROUGH: bool = false;
+}
+
+impl VideoFilterImpl for DkcLumaKey {
+    fn transform_frame_ip(
+        &self,
+        _element: &Self::Type,
+        frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = *self.settings.lock().unwrap();
+
+        let (r, b) = match frame.format() {
+            gst_video::VideoFormat::Bgra => (2, 0),
+            _ => (0, 2),
+        };
+
+        let (width, height) = (frame.width() as usize, frame.height() as usize);
+        let stride = frame.plane_stride()[0] as usize;
+        let data = frame.plane_data_mut(0).map_err(|_| gst::FlowError::Error)?;
+
+        for line in data.chunks_exact_mut(stride).take(height) {
+            for pixel in line[..width * 4].chunks_exact_mut(4) {
+                let rgb = [pixel[r] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[b] as f32 / 255.0];
+                let alpha = settings.key(rgb);
+                pixel[3] = (pixel[3] as f32 * alpha).round() as u8;
+            }
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
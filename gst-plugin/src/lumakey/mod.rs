@@ -0,0 +1,81 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcLumaKey(ObjectSubclass<imp::DkcLumaKey>) @extends gst_video::VideoFilter, gst_base::BaseTransform, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcLumaKey {}
+unsafe impl Sync for DkcLumaKey {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkclumakey",
+        gst::Rank::None,
+        DkcLumaKey::static_type(),
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    #[test]
+    fn test_key() {
+        let settings = lumakey::imp::Settings::default();
+
+        // The default range keys nothing.
+        assert_eq!(settings.key([0.0, 0.0, 0.0]), 1.0);
+        assert_eq!(settings.key([1.0, 1.0, 1.0]), 1.0);
+    }
+
+    #[test]
+    fn test_key_range() {
+        let settings = lumakey::imp::Settings { luma_min: 0.25, luma_max: 0.75,
+                                                ..lumakey::imp::Settings::default() };
+
+        // Hard edges keep their bounds and key out what is past them.
+        assert_eq!(settings.key([0.5, 0.5, 0.5]), 1.0);
+        assert_eq!(settings.key([0.75, 0.75, 0.75]), 1.0);
+        assert_eq!(settings.key([0.25, 0.25, 0.25]), 1.0);
+        assert_eq!(settings.key([0.8, 0.8, 0.8]), 0.0);
+        assert_eq!(settings.key([0.2, 0.2, 0.2]), 0.0);
+    }
+
+    #[test]
+    fn test_properties() {
+        set_up();
+
+        let key = gst::ElementFactory::make("dkclumakey", Some("key"))
+            .expect("Could not make dkclumakey element");
+
+        key.set_property("luma-min", 0.1f64);
+        key.set_property("luma-min-smooth", 0.05f64);
+        key.set_property("luma-max", 0.9f64);
+        key.set_property("luma-max-smooth", 0.05f64);
+
+        assert_eq!(key.property::<f64>("luma-min"), 0.1);
+        assert_eq!(key.property::<f64>("luma-min-smooth"), 0.05);
+        assert_eq!(key.property::<f64>("luma-max"), 0.9);
+        assert_eq!(key.property::<f64>("luma-max-smooth"), 0.05);
+    }
+
+}
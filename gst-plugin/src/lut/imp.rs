@@ -0,0 +1,285 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::subclass::prelude::*;
+use gst_video::subclass::prelude::*;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+const DEFAULT_INTENSITY: f64 = 1.0;
+
+/* 3D color lookup table, as found in .cube files (red varying fastest) */
+pub(super) struct Lut {
+    size: usize,
+    table: Vec<[f32; 3]>,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+}
+
+fn parse_triplet<'a>(values: impl Iterator<Item = &'a str>) -> Result<[f32; 3], String> {
+    let values = values.map(|value| value.parse::<f32>().map_err(|_| format!("Invalid value '{}'", value)))
+        .collect::<Result<Vec<f32>, String>>()?;
+
+    match values.as_slice() {
+        [r, g, b] => Ok([*r, *g, *b]),
+        _ => Err("Expected 3 values per line".to_string()),
+    }
+}
+
+pub(super) fn parse_cube(contents: &str) -> Result<Lut, String> {
+    let mut size = None;
+    let mut table = Vec::new();
+    let mut domain_min = [0.0; 3];
+    let mut domain_max = [1.0; 3];
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("TITLE") => (),
+            Some("LUT_1D_SIZE") => return Err("1D lookup tables are not supported".to_string()),
+            Some("LUT_3D_SIZE") => size = Some(fields.next().and_then(|size| size.parse::<usize>().ok())
+                                               .filter(|size| *size >= 2)
+                                               .ok_or_else(|| "Invalid LUT_3D_SIZE".to_string())?),
+            Some("DOMAIN_MIN") => domain_min = parse_triplet(fields)?,
+            Some("DOMAIN_MAX") => domain_max = parse_triplet(fields)?,
+            Some(first) => table.push(parse_triplet(std::iter::once(first).chain(fields))?),
+            None => (),
+        }
+    }
+
+    let size = size.ok_or_else(|| "Missing LUT_3D_SIZE".to_string())?;
+    if table.len() != size * size * size {
+        return Err(format!("Expected {} entries, got {}", size * size * size, table.len()));
+    }
+
+    Ok(Lut { size, table, domain_min, domain_max })
+}
+
+impl Lut {
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[r + g * self.size + b * self.size * self.size]
+    }
+
+    /* Trilinear interpolation between the 8 surrounding entries */
+    pub(super) fn lookup(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let max = (self.size - 1) as f32;
+        let mut low = [0usize; 3];
+        let mut high = [0usize; 3];
+        let mut frac = [0f32; 3];
+
+        for c in 0..3 {
+            let range = self.domain_max[c] - self.domain_min[c];
+            let pos = ((rgb[c] - self.domain_min[c]) / range).clamp(0.0, 1.0) * max;
+            low[c] = pos.floor() as usize;
+            high[c] = (low[c] + 1).min(self.size - 1);
+            frac[c] = pos - low[c] as f32;
+        }
+
+        let mut out = [0f32; 3];
+        for (corner, weight) in (0..8).map(|corner| {
+            let pick = |c: usize| if corner & (1 << c) != 0 { (high[c], frac[c]) } else { (low[c], 1.0 - frac[c]) };
+            let ((r, wr), (g, wg), (b, wb)) = (pick(0), pick(1), pick(2));
+            (self.entry(r, g, b), wr * wg * wb)
+        }) {
+            for c in 0..3 {
+                out[c] += corner[c] * weight;
+            }
+        }
+
+        out
+    }
+}
+
+struct Settings {
+    location: Option<String>,
+    intensity: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            location: None,
+            intensity: DEFAULT_INTENSITY,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DkcLut {
+    settings: Mutex<Settings>,
+    lut: Mutex<Option<Lut>>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkclut",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster color lookup table filter"),
+    )
+});
+
+impl DkcLut {
+    fn load(&self, obj: &super::DkcLut, location: Option<&str>) {
+        let lut = match location {
+            Some(location) => match std::fs::read_to_string(location).map_err(|err| err.to_string())
+                .and_then(|contents| parse_cube(&contents)) {
+                Ok(lut) => Some(lut),
+                Err(err) => {
+                    gst::warning!(CAT, obj: obj, "Could not load lookup table {}: {}", location, err);
+                    None
+                },
+            },
+            None => None,
+        };
+
+        *self.lut.lock().unwrap() = lut;
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcLut {
+    const NAME: &'static str = "DkcLut";
+    type Type = super::DkcLut;
+    type ParentType = gst_video::VideoFilter;
+}
+
+impl ObjectImpl for DkcLut {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::new(
+                    "location",
+                    "Location",
+                    "Path of the .cube lookup table file",
+                    None,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecDouble::new(
+                    "intensity",
+                    "Intensity",
+                    "Mix between the original (0) and the looked up (1) colors",
+                    0.0,
+                    1.0,
+                    DEFAULT_INTENSITY,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "location" => {
+                let location = value.get::<Option<String>>().expect("type checked upstream");
+                self.load(obj, location.as_deref());
+                self.settings.lock().unwrap().location = location;
+            },
+            "intensity" => self.settings.lock().unwrap().intensity = value.get().expect("type checked upstream"),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "location" => self.settings.lock().unwrap().location.to_value(),
+            "intensity" => self.settings.lock().unwrap().intensity.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcLut {}
+
+impl ElementImpl for DkcLut {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster LUT",
+                "Filter/Effect/Video",
+                "Applies a 3D color lookup table (.cube) to video frames",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::builder("video/x-raw")
+                .field("format", gst::List::new(&[&"RGBA", &"RGBx", &"BGRA", &"BGRx"]))
+                .build();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![sink_pad_template, src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BaseTransformImpl for DkcLut {
+    const MODE: gst_base::subclass::BaseTransformMode = gst_base::subclass::BaseTransformMode::AlwaysInPlace;
+    const PASSTHROUGH_ON_SAME_CAPS: bool = false;
+    const TRANSFORM_IP_ON_PASSTHROUGH: bool = false;
+}
+
+impl VideoFilterImpl for DkcLut {
+    fn transform_frame_ip(
+        &self,
+        _element: &Self::Type,
+        frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let lut_lock = self.lut.lock().unwrap();
+        let lut = match lut_lock.as_ref() {
+            Some(lut) => lut,
+            None => return Ok(gst::FlowSuccess::Ok), // No table, leave frames untouched
+        };
+        let intensity = self.settings.lock().unwrap().intensity as f32;
+
+        let (r, b) = match frame.format() {
+            gst_video::VideoFormat::Bgra | gst_video::VideoFormat::Bgrx => (2, 0),
+            _ => (0, 2),
+        };
+
+        let (width, height) = (frame.width() as usize, frame.height() as usize);
+        let stride = frame.plane_stride()[0] as usize;
+        let data = frame.plane_data_mut(0).map_err(|_| gst::FlowError::Error)?;
+
+        for line in data.chunks_exact_mut(stride).take(height) {
+            for pixel in line[..width * 4].chunks_exact_mut(4) {
+                let rgb = [pixel[r] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[b] as f32 / 255.0];
+                let looked_up = lut.lookup(rgb);
+                for (channel, c) in [r, 1, b].iter().zip(0..3) {
+                    let value = rgb[c] + (looked_up[c] - rgb[c]) * intensity;
+                    pixel[*channel] = (value * 255.0).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
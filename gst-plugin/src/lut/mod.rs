@@ -0,0 +1,72 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcLut(ObjectSubclass<imp::DkcLut>) @extends gst_video::VideoFilter, gst_base::BaseTransform, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcLut {}
+unsafe impl Sync for DkcLut {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkclut",
+        gst::Rank::None,
+        DkcLut::static_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    const INVERT_CUBE: &str = "TITLE \"invert\"\n\
+                               # Red varies fastest\n\
+                               LUT_3D_SIZE 2\n\
+                               1.0 1.0 1.0\n0.0 1.0 1.0\n1.0 0.0 1.0\n0.0 0.0 1.0\n\
+                               1.0 1.0 0.0\n0.0 1.0 0.0\n1.0 0.0 0.0\n0.0 0.0 0.0\n";
+
+    #[test]
+    fn test_parse_cube() {
+        let lut = lut::imp::parse_cube(INVERT_CUBE).expect("Could not parse lookup table");
+
+        assert_eq!(lut.lookup([0.0, 0.0, 0.0]), [1.0, 1.0, 1.0]);
+        assert_eq!(lut.lookup([1.0, 0.0, 1.0]), [0.0, 1.0, 0.0]);
+        assert_eq!(lut.lookup([0.5, 0.25, 0.75]), [0.5, 0.75, 0.25]);
+
+        assert!(lut::imp::parse_cube("LUT_3D_SIZE 2\n0.0 0.0 0.0\n").is_err());
+        assert!(lut::imp::parse_cube("LUT_1D_SIZE 2\n0.0 0.0 0.0\n1.0 1.0 1.0\n").is_err());
+    }
+
+    #[test]
+    fn test_properties() {
+        set_up();
+
+        let lut = gst::ElementFactory::make("dkclut", Some("lut"))
+            .expect("Could not make dkclut element");
+
+        lut.set_property("intensity", 0.5f64);
+        assert_eq!(lut.property::<f64>("intensity"), 0.5);
+
+        lut.set_property("location", "/does/not/exist.cube");
+        assert_eq!(lut.property::<Option<String>>("location").as_deref(), Some("/does/not/exist.cube"));
+    }
+
+}
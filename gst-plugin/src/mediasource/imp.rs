@@ -0,0 +1,196 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+#[derive(Default)]
+pub struct DkcMediaSource {
+
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcmediasource",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster media source element"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcMediaSource {
+    const NAME: &'static str = "DkcMediaSource";
+    type Type = super::DkcMediaSource;
+    type ParentType = gst::Bin;
+}
+
+impl ObjectImpl for DkcMediaSource {
+    fn constructed(&self, obj: &Self::Type) {
+        /* uridecodebin ! videoconvert, uridecodebin ! audioconvert */
+        let decode_elem = gst::ElementFactory::make("uridecodebin", Some("mediadecoder"))
+            .expect("Could not create decoder element.");
+        let video_elem = gst::ElementFactory::make("videoconvert", Some("videoconverter"))
+            .expect("Could not create video converter element.");
+        let audio_elem = gst::ElementFactory::make("audioconvert", Some("audioconverter"))
+            .expect("Could not create audio converter element.");
+
+        self.add_element(obj, &decode_elem).expect("Could not add decoder element to this source");
+        self.add_element(obj, &video_elem).expect("Could not add video element to this source");
+        self.add_element(obj, &audio_elem).expect("Could not add audio element to this source");
+
+        let video_weak = video_elem.downgrade();
+        let audio_weak = audio_elem.downgrade();
+        decode_elem.connect_pad_added(move |_decode_elem, pad| {
+            let caps = match pad.current_caps() {
+                Some(caps) => caps,
+                None => pad.query_caps(None),
+            };
+
+            let media_type = match caps.structure(0) {
+                Some(structure) => structure.name().to_string(),
+                None => return,
+            };
+
+            let convert_elem = if media_type.starts_with("video/") {
+                video_weak.upgrade()
+            } else if media_type.starts_with("audio/") {
+                audio_weak.upgrade()
+            } else {
+                None
+            };
+
+            if let Some(convert_elem) = convert_elem {
+                let sink_pad = convert_elem.static_pad("sink").unwrap();
+                if !sink_pad.is_linked() && pad.link(&sink_pad).is_err() {
+                    gst::error!(CAT, obj: pad, "Could not link decoded {} stream", media_type);
+                }
+            }
+        });
+
+        /* Media lacking a video or audio stream get a black or silent one instead, for the
+         * unused pad not to stall the mixers it is linked to */
+        let obj_weak = obj.downgrade();
+        let video_weak = video_elem.downgrade();
+        let audio_weak = audio_elem.downgrade();
+        decode_elem.connect_no_more_pads(move |_decode_elem| {
+            let obj = match obj_weak.upgrade() {
+                Some(obj) => obj,
+                None => return,
+            };
+
+            let fillers = [(video_weak.upgrade(), "videotestsrc", "videofiller"),
+                           (audio_weak.upgrade(), "audiotestsrc", "audiofiller")];
+            for (convert_elem, factory, name) in fillers {
+                let convert_elem = match convert_elem {
+                    Some(convert_elem) => convert_elem,
+                    None => continue,
+                };
+                if convert_elem.static_pad("sink").unwrap().is_linked() {
+                    continue;
+                }
+
+                gst::debug!(CAT, obj: &obj, "No decoded stream for {}, filling it", convert_elem.name());
+                let filler_elem = gst::ElementFactory::make(factory, Some(name))
+                    .expect("Could not create filler element.");
+                match factory {
+                    "videotestsrc" => filler_elem.set_property_from_str("pattern", "black"),
+                    _ => filler_elem.set_property_from_str("wave", "silence"),
+                }
+
+                if obj.add(&filler_elem).is_err() || filler_elem.link(&convert_elem).is_err() {
+                    gst::error!(CAT, obj: &obj, "Could not fill {}", convert_elem.name());
+                    continue;
+                }
+                let _ = filler_elem.sync_state_with_parent();
+            }
+        });
+
+        let video_pad = video_elem.static_pad("src").unwrap();
+        let audio_pad = audio_elem.static_pad("src").unwrap();
+
+        let video_ghost_pad = gst::GhostPad::with_target(Some("video_src"), &video_pad).unwrap();
+        let audio_ghost_pad = gst::GhostPad::with_target(Some("audio_src"), &audio_pad).unwrap();
+
+        obj.add_pad(&video_ghost_pad).unwrap();
+        obj.add_pad(&audio_ghost_pad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::new(
+                    "uri",
+                    "URI",
+                    "URI of the media to play",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "uri" => obj.by_name("mediadecoder").unwrap().set_property_from_value("uri", value),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "uri" => obj.by_name("mediadecoder").unwrap().property_value("uri"),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcMediaSource {}
+
+impl ElementImpl for DkcMediaSource {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Media Source",
+                "Audio/Video",
+                "DankCaster media file or stream source element",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // src pad capabilities
+            let video_caps = gst::Caps::builder("video/x-raw")
+                .build();
+            let audio_caps = gst::Caps::builder("audio/x-raw")
+                .build();
+
+            let video_src_pad_template = gst::PadTemplate::new(
+                "video_src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &video_caps,
+            )
+            .unwrap();
+
+            let audio_src_pad_template = gst::PadTemplate::new(
+                "audio_src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &audio_caps,
+            )
+            .unwrap();
+
+            vec![video_src_pad_template, audio_src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for DkcMediaSource {}
@@ -0,0 +1,97 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcMediaSource(ObjectSubclass<imp::DkcMediaSource>) @extends gst::Bin, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcMediaSource {}
+unsafe impl Sync for DkcMediaSource {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkcmediasource",
+        gst::Rank::None,
+        DkcMediaSource::static_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    /* Play the source into fake sinks, true once every one of them got a first buffer */
+    fn preroll(source: &gst::Element) -> bool {
+        let pipeline = gst::Pipeline::new(None);
+        pipeline.add(source).unwrap();
+
+        for pad in source.src_pads() {
+            let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+            pipeline.add(&sink).unwrap();
+            pad.link(&sink.static_pad("sink").unwrap()).unwrap();
+        }
+
+        let prerolled = pipeline.set_state(gst::State::Playing).is_ok()
+            && pipeline.state(gst::ClockTime::from_seconds(10)).0.is_ok();
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        prerolled
+    }
+
+    /* Short mono WAV file of silence, with no video stream */
+    fn write_wav(path: &std::path::Path) {
+        let samples = 4410u32;
+        let mut data = Vec::new();
+        data.extend_from_slice(b"RIFF");
+        data.extend_from_slice(&(36 + samples * 2).to_le_bytes());
+        data.extend_from_slice(b"WAVEfmt ");
+        data.extend_from_slice(&16u32.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&44100u32.to_le_bytes());
+        data.extend_from_slice(&(44100u32 * 2).to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&16u16.to_le_bytes());
+        data.extend_from_slice(b"data");
+        data.extend_from_slice(&(samples * 2).to_le_bytes());
+        data.resize(data.len() + samples as usize * 2, 0);
+        std::fs::write(path, data).expect("Could not write media file");
+    }
+
+    #[test]
+    fn test_preroll_audio_only() {
+        set_up();
+
+        let path = std::env::temp_dir().join("dkcmediasource-test.wav");
+        write_wav(&path);
+
+        let source = gst::ElementFactory::make("dkcmediasource", Some("media"))
+            .expect("Could not make dkcmediasource element");
+        source.set_property("uri", format!("file://{}", path.display()));
+
+        // The missing video stream is filled for both pads to preroll.
+        assert!(preroll(&source));
+        assert!(source.downcast_ref::<gst::Bin>().unwrap().by_name("videofiller").is_some());
+        assert!(source.downcast_ref::<gst::Bin>().unwrap().by_name("audiofiller").is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+}
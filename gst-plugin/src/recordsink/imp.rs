@@ -0,0 +1,166 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+#[derive(Default)]
+pub struct DkcRecordSink {
+
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcrecordsink",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster recording sink element"),
+    )
+});
+
+/* First element available out of several doing the same job, preferred first */
+pub(crate) fn make_first(factories: &[&str], name: &str) -> gst::Element {
+    factories.iter()
+        .find_map(|factory| gst::ElementFactory::make(factory, Some(name)).ok())
+        .unwrap_or_else(|| panic!("Could not create {} element, none of {:?} is available.", name, factories))
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcRecordSink {
+    const NAME: &'static str = "DkcRecordSink";
+    type Type = super::DkcRecordSink;
+    type ParentType = gst::Bin;
+}
+
+impl ObjectImpl for DkcRecordSink {
+    fn constructed(&self, obj: &Self::Type) {
+        /* Matroska stays readable when the recording stops abruptly:
+         * videoconvert ! queue ! h264 ! mux, audioconvert ! audioresample ! opus ! queue ! mux */
+        let video_convert = gst::ElementFactory::make("videoconvert", Some("videoconverter"))
+            .expect("Could not create video converter element.");
+        let video_queue = gst::ElementFactory::make("queue", Some("videoqueue"))
+            .expect("Could not create video queue element.");
+        let video_enc = make_first(&["x264enc", "openh264enc"], "videoencoder");
+        let video_parse = gst::ElementFactory::make("h264parse", Some("videoparser"))
+            .expect("Could not create video parser element.");
+        let audio_convert = gst::ElementFactory::make("audioconvert", Some("audioconverter"))
+            .expect("Could not create audio converter element.");
+        let audio_resample = gst::ElementFactory::make("audioresample", Some("audioresampler"))
+            .expect("Could not create audio resampler element.");
+        let audio_enc = gst::ElementFactory::make("opusenc", Some("audioencoder"))
+            .expect("Could not create audio encoder element.");
+        let audio_queue = gst::ElementFactory::make("queue", Some("audioqueue"))
+            .expect("Could not create audio queue element.");
+        let mux_elem = gst::ElementFactory::make("matroskamux", Some("muxer"))
+            .expect("Could not create muxer element.");
+        let file_elem = gst::ElementFactory::make("filesink", Some("recordfile"))
+            .expect("Could not create file sink element.");
+
+        if video_enc.find_property("tune").is_some() {
+            video_enc.set_property_from_str("tune", "zerolatency");
+        }
+
+        let elements = [&video_convert, &video_queue, &video_enc, &video_parse,
+                        &audio_convert, &audio_resample, &audio_enc, &audio_queue,
+                        &mux_elem, &file_elem];
+        for element in elements {
+            self.add_element(obj, element).expect("Could not add element to this sink");
+        }
+
+        gst::Element::link_many(&[&video_convert, &video_queue, &video_enc, &video_parse, &mux_elem])
+            .expect("Could not link video elements.");
+        gst::Element::link_many(&[&audio_convert, &audio_resample, &audio_enc, &audio_queue, &mux_elem])
+            .expect("Could not link audio elements.");
+        mux_elem.link(&file_elem).expect("Could not link muxer element to file element.");
+
+        let video_pad = video_convert.static_pad("sink").unwrap();
+        let audio_pad = audio_convert.static_pad("sink").unwrap();
+
+        let video_ghost_pad = gst::GhostPad::with_target(Some("video_sink"), &video_pad).unwrap();
+        let audio_ghost_pad = gst::GhostPad::with_target(Some("audio_sink"), &audio_pad).unwrap();
+
+        obj.add_pad(&video_ghost_pad).unwrap();
+        obj.add_pad(&audio_ghost_pad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::new(
+                    "location",
+                    "Location",
+                    "Path of the Matroska file to record to",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "location" => {
+                gst::debug!(CAT, obj: obj, "Recording to {:?}", value.get::<Option<String>>());
+                obj.by_name("recordfile").unwrap().set_property_from_value("location", value)
+            },
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "location" => obj.by_name("recordfile").unwrap().property_value("location"),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcRecordSink {}
+
+impl ElementImpl for DkcRecordSink {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Record Sink",
+                "Audio/Video",
+                "DankCaster sink element recording to a file",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // sink pad capabilities
+            let video_caps = gst::Caps::builder("video/x-raw")
+                .build();
+            let audio_caps = gst::Caps::builder("audio/x-raw")
+                .build();
+
+            let video_sink_pad_template = gst::PadTemplate::new(
+                "video_sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &video_caps,
+            )
+            .unwrap();
+
+            let audio_sink_pad_template = gst::PadTemplate::new(
+                "audio_sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &audio_caps,
+            )
+            .unwrap();
+
+            vec![video_sink_pad_template, audio_sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for DkcRecordSink {}
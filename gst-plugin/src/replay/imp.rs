@@ -0,0 +1,426 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use once_cell::sync::Lazy;
+
+const DEFAULT_DURATION: u32 = 30;
+
+#[derive(Clone)]
+struct ReplayStream {
+    caps: Option<gst::Caps>,
+    buffers: VecDeque<gst::Buffer>,
+}
+
+impl ReplayStream {
+    fn new() -> Self {
+        Self {
+            caps: None,
+            buffers: VecDeque::new(),
+        }
+    }
+}
+
+/* Encoded audio and video, covering (at least) the last `duration` */
+struct ReplayBuffer {
+    duration: gst::ClockTime,
+    video: ReplayStream,
+    audio: ReplayStream,
+}
+
+impl Default for ReplayBuffer {
+    fn default() -> Self {
+        Self {
+            duration: gst::ClockTime::from_seconds(DEFAULT_DURATION as u64),
+            video: ReplayStream::new(),
+            audio: ReplayStream::new(),
+        }
+    }
+}
+
+fn buffer_time(buffer: &gst::Buffer) -> Option<gst::ClockTime> {
+    buffer.dts().or_else(|| buffer.pts())
+}
+
+fn is_keyframe(buffer: &gst::Buffer) -> bool {
+    !buffer.flags().contains(gst::BufferFlags::DELTA_UNIT)
+}
+
+impl ReplayBuffer {
+    fn push(&mut self, is_video: bool, sample: &gst::Sample) {
+        let stream = if is_video { &mut self.video } else { &mut self.audio };
+
+        if let Some(caps) = sample.caps() {
+            stream.caps = Some(caps.to_owned());
+        }
+
+        if let Some(buffer) = sample.buffer_owned() {
+            stream.buffers.push_back(buffer);
+        }
+
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        let latest = self.video.buffers.back().and_then(buffer_time).into_iter()
+            .chain(self.audio.buffers.back().and_then(buffer_time))
+            .max();
+
+        let start = match latest {
+            Some(latest) => gst::ClockTime::from_nseconds(
+                latest.nseconds().saturating_sub(self.duration.nseconds())),
+            None => return,
+        };
+
+        /* Video has to start on a keyframe: keep the last one before the window start */
+        let video_cut = self.video.buffers.iter()
+            .enumerate()
+            .filter(|(_, buffer)| is_keyframe(buffer))
+            .take_while(|(_, buffer)| buffer_time(buffer).map_or(true, |time| time <= start))
+            .last()
+            .map(|(index, _)| index)
+            .or_else(|| self.video.buffers.iter().position(is_keyframe))
+            .unwrap_or(self.video.buffers.len());
+        self.video.buffers.drain(..video_cut);
+
+        /* Audio starts along with the first video frame we kept */
+        let audio_start = self.video.buffers.front().and_then(buffer_time).unwrap_or(start);
+        while let Some(buffer) = self.audio.buffers.front() {
+            match buffer_time(buffer) {
+                Some(time) if time >= audio_start => break,
+                _ => { self.audio.buffers.pop_front(); },
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DkcReplaySink {
+    replay: Arc<Mutex<ReplayBuffer>>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcreplaysink",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster replay sink element"),
+    )
+});
+
+fn write_replay(location: &str, streams: Vec<ReplayStream>) -> Result<(), glib::BoolError> {
+    let pipeline = gst::Pipeline::new(None);
+    let mux = gst::ElementFactory::make("matroskamux", None)?;
+    let filesink = gst::ElementFactory::make("filesink", None)?;
+    filesink.set_property("location", location);
+
+    pipeline.add_many(&[&mux, &filesink])?;
+    mux.link(&filesink)?;
+
+    /* Every stream gets rebased on the earliest timestamp we have */
+    let base = streams.iter()
+        .filter_map(|stream| stream.buffers.front().and_then(buffer_time))
+        .min()
+        .unwrap_or(gst::ClockTime::ZERO);
+    let rebase = |time: gst::ClockTime| gst::ClockTime::from_nseconds(
+        time.nseconds().saturating_sub(base.nseconds()));
+
+    let mut appsrcs = vec![];
+    for stream in streams.into_iter() {
+        let caps = match (&stream.caps, stream.buffers.is_empty()) {
+            (Some(caps), false) => caps.clone(),
+            _ => continue,
+        };
+
+        let appsrc = gst::ElementFactory::make("appsrc", None)?
+            .dynamic_cast::<gst_app::AppSrc>()
+            .expect("appsrc element is not an AppSrc");
+        appsrc.set_caps(Some(&caps));
+        appsrc.set_format(gst::Format::Time);
+
+        pipeline.add(&appsrc)?;
+        appsrc.link(&mux)?;
+        appsrcs.push((appsrc, stream));
+    }
+
+    pipeline.set_state(gst::State::Playing)
+        .map_err(|_| glib::bool_error!("Could not start replay pipeline"))?;
+
+    for (appsrc, stream) in appsrcs.iter() {
+        for buffer in stream.buffers.iter() {
+            let mut buffer = buffer.copy();
+            {
+                let buffer = buffer.get_mut().unwrap();
+                if let Some(pts) = buffer.pts() {
+                    buffer.set_pts(rebase(pts));
+                }
+                if let Some(dts) = buffer.dts() {
+                    buffer.set_dts(rebase(dts));
+                }
+            }
+            appsrc.push_buffer(buffer)
+                .map_err(|_| glib::bool_error!("Could not push replay buffer"))?;
+        }
+        appsrc.end_of_stream()
+            .map_err(|_| glib::bool_error!("Could not end replay stream"))?;
+    }
+
+    let bus = pipeline.bus().expect("Pipeline without bus. Shouldn't happen!");
+    let mut ret = Ok(());
+
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                ret = Err(glib::bool_error!("Could not write replay: {}", err.error()));
+                break;
+            },
+            _ => (),
+        }
+    }
+
+    pipeline.set_state(gst::State::Null)
+        .map_err(|_| glib::bool_error!("Could not stop replay pipeline"))?;
+
+    ret
+}
+
+impl DkcReplaySink {
+    fn save(&self, element: &super::DkcReplaySink, location: &str) -> bool {
+        let streams = {
+            let replay = self.replay.lock().unwrap();
+            vec![replay.video.clone(), replay.audio.clone()]
+        };
+
+        if streams.iter().all(|stream| stream.buffers.is_empty()) {
+            gst::warning!(CAT, obj: element, "Nothing to save to {}", location);
+            return false;
+        }
+
+        gst::info!(CAT, obj: element, "Saving replay to {}", location);
+
+        /* Write from a separate thread, the element keeps on recording meanwhile */
+        let element = element.clone();
+        let location = location.to_string();
+        thread::spawn(move || {
+            let ret = write_replay(&location, streams);
+
+            if let Err(ref err) = ret {
+                gst::error!(CAT, obj: &element, "Could not save replay to {}: {}", location, err);
+            }
+
+            let structure = gst::Structure::builder("dkc-replay-saved")
+                .field("location", &location)
+                .field("success", ret.is_ok())
+                .build();
+            let _ = element.post_message(gst::message::Element::builder(structure).src(&element).build());
+        });
+
+        true
+    }
+
+    fn class_save_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                          -> Option<glib::value::Value> {
+
+        let element = args[0].get::<super::DkcReplaySink>().expect("signal arg");
+        let location = args[1].get::<String>().expect("signal arg");
+
+        let imp = DkcReplaySink::from_instance(&element);
+        Some(imp.save(&element, &location).to_value())
+    }
+
+    fn make_appsink(&self, is_video: bool) -> gst_app::AppSink {
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .expect("Could not create replay app sink element.")
+            .dynamic_cast::<gst_app::AppSink>()
+            .expect("appsink element is not an AppSink");
+
+        appsink.set_property("sync", false);
+
+        let replay = self.replay.clone();
+        appsink.set_callbacks(
+            gst_app::AppSinkCallbacks::builder()
+                .new_sample(move |appsink| {
+                    let sample = appsink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+                    replay.lock().unwrap().push(is_video, &sample);
+                    Ok(gst::FlowSuccess::Ok)
+                })
+                .build(),
+        );
+
+        appsink
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcReplaySink {
+    const NAME: &'static str = "DkcReplaySink";
+    type Type = super::DkcReplaySink;
+    type ParentType = gst::Bin;
+}
+
+impl ObjectImpl for DkcReplaySink {
+    fn constructed(&self, obj: &Self::Type) {
+        /* queue ! videoconvert ! x264enc ! h264parse ! appsink */
+        let video_queue = gst::ElementFactory::make("queue", Some("videoqueue"))
+            .expect("Could not create video queue element.");
+        let video_convert = gst::ElementFactory::make("videoconvert", Some("videoconvert"))
+            .expect("Could not create video converter element.");
+        let video_enc = gst::ElementFactory::make("x264enc", Some("videoencoder"))
+            .expect("Could not create video encoder element.");
+        let video_parse = gst::ElementFactory::make("h264parse", Some("videoparser"))
+            .expect("Could not create video parser element.");
+        let video_appsink = self.make_appsink(true);
+        video_appsink.set_caps(Some(&gst::Caps::builder("video/x-h264")
+                                    .field("stream-format", "avc")
+                                    .field("alignment", "au")
+                                    .build()));
+
+        /* queue ! audioconvert ! audioresample ! opusenc ! appsink */
+        let audio_queue = gst::ElementFactory::make("queue", Some("audioqueue"))
+            .expect("Could not create audio queue element.");
+        let audio_convert = gst::ElementFactory::make("audioconvert", Some("audioconvert"))
+            .expect("Could not create audio converter element.");
+        let audio_resample = gst::ElementFactory::make("audioresample", Some("audioresample"))
+            .expect("Could not create audio resampler element.");
+        let audio_enc = gst::ElementFactory::make("opusenc", Some("audioencoder"))
+            .expect("Could not create audio encoder element.");
+        let audio_appsink = self.make_appsink(false);
+
+        /* Never hold the other outputs back, drop frames instead */
+        video_queue.set_property_from_str("leaky", "downstream");
+        audio_queue.set_property_from_str("leaky", "downstream");
+
+        video_enc.set_property_from_str("tune", "zerolatency");
+        video_enc.set_property_from_str("speed-preset", "veryfast");
+        video_enc.set_property("key-int-max", 60u32);
+
+        let video_elements = [&video_queue, &video_convert, &video_enc, &video_parse,
+                              video_appsink.upcast_ref::<gst::Element>()];
+        let audio_elements = [&audio_queue, &audio_convert, &audio_resample, &audio_enc,
+                              audio_appsink.upcast_ref::<gst::Element>()];
+
+        for element in video_elements.iter().chain(audio_elements.iter()) {
+            self.add_element(obj, element).expect("Could not add element to this sink");
+        }
+
+        gst::Element::link_many(&video_elements).expect("Could not link video encoding elements.");
+        gst::Element::link_many(&audio_elements).expect("Could not link audio encoding elements.");
+
+        let video_pad = video_queue.static_pad("sink").unwrap();
+        let audio_pad = audio_queue.static_pad("sink").unwrap();
+
+        let video_ghost_pad = gst::GhostPad::with_target(Some("video_sink"), &video_pad).unwrap();
+        let audio_ghost_pad = gst::GhostPad::with_target(Some("audio_sink"), &audio_pad).unwrap();
+
+        obj.add_pad(&video_ghost_pad).unwrap();
+        obj.add_pad(&audio_ghost_pad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecUInt::new(
+                    "duration",
+                    "Duration",
+                    "How many seconds of audio and video are kept",
+                    1,
+                    3600,
+                    DEFAULT_DURATION,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "duration" => {
+                let duration = value.get::<u32>().expect("type checked upstream");
+                let mut replay = self.replay.lock().unwrap();
+                replay.duration = gst::ClockTime::from_seconds(duration as u64);
+                replay.trim();
+            },
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "duration" => (self.replay.lock().unwrap().duration.seconds() as u32).to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            vec![
+                glib::subclass::Signal::builder(
+                    "save",
+                    &[String::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcReplaySink::class_save_handler)
+                .build()
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+}
+
+impl GstObjectImpl for DkcReplaySink {}
+
+impl ElementImpl for DkcReplaySink {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Replay Sink",
+                "Audio/Video",
+                "DankCaster replay buffer, keeping the last seconds of encoded audio and video",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // sink pad capabilities
+            let video_caps = gst::Caps::builder("video/x-raw")
+                .build();
+            let audio_caps = gst::Caps::builder("audio/x-raw")
+                .build();
+
+            let video_sink_pad_template = gst::PadTemplate::new(
+                "video_sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &video_caps,
+            )
+            .unwrap();
+
+            let audio_sink_pad_template = gst::PadTemplate::new(
+                "audio_sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &audio_caps,
+            )
+            .unwrap();
+
+            vec![video_sink_pad_template, audio_sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for DkcReplaySink {}
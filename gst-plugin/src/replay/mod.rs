@@ -0,0 +1,75 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcReplaySink(ObjectSubclass<imp::DkcReplaySink>) @extends gst::Bin, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcReplaySink {}
+unsafe impl Sync for DkcReplaySink {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkcreplaysink",
+        gst::Rank::None,
+        DkcReplaySink::static_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    #[test]
+    fn test_new() {
+        set_up();
+
+        let replay = gst::ElementFactory::make("dkcreplaysink", Some("replay"))
+            .expect("Could not make dkcreplaysink element");
+
+        assert!(replay.static_pad("video_sink").is_some());
+        assert!(replay.static_pad("audio_sink").is_some());
+    }
+
+    #[test]
+    fn test_duration_property() {
+        set_up();
+
+        let replay = gst::ElementFactory::make("dkcreplaysink", Some("replay"))
+            .expect("Could not make dkcreplaysink element");
+
+        assert_eq!(replay.property::<u32>("duration"), 30);
+        replay.set_property("duration", 10u32);
+        assert_eq!(replay.property::<u32>("duration"), 10);
+    }
+
+    #[test]
+    fn test_save_empty() {
+        set_up();
+
+        let replay = gst::ElementFactory::make("dkcreplaysink", Some("replay"))
+            .expect("Could not make dkcreplaysink element");
+
+        // Nothing was recorded yet, there is nothing to save.
+        assert!(!replay.emit_by_name_with_values("save", &["/tmp/dkc-replay-test.mkv".into()])
+                .unwrap().get::<bool>().unwrap());
+    }
+
+}
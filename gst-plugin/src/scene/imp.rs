@@ -1,127 +1,1736 @@
 use gst::glib;
 use gst::prelude::*;
 use gst::subclass::prelude::*;
-use std::sync::Mutex;
+use gst_controller::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use once_cell::sync::Lazy;
 
+const DEFAULT_WIDTH: i32 = 1280;
+const DEFAULT_HEIGHT: i32 = 720;
+const DEFAULT_FRAMERATE_NUM: i32 = 30;
+const DEFAULT_FRAMERATE_DEN: i32 = 1;
+const DEFAULT_AUDIO_RATE: i32 = 48000;
+const DEFAULT_AUDIO_CHANNELS: i32 = 2;
+const DEFAULT_LEVEL_INTERVAL: u64 = 100_000_000;
+const DEFAULT_QUEUE_TIME: u64 = 1_000_000_000;
+const MAX_SYNC_OFFSET_MS: u32 = 10_000;
+const DEFAULT_MONITOR_MODE: &str = "off";
+const DEFAULT_AUDIO_TRACKS: u32 = 1;
+const DEFAULT_BACKGROUND: &str = "black";
+const DEFAULT_BACKGROUND_COLOR: u32 = 0xff000000;
+const FILLER_SIZE: i32 = 16;
+const DEFAULT_DUCK_THRESHOLD: f64 = -30.0;
+const DEFAULT_DUCK_RATIO: f64 = 4.0;
+const DEFAULT_DUCK_ATTACK_MS: u32 = 50;
+const DEFAULT_DUCK_RELEASE_MS: u32 = 500;
+const MAX_DUCK_TIME_MS: u32 = 10_000;
+const MAX_AUDIO_TRACKS: u32 = 6;
+
+/* Canvas (output) settings, enforced by capsfilters right after the mixers */
+struct Settings {
+    width: i32,
+    height: i32,
+    framerate: gst::Fraction,
+    format: Option<String>,
+    audio_rate: i32,
+    audio_channels: i32,
+    audio_tracks: u32,
+    level_interval: u64,
+    video_enabled: bool,
+    audio_enabled: bool,
+    background: String,
+    background_color: u32,
+    background_location: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            framerate: gst::Fraction::new(DEFAULT_FRAMERATE_NUM, DEFAULT_FRAMERATE_DEN),
+            format: None,
+            audio_rate: DEFAULT_AUDIO_RATE,
+            audio_channels: DEFAULT_AUDIO_CHANNELS,
+            audio_tracks: DEFAULT_AUDIO_TRACKS,
+            level_interval: DEFAULT_LEVEL_INTERVAL,
+            video_enabled: true,
+            audio_enabled: true,
+            background: DEFAULT_BACKGROUND.to_string(),
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            background_location: None,
+        }
+    }
+}
+
+impl Settings {
+    fn video_caps(&self) -> gst::Caps {
+        let mut builder = gst::Caps::builder("video/x-raw")
+            .field("width", self.width)
+            .field("height", self.height)
+            .field("framerate", self.framerate)
+            .field("pixel-aspect-ratio", gst::Fraction::new(1, 1));
+
+        if let Some(ref format) = self.format {
+            builder = builder.field("format", format.as_str());
+        }
+
+        builder.build()
+    }
+
+    /* Transparent frames at the canvas framerate, small as they are not drawn */
+    fn filler_caps(&self) -> gst::Caps {
+        gst::Caps::builder("video/x-raw")
+            .field("format", "RGBA")
+            .field("width", FILLER_SIZE)
+            .field("height", FILLER_SIZE)
+            .field("framerate", self.framerate)
+            .build()
+    }
+
+    fn audio_caps(&self) -> gst::Caps {
+        gst::Caps::builder("audio/x-raw")
+            .field("rate", self.audio_rate)
+            .field("channels", self.audio_channels)
+            .build()
+    }
+}
+
+/* Output format of a single video src pad, unset fields follow the canvas */
+#[derive(Default)]
+struct OutputSettings {
+    width: Option<i32>,
+    height: Option<i32>,
+    framerate: Option<gst::Fraction>,
+}
+
+impl OutputSettings {
+    fn caps(&self) -> gst::Caps {
+        let mut builder = gst::Caps::builder("video/x-raw");
+
+        if let Some(width) = self.width {
+            builder = builder.field("width", width);
+        }
+        if let Some(height) = self.height {
+            builder = builder.field("height", height);
+        }
+        if let Some(framerate) = self.framerate {
+            builder = builder.field("framerate", framerate);
+        }
+
+        builder.build()
+    }
+}
+
+/* Branch hanging off one of the tees, behind a src pad */
+struct Output {
+    tee_pad: gst::Pad,
+    elements: Vec<gst::Element>,
+    settings: OutputSettings,
+    track: u32,
+}
+
+/* Hang an audio output off the tee of another track */
+fn move_output(output: &mut Output, tee: &gst::Element, track: u32) {
+    let queue_pad = output.elements[0].static_pad("sink").unwrap();
+
+    if let Some(old_tee) = output.tee_pad.parent_element() {
+        let _ = output.tee_pad.unlink(&queue_pad);
+        old_tee.release_request_pad(&output.tee_pad);
+    }
+
+    output.tee_pad = tee.request_pad_simple("src_%u").unwrap();
+    output.tee_pad.link(&queue_pad).expect("Could not link output queue to track tee");
+    output.track = track;
+}
+
+/* Audio mix on top of the program one, made of the inputs assigned to it at unity gain:
+ * audiomixer ! capsfilter ! tee */
+struct Track {
+    mixer: gst::Element,
+    capsfilter: gst::Element,
+    tee: gst::Element,
+}
+
+impl Track {
+    fn new() -> Self {
+        let track = Self {
+            mixer: gst::ElementFactory::make("audiomixer", None)
+                .expect("Could not create track mixer element."),
+            capsfilter: gst::ElementFactory::make("capsfilter", None)
+                .expect("Could not create track capsfilter element."),
+            tee: gst::ElementFactory::make("tee", None)
+                .expect("Could not create track tee element."),
+        };
+
+        /* Tracks may not be recorded */
+        track.tee.set_property("allow-not-linked", true);
+        track
+    }
+
+    fn elements(&self) -> [&gst::Element; 3] {
+        [&self.mixer, &self.capsfilter, &self.tee]
+    }
+}
+
+/* Comma separated track numbers, from 1 as track 0 is the program mix every input is part of */
+fn parse_tracks(tracks: &str) -> Option<Vec<u32>> {
+    if tracks.trim().is_empty() {
+        return Some(Vec::new());
+    }
+
+    tracks.split(',')
+        .map(|track| track.trim().parse::<u32>().ok().filter(|track| (1..MAX_AUDIO_TRACKS).contains(track)))
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum InputKind {
+    Video,
+    Audio,
+}
+
+/* Object of an input branch holding the property behind a parameter */
+#[derive(Clone, Copy, PartialEq)]
+enum ParamTarget {
+    MixerPad,
+    Crop,
+    Transform,
+    Sync,
+    Routing,
+    Ducking,
+}
+
+/* Input parameter, along with the variant type it is set from */
+struct InputParam {
+    name: &'static str,
+    variant_type: &'static str,
+    target: ParamTarget,
+    property: &'static str,
+}
+
+const fn input_param(name: &'static str, variant_type: &'static str,
+                     target: ParamTarget, property: &'static str) -> InputParam {
+    InputParam { name, variant_type, target, property }
+}
+
+const VIDEO_PARAMS: &[InputParam] = &[
+    input_param("xpos", "i", ParamTarget::MixerPad, "xpos"),
+    input_param("ypos", "i", ParamTarget::MixerPad, "ypos"),
+    input_param("width", "i", ParamTarget::MixerPad, "width"),
+    input_param("height", "i", ParamTarget::MixerPad, "height"),
+    input_param("alpha", "d", ParamTarget::MixerPad, "alpha"),
+    input_param("zorder", "u", ParamTarget::MixerPad, "zorder"),
+    input_param("blend-mode", "s", ParamTarget::MixerPad, "blend-mode"),
+    input_param("crop-top", "i", ParamTarget::Crop, "top"),
+    input_param("crop-bottom", "i", ParamTarget::Crop, "bottom"),
+    input_param("crop-left", "i", ParamTarget::Crop, "left"),
+    input_param("crop-right", "i", ParamTarget::Crop, "right"),
+    input_param("scale-mode", "s", ParamTarget::Transform, "scale-mode"),
+    input_param("rotation", "u", ParamTarget::Transform, "rotation"),
+    input_param("flip", "s", ParamTarget::Transform, "flip"),
+    input_param("video-offset", "u", ParamTarget::Sync, "offset"),
+];
+const AUDIO_PARAMS: &[InputParam] = &[
+    input_param("mute", "b", ParamTarget::MixerPad, "mute"),
+    input_param("volume", "d", ParamTarget::MixerPad, "volume"),
+    input_param("audio-offset", "u", ParamTarget::Sync, "offset"),
+    input_param("monitor", "s", ParamTarget::Routing, "monitor"),
+    input_param("tracks", "s", ParamTarget::Routing, "tracks"),
+    input_param("duck-source", "s", ParamTarget::Ducking, "source"),
+    input_param("duck-threshold", "d", ParamTarget::Ducking, "threshold"),
+    input_param("duck-ratio", "d", ParamTarget::Ducking, "ratio"),
+    input_param("duck-attack", "u", ParamTarget::Ducking, "attack"),
+    input_param("duck-release", "u", ParamTarget::Ducking, "release"),
+];
+
+/* Parameters which can be animated, as they are continuous */
+const ANIMATED_PARAMS: &[&str] = &["xpos", "ypos", "width", "height", "alpha", "volume"];
+
+/* Stream time of a pad at a running time, controlled values being synced against it */
+fn stream_time(pad: &gst::Pad, running_time: gst::ClockTime) -> gst::ClockTime {
+    pad.sticky_event::<gst::event::Segment>(0)
+        .and_then(|event| {
+            let segment = event.segment().downcast_ref::<gst::ClockTime>()?.clone();
+            segment.to_stream_time(segment.position_from_running_time(running_time))
+        })
+        .unwrap_or(running_time)
+}
+
+/* Interval between two keyframes of an animation, linearly interpolated */
+const ANIMATION_STEP_MS: u64 = 20;
+
+fn ease(easing: &str, progress: f64) -> Option<f64> {
+    match easing {
+        "linear" => Some(progress),
+        "ease-in" => Some(progress.powi(3)),
+        "ease-out" => Some(1.0 - (1.0 - progress).powi(3)),
+        "ease-in-out" => Some(if progress < 0.5 {
+            4.0 * progress.powi(3)
+        } else {
+            1.0 - (2.0 - 2.0 * progress).powi(3) / 2.0
+        }),
+        _ => None
+    }
+}
+
+fn variant_to_f64(variant: &glib::Variant) -> Option<f64> {
+    match variant.type_().as_str() {
+        "i" => variant.get::<i32>().map(f64::from),
+        "u" => variant.get::<u32>().map(f64::from),
+        "d" => variant.get::<f64>(),
+        _ => None
+    }
+}
+
+fn variant_to_value(variant: &glib::Variant) -> Option<glib::SendValue> {
+    match variant.type_().as_str() {
+        "b" => variant.get::<bool>().map(|value| value.to_send_value()),
+        "i" => variant.get::<i32>().map(|value| value.to_send_value()),
+        "u" => variant.get::<u32>().map(|value| value.to_send_value()),
+        "x" => variant.get::<i64>().map(|value| value.to_send_value()),
+        "t" => variant.get::<u64>().map(|value| value.to_send_value()),
+        "d" => variant.get::<f64>().map(|value| value.to_send_value()),
+        "s" => variant.get::<String>().map(|value| value.to_send_value()),
+        _ => None
+    }
+}
+
+fn value_to_variant(value: &glib::Value) -> Option<glib::Variant> {
+    if let Ok(value) = value.get::<bool>() {
+        Some(value.to_variant())
+    } else if let Ok(value) = value.get::<i32>() {
+        Some(value.to_variant())
+    } else if let Ok(value) = value.get::<u32>() {
+        Some(value.to_variant())
+    } else if let Ok(value) = value.get::<i64>() {
+        Some(value.to_variant())
+    } else if let Ok(value) = value.get::<u64>() {
+        Some(value.to_variant())
+    } else if let Ok(value) = value.get::<f64>() {
+        Some(value.to_variant())
+    } else if let Ok(value) = value.get::<String>() {
+        Some(value.to_variant())
+    } else if value.type_().is_a(glib::Type::ENUM) {
+        value.serialize().ok().map(|nick| nick.as_str().to_variant())
+    } else {
+        None
+    }
+}
+
+const SCALE_MODES: &[&str] = &["stretch", "fit", "fill", "none"];
+const ROTATIONS: &[u32] = &[0, 90, 180, 270];
+const FLIPS: &[&str] = &["none", "horizontal", "vertical"];
+
+/* videoflip method flipping first, then rotating clockwise */
+fn video_direction(rotation: u32, flip: &str) -> &'static str {
+    match (rotation, flip) {
+        (0, "horizontal") | (180, "vertical") => "horiz",
+        (0, "vertical") | (180, "horizontal") => "vert",
+        (90, "horizontal") | (270, "vertical") => "ur-ll",
+        (90, "vertical") | (270, "horizontal") => "ul-lr",
+        (90, _) => "90r",
+        (180, _) => "180",
+        (270, _) => "90l",
+        _ => "identity",
+    }
+}
+
+struct TransformSettings {
+    scale_mode: String,
+    rotation: u32,
+    flip: String,
+}
+
+/* Scaling mode, rotation and flipping of a video input within its bounding box
+ * (the mixer pad width and height) */
+struct Transform {
+    flip: gst::Element,
+    aspect_crop: gst::Element,
+    mixer_pad: gst::Pad,
+    settings: Mutex<TransformSettings>,
+}
+
+impl Transform {
+    fn new(flip: gst::Element, aspect_crop: gst::Element, mixer_pad: gst::Pad) -> Self {
+        Transform {
+            flip,
+            aspect_crop,
+            mixer_pad,
+            settings: Mutex::new(TransformSettings {
+                scale_mode: "stretch".to_string(),
+                rotation: 0,
+                flip: "none".to_string(),
+            }),
+        }
+    }
+
+    fn accepts(&self, property: &str, value: &glib::Value) -> bool {
+        match property {
+            "scale-mode" => value.get::<String>().map(|mode| SCALE_MODES.contains(&mode.as_str())).unwrap_or(false),
+            "rotation" => value.get::<u32>().map(|rotation| ROTATIONS.contains(&rotation)).unwrap_or(false),
+            "flip" => value.get::<String>().map(|flip| FLIPS.contains(&flip.as_str())).unwrap_or(false),
+            _ => false
+        }
+    }
+
+    fn get(&self, property: &str) -> Option<glib::Value> {
+        let settings = self.settings.lock().unwrap();
+        match property {
+            "scale-mode" => Some(settings.scale_mode.to_value()),
+            "rotation" => Some(settings.rotation.to_value()),
+            "flip" => Some(settings.flip.to_value()),
+            _ => None
+        }
+    }
+
+    fn set(&self, property: &str, value: &glib::Value) {
+        let unscaled = {
+            let mut settings = self.settings.lock().unwrap();
+            match property {
+                "scale-mode" => settings.scale_mode = value.get::<String>().expect("type checked upstream"),
+                "rotation" => settings.rotation = value.get::<u32>().expect("type checked upstream"),
+                "flip" => settings.flip = value.get::<String>().expect("type checked upstream"),
+                _ => ()
+            }
+            property == "scale-mode" && settings.scale_mode == "none"
+        };
+
+        /* Unscaled sources are drawn at their natural size (resizing the pad applies the settings
+         * again, see `watch_size`) */
+        if unscaled {
+            self.mixer_pad.set_property("width", 0i32);
+            self.mixer_pad.set_property("height", 0i32);
+        }
+
+        self.apply();
+    }
+
+    /* Filling depends on the bounding box size, whether it is updated or animated */
+    fn watch_size(transform: &Arc<Transform>) {
+        for property in ["width", "height"].iter() {
+            let weak_transform = Arc::downgrade(transform);
+            transform.mixer_pad.connect_notify(Some(*property), move |_, _| {
+                if let Some(transform) = weak_transform.upgrade() {
+                    transform.apply();
+                }
+            });
+        }
+    }
+
+    fn apply(&self) {
+        let settings = self.settings.lock().unwrap();
+
+        self.flip.set_property_from_str("video-direction", video_direction(settings.rotation, &settings.flip));
+
+        let keep_aspect = settings.scale_mode == "fit" || settings.scale_mode == "fill";
+        self.mixer_pad.set_property_from_str("sizing-policy", if keep_aspect { "keep-aspect-ratio" } else { "none" });
+
+        /* Filling the bounding box crops the source to its aspect ratio (0/1 disables it) */
+        let (width, height) = (self.mixer_pad.property::<i32>("width"), self.mixer_pad.property::<i32>("height"));
+        let aspect_ratio = if settings.scale_mode == "fill" && width > 0 && height > 0 {
+            gst::Fraction::new(width, height)
+        } else {
+            gst::Fraction::new(0, 1)
+        };
+        self.aspect_crop.set_property("aspect-ratio", aspect_ratio);
+    }
+}
+
+/* Video filter kinds, along with the element applying them */
+const FILTER_KINDS: &[(&str, &str)] = &[
+    ("color-balance", "videobalance"), // brightness, contrast, saturation, hue
+    ("gamma", "gamma"),
+    ("blur", "gaussianblur"),
+    ("sharpen", "gaussianblur"), // negative sigma
+    ("lut", "dkclut"),
+    ("chroma-key", "dkcchromakey"),
+    ("luma-key", "dkclumakey"),
+    ("alpha-mask", "dkcalphamask"), // rounded corners, circle webcam...
+];
+
+/* Audio filter kinds, along with the element applying them */
+const AUDIO_FILTER_KINDS: &[(&str, &str)] = &[
+    ("gain", "volume"),
+    ("noise-gate", "audiodynamic"), // expander
+    ("compressor", "audiodynamic"),
+    ("limiter", "audiodynamic"), // compressor with a flat ratio
+    ("equalizer", "equalizer-nbands"), // band<n>::freq, band<n>::bandwidth, band<n>::gain
+    ("high-pass", "audiocheblimit"),
+];
+
+/* Initial properties of the elements of some filter kinds */
+const FILTER_DEFAULTS: &[(&str, &str, &str)] = &[
+    ("sharpen", "sigma", "-1.2"), // negative sigma
+    ("noise-gate", "mode", "expander"),
+    ("noise-gate", "threshold", "0.05"),
+    ("noise-gate", "ratio", "4.0"),
+    ("compressor", "characteristics", "soft-knee"),
+    ("compressor", "threshold", "0.5"),
+    ("compressor", "ratio", "0.25"), // output slope above the threshold
+    ("limiter", "threshold", "0.9"),
+    ("limiter", "ratio", "0.0"),
+    ("equalizer", "num-bands", "3"),
+    ("high-pass", "mode", "high-pass"),
+    ("high-pass", "cutoff", "80.0"),
+    ("high-pass", "poles", "4"),
+];
+
+/* Effect of an input filter chain, wrapped as: videoconvert ! filter (or audioconvert) */
+struct Filter {
+    name: String,
+    bin: gst::Element,
+    element: gst::Element,
+}
+
+fn make_filter(name: &str, kind: &str, input_kind: InputKind) -> Option<Filter> {
+    let (kinds, convert_factory_name) = match input_kind {
+        InputKind::Video => (FILTER_KINDS, "videoconvert"),
+        InputKind::Audio => (AUDIO_FILTER_KINDS, "audioconvert"),
+    };
+
+    let (_, factory_name) = kinds.iter().find(|(filter_kind, _)| *filter_kind == kind)?;
+    let element = gst::ElementFactory::make(factory_name, None).ok()?;
+    for (_, property, value) in FILTER_DEFAULTS.iter().filter(|(filter_kind, _, _)| *filter_kind == kind) {
+        element.set_property_from_str(property, value);
+    }
+
+    let convert = gst::ElementFactory::make(convert_factory_name, None).ok()?;
+    let bin = gst::Bin::new(None);
+    bin.add_many(&[&convert, &element]).ok()?;
+    convert.link(&element).ok()?;
+
+    let sink_pad = gst::GhostPad::with_target(Some("sink"), &convert.static_pad("sink")?).ok()?;
+    let src_pad = gst::GhostPad::with_target(Some("src"), &element.static_pad("src")?).ok()?;
+    bin.add_pad(&sink_pad).ok()?;
+    bin.add_pad(&src_pad).ok()?;
+
+    Some(Filter { name: name.to_string(), bin: bin.upcast::<gst::Element>(), element })
+}
+
+fn set_filter_property(element: &gst::Element, name: &str, param_value: &glib::Variant) -> bool {
+    /* Properties of children (e.g. "band0::gain" of an equalizer) go through the child proxy */
+    let (object, pspec) = match element.dynamic_cast_ref::<gst::ChildProxy>() {
+        Some(child_proxy) if name.contains("::") => match child_proxy.lookup(name) {
+            Ok(found) => found,
+            Err(_) => return false
+        },
+        _ => match element.find_property(name) {
+            Some(pspec) => (element.clone().upcast::<glib::Object>(), pspec),
+            None => return false
+        }
+    };
+    if !pspec.flags().contains(glib::ParamFlags::WRITABLE) {
+        return false; // Read-only property
+    }
+
+    /* Enums, flags, fractions... are set from their string representation */
+    let value = match param_value.str() {
+        Some(string) if pspec.value_type() != String::static_type() =>
+            glib::Value::deserialize(string, pspec.value_type()).ok(),
+        _ => variant_to_value(param_value)
+            .and_then(|value| value.transform_with_type(pspec.value_type()).ok()),
+    };
+
+    match value {
+        Some(value) => { object.set_property_from_value(pspec.name(), &value); true },
+        None => false
+    }
+}
+
+/* Position in a list of the given length, negative or out of bounds ones appending */
+fn list_position(position: i32, len: usize) -> usize {
+    if position < 0 || position as usize > len { len } else { position as usize }
+}
+
+/* Link filters between the head and tail elements of an input once no data flows through
+ * them, in place of whatever is linked there by then: operations queued back to back each
+ * start from the actual links. Removed filters leave the bin, the others join it */
+fn relink(bin: gst::Bin, head: gst::Element, tail: gst::Element, filters: Vec<gst::Element>, removed: Vec<gst::Element>) {
+    let head_pad = head.static_pad("src").expect("Could not get chain head src pad");
+
+    head_pad.add_probe(gst::PadProbeType::IDLE, move |_, _| {
+        let mut element = head.clone();
+        while element != tail {
+            let next = match element.static_pad("src").and_then(|pad| pad.peer()).and_then(|pad| pad.parent_element()) {
+                Some(next) => next,
+                None => break
+            };
+            element.unlink(&next);
+            element = next;
+        }
+
+        for filter in removed.iter().filter(|filter| !filters.contains(filter)) {
+            let _ = filter.set_state(gst::State::Null);
+            let _ = bin.remove(filter);
+        }
+        for filter in filters.iter().filter(|filter| filter.parent().is_none()) {
+            let _ = bin.add(filter);
+        }
+
+        let mut chain = vec![&head];
+        chain.extend(filters.iter());
+        chain.push(&tail);
+        if gst::Element::link_many(&chain).is_err() {
+            gst::error!(CAT, obj: &bin, "Could not link input filter chain");
+        }
+
+        for filter in filters.iter().rev() {
+            let _ = filter.sync_state_with_parent();
+        }
+
+        gst::PadProbeReturn::Remove
+    });
+}
+
+/* Parameter update, applied right away or at the next frame boundary */
+type InputUpdate = Box<dyn FnOnce() + Send>;
+
+/* Whether an audio input is heard on the monitor output, the program one or both */
+const MONITOR_MODES: &[&str] = &["off", "monitor-only", "monitor-and-output"];
+
+/* Gain reduction of an input on the program mix, driven by the level of another input
+ * (its sink pad name, none if empty) going over the threshold */
+#[derive(Clone)]
+pub(super) struct Ducking {
+    pub(super) source: String,
+    pub(super) threshold: f64,
+    pub(super) ratio: f64,
+    pub(super) attack: u32,
+    pub(super) release: u32,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Self {
+            source: String::new(),
+            threshold: DEFAULT_DUCK_THRESHOLD,
+            ratio: DEFAULT_DUCK_RATIO,
+            attack: DEFAULT_DUCK_ATTACK_MS,
+            release: DEFAULT_DUCK_RELEASE_MS,
+        }
+    }
+}
+
+impl Ducking {
+    fn accepts(property: &str, value: &glib::Value) -> bool {
+        match property {
+            "threshold" => value.get::<f64>().map_or(false, |threshold| (-100.0..=0.0).contains(&threshold)),
+            "ratio" => value.get::<f64>().map_or(false, |ratio| (1.0..=100.0).contains(&ratio)),
+            "attack" | "release" => value.get::<u32>().map_or(false, |time| time <= MAX_DUCK_TIME_MS),
+            _ => value.get::<String>().is_ok(),
+        }
+    }
+
+    fn get(&self, property: &str) -> glib::Variant {
+        match property {
+            "threshold" => self.threshold.to_variant(),
+            "ratio" => self.ratio.to_variant(),
+            "attack" => self.attack.to_variant(),
+            "release" => self.release.to_variant(),
+            _ => self.source.to_variant(),
+        }
+    }
+
+    fn set(&mut self, property: &str, value: &glib::Value) {
+        match property {
+            "threshold" => self.threshold = value.get().unwrap(),
+            "ratio" => self.ratio = value.get().unwrap(),
+            "attack" => self.attack = value.get().unwrap(),
+            "release" => self.release = value.get().unwrap(),
+            _ => self.source = value.get().unwrap(),
+        }
+    }
+
+    /* Gain for a sidechain level in dBFS, the part over the threshold being divided by the ratio */
+    pub(super) fn target_gain(&self, level: f64) -> f64 {
+        let over = (level - self.threshold).max(0.0);
+        10f64.powf(-over * (1.0 - 1.0 / self.ratio) / 20.0)
+    }
+
+    /* Move the current gain toward the target one, a level interval at a time */
+    pub(super) fn step(&self, gain: f64, level: f64, interval_ms: f64) -> f64 {
+        let target = self.target_gain(level);
+        let time = if target < gain { self.attack } else { self.release };
+
+        if time == 0 {
+            target
+        } else {
+            gain + (target - gain) * (interval_ms / time as f64).min(1.0)
+        }
+    }
+}
+
+/* Branch of an audio input into a track mixer, muted unless the input is assigned to it */
+struct TrackBranch {
+    queue: gst::Element,
+    mixer_pad: gst::Pad,
+}
+
+/* Split of an audio input between the program mixer, the monitor one and the track ones:
+ * tee ! queue ! volume ! program mixer pad, tee ! queue ! monitor mixer pad,
+ * tee ! queue ! track mixer pad (one per track).
+ * The program volume mutes monitor-only inputs and ducks them, its gain ramping from one
+ * ducking step to the next. */
+struct Routing {
+    elements: Vec<gst::Element>,
+    program: gst::Element,
+    monitor_pad: gst::Pad,
+    tracks: Mutex<Vec<TrackBranch>>,
+    ducking: Mutex<Ducking>,
+    gain: Mutex<f64>,
+    gain_control: gst_controller::InterpolationControlSource,
+}
+
+impl Routing {
+    fn mode(&self) -> &'static str {
+        match (self.monitor_pad.property::<bool>("mute"), self.program.property::<bool>("mute")) {
+            (true, _) => "off",
+            (false, true) => "monitor-only",
+            (false, false) => "monitor-and-output",
+        }
+    }
+
+    fn set_mode(&self, mode: &str) {
+        self.monitor_pad.set_property("mute", mode == "off");
+        self.program.set_property("mute", mode == "monitor-only");
+    }
+
+    fn set_ducking(&self, property: &str, value: &glib::Value) {
+        let mut ducking = self.ducking.lock().unwrap();
+        ducking.set(property, value);
+
+        /* Another source starts from an unducked input */
+        if property == "source" {
+            if let Some(binding) = self.program.control_binding("volume") {
+                self.program.remove_control_binding(&binding);
+            }
+            self.gain_control.unset_all();
+            *self.gain.lock().unwrap() = 1.0;
+            self.program.set_property("volume", 1.0f64);
+        }
+    }
+
+    /* The gain ramps linearly to its next step over a level interval, the volume element
+     * interpolating it sample by sample rather than jumping once per interval */
+    fn duck(&self, level: f64, running_time: gst::ClockTime, interval: gst::ClockTime) {
+        let mut gain = self.gain.lock().unwrap();
+        let target = self.ducking.lock().unwrap().step(*gain, level, interval.nseconds() as f64 / 1_000_000.0);
+        let start = stream_time(&self.program.static_pad("sink").unwrap(), running_time);
+
+        self.gain_control.unset_all();
+        self.gain_control.set(start, *gain);
+        self.gain_control.set(start + interval, target);
+        if self.program.control_binding("volume").is_none() {
+            let binding = gst_controller::DirectControlBinding::new_absolute(&self.program, "volume",
+                                                                             &self.gain_control);
+            let _ = self.program.add_control_binding(&binding);
+        }
+
+        *gain = target;
+    }
+
+    fn queues(&self) -> Vec<gst::Element> {
+        let mut queues = self.elements.iter()
+            .filter(|element| element.factory().map_or(false, |factory| factory.name() == "queue"))
+            .cloned()
+            .collect::<Vec<gst::Element>>();
+        queues.extend(self.tracks.lock().unwrap().iter().map(|branch| branch.queue.clone()));
+        queues
+    }
+
+    fn track_count(&self) -> usize {
+        self.tracks.lock().unwrap().len()
+    }
+
+    fn tracks(&self) -> String {
+        self.tracks.lock().unwrap().iter().enumerate()
+            .filter(|(_, branch)| !branch.mixer_pad.property::<bool>("mute"))
+            .map(|(index, _)| (index + 1).to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    fn set_tracks(&self, tracks: &[u32]) {
+        for (index, branch) in self.tracks.lock().unwrap().iter().enumerate() {
+            branch.mixer_pad.set_property("mute", !tracks.contains(&(index as u32 + 1)));
+        }
+    }
+
+    /* New branches queue as much as the program one, which follows the sync offset */
+    fn add_track(&self, bin: &gst::Bin, track: &Track) {
+        let tee = &self.elements[0];
+        let program_queue = &self.elements[1];
+        let queue = gst::ElementFactory::make("queue", None)
+            .expect("Could not create input track queue element.");
+        for property in ["max-size-buffers", "max-size-bytes", "max-size-time"].iter() {
+            queue.set_property_from_value(property, &program_queue.property_value(property));
+        }
+
+        bin.add(&queue).expect("Could not add input track queue to the bin");
+        tee.link(&queue).expect("Could not link input track queue");
+
+        let mixer_pad = track.mixer.request_pad_simple("sink_%u").unwrap();
+        mixer_pad.set_property("mute", true);
+        queue.static_pad("src").unwrap().link(&mixer_pad)
+            .expect("Could not link input elements to track mixer");
+        let _ = queue.sync_state_with_parent();
+
+        self.tracks.lock().unwrap().push(TrackBranch { queue, mixer_pad });
+    }
+
+    fn remove_track(&self, bin: &gst::Bin) {
+        let branch = match self.tracks.lock().unwrap().pop() {
+            Some(branch) => branch,
+            None => return
+        };
+
+        if let Some(tee_pad) = branch.queue.static_pad("sink").and_then(|queue_pad| queue_pad.peer()) {
+            self.elements[0].release_request_pad(&tee_pad);
+        }
+        let _ = branch.queue.set_state(gst::State::Null);
+        let _ = bin.remove(&branch.queue);
+
+        if let Some(mixer) = branch.mixer_pad.parent_element() {
+            mixer.release_request_pad(&branch.mixer_pad);
+        }
+    }
+
+    fn remove_tracks(&self, bin: &gst::Bin) {
+        while self.track_count() > 0 {
+            self.remove_track(bin);
+        }
+    }
+}
+
+/* Delay an input by shifting the running time of what it hands to the mixer, the input queue
+ * holds the delayed data so that upstream and the other inputs keep flowing */
+fn set_sync_offset(queues: &[gst::Element], src_pad: &gst::Pad, offset_ms: u32) {
+    let offset = gst::ClockTime::from_mseconds(offset_ms as u64);
+
+    for queue in queues.iter() {
+        queue.set_property("max-size-buffers", 0u32);
+        queue.set_property("max-size-bytes", 0u32);
+        queue.set_property("max-size-time", DEFAULT_QUEUE_TIME + offset.nseconds());
+    }
+    src_pad.set_offset(offset.nseconds() as i64);
+}
+
+/* Branch behind a sink pad:
+ * queue ! filters ! [videocrop ! videoflip ! aspectratiocrop ! videoconvert | level ! routing] ! mixer pad
+ * Filters come from one of the named chains of the input, the others being kept out of the bin */
+struct Input {
+    kind: InputKind,
+    elements: Vec<gst::Element>,
+    filters: Vec<Filter>,
+    chain: String,
+    chains: HashMap<String, Vec<Filter>>,
+    crop: Option<gst::Element>,
+    transform: Option<Arc<Transform>>,
+    level: Option<gst::Element>,
+    routing: Option<Arc<Routing>>,
+    mixer_pad: gst::Pad,
+}
+
+impl Input {
+    /* Filters of a chain, the selected one being linked */
+    fn chain_filters(&mut self, chain: &str) -> &mut Vec<Filter> {
+        if chain == self.chain {
+            &mut self.filters
+        } else {
+            self.chains.entry(chain.to_string()).or_default()
+        }
+    }
+
+    /* queue ! filters ! videocrop (or level) */
+    fn relink_filters(&self, bin: &gst::Bin, removed: Vec<gst::Element>) {
+        relink(bin.clone(), self.elements[0].clone(), self.elements[1].clone(),
+               self.filters.iter().map(|filter| filter.bin.clone()).collect(), removed);
+    }
+
+    fn add_filter(&mut self, bin: &gst::Bin, chain: &str, filter_name: &str, kind: &str, position: i32) -> bool {
+        let input_kind = self.kind;
+        let filters = self.chain_filters(chain);
+        if filters.iter().any(|filter| filter.name == filter_name) {
+            return false;
+        }
+
+        let filter = match make_filter(filter_name, kind, input_kind) {
+            Some(filter) => filter,
+            None => return false // Unknown filter kind
+        };
+        let position = list_position(position, filters.len());
+        filters.insert(position, filter);
+
+        if chain == self.chain {
+            self.relink_filters(bin, Vec::new());
+        }
+
+        true
+    }
+
+    fn remove_filter(&mut self, bin: &gst::Bin, chain: &str, filter_name: &str) -> bool {
+        let filters = self.chain_filters(chain);
+        let filter = match filters.iter().position(|filter| filter.name == filter_name) {
+            Some(index) => filters.remove(index),
+            None => return false
+        };
+
+        if chain == self.chain {
+            self.relink_filters(bin, vec![filter.bin]);
+        }
+
+        true
+    }
+
+    fn move_filter(&mut self, bin: &gst::Bin, chain: &str, filter_name: &str, position: i32) -> bool {
+        let filters = self.chain_filters(chain);
+        let filter = match filters.iter().position(|filter| filter.name == filter_name) {
+            Some(index) => filters.remove(index),
+            None => return false
+        };
+        let position = list_position(position, filters.len());
+        filters.insert(position, filter);
+
+        if chain == self.chain {
+            self.relink_filters(bin, Vec::new());
+        }
+
+        true
+    }
+
+    fn update_filter(&mut self, chain: &str, filter_name: &str, param_name: &str, param_value: &glib::Variant) -> bool {
+        match self.chain_filters(chain).iter().find(|filter| filter.name == filter_name) {
+            Some(filter) => set_filter_property(&filter.element, param_name, param_value),
+            None => false
+        }
+    }
+
+    /* Link the filters of another chain instead of the current ones */
+    fn select_chain(&mut self, bin: &gst::Bin, chain: &str) -> bool {
+        if chain == self.chain {
+            return true;
+        }
+
+        let filters = self.chains.remove(chain).unwrap_or_default();
+        let previous = std::mem::replace(&mut self.filters, filters);
+        let removed = previous.iter().map(|filter| filter.bin.clone()).collect();
+        let previous_chain = std::mem::replace(&mut self.chain, chain.to_string());
+        self.chains.insert(previous_chain, previous);
+        self.relink_filters(bin, removed);
+
+        true
+    }
+
+    /* Pad handing the input to the mixer, or to the routing of audio inputs */
+    fn src_pad(&self) -> gst::Pad {
+        self.elements.last().unwrap().static_pad("src").unwrap()
+    }
+
+    fn params(&self) -> &'static [InputParam] {
+        match self.kind {
+            InputKind::Video => VIDEO_PARAMS,
+            InputKind::Audio => AUDIO_PARAMS,
+        }
+    }
+
+    fn param(&self, param_name: &str) -> Option<&'static InputParam> {
+        self.params().iter().find(|param| param.name == param_name)
+    }
+
+    /* Object and property behind a parameter, transform settings have none */
+    fn target(&self, param_name: &str) -> Option<(gst::Object, &'static InputParam)> {
+        let param = self.param(param_name)?;
+        let object = match param.target {
+            ParamTarget::MixerPad => self.mixer_pad.clone().upcast::<gst::Object>(),
+            ParamTarget::Crop => self.crop.clone()?.upcast::<gst::Object>(),
+            ParamTarget::Transform | ParamTarget::Sync | ParamTarget::Routing | ParamTarget::Ducking => return None,
+        };
+
+        Some((object, param))
+    }
+
+    /* Update, if the parameter exists and the value has the right type */
+    fn check(&self, param_name: &str, param_value: &glib::Variant) -> Option<InputUpdate> {
+        let param = self.param(param_name)?;
+        if param_value.type_().as_str() != param.variant_type {
+            return None; // Invalid value type
+        }
+
+        let value = variant_to_value(param_value)?;
+
+        match param.target {
+            ParamTarget::Transform => {
+                let transform = self.transform.clone()?;
+                if !transform.accepts(param.property, &value) {
+                    return None;
+                }
+                Some(Box::new(move || transform.set(param.property, &value)))
+            },
+            ParamTarget::Sync => {
+                let offset_ms = value.get::<u32>().ok()?;
+                if offset_ms > MAX_SYNC_OFFSET_MS {
+                    return None;
+                }
+                let mut queues = vec![self.elements[0].clone()];
+                queues.extend(self.routing.iter().flat_map(|routing| routing.queues()));
+                let src_pad = self.src_pad();
+                Some(Box::new(move || set_sync_offset(&queues, &src_pad, offset_ms)))
+            },
+            ParamTarget::Routing => {
+                let routing = self.routing.clone()?;
+                let value = value.get::<String>().ok()?;
+                match param.property {
+                    "tracks" => {
+                        let tracks = parse_tracks(&value)?;
+                        if tracks.iter().any(|track| *track as usize > routing.track_count()) {
+                            return None; // No such track
+                        }
+                        Some(Box::new(move || routing.set_tracks(&tracks)))
+                    },
+                    _ => {
+                        if !MONITOR_MODES.contains(&value.as_str()) {
+                            return None;
+                        }
+                        Some(Box::new(move || routing.set_mode(&value)))
+                    }
+                }
+            },
+            ParamTarget::Ducking => {
+                let routing = self.routing.clone()?;
+                if !Ducking::accepts(param.property, &value) {
+                    return None;
+                }
+                Some(Box::new(move || routing.set_ducking(param.property, &value)))
+            },
+            _ => {
+                let (object, _) = self.target(param_name)?;
+
+                /* Enums are set from their nick */
+                let value_type = object.find_property(param.property)?.value_type();
+                if value_type.is_a(glib::Type::ENUM) {
+                    let nick = value.get::<String>().ok()?;
+                    glib::Value::deserialize(&nick, value_type).ok()?; // Unknown nick
+                    return Some(Box::new(move || object.set_property_from_str(param.property, &nick)));
+                }
+
+                Some(Box::new(move || object.set_property_from_value(param.property, &value)))
+            }
+        }
+    }
+
+    fn update(&self, param_name: &str, param_value: &glib::Variant) -> bool {
+        match self.check(param_name, param_value) {
+            Some(update) => {
+                self.cancel(param_name);
+                update();
+                true
+            },
+            None => false
+        }
+    }
+
+    /* Keyframes from the current value to the target one, sampled along the easing curve, starting
+     * at a running time */
+    fn animate(&self, param_name: &str, target: &glib::Variant,
+               running_time: gst::ClockTime, duration: gst::ClockTime, easing: &str) -> bool {
+
+        if !ANIMATED_PARAMS.contains(&param_name) || self.check(param_name, target).is_none() {
+            return false;
+        }
+
+        let (from, to) = match (self.query(param_name).as_ref().and_then(variant_to_f64),
+                                variant_to_f64(target)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return false
+        };
+
+        if ease(easing, 0.0).is_none() {
+            return false; // Unknown easing curve
+        }
+
+        /* Controlled values are synced against the stream time of the target (pad, or element
+         * sink pad) */
+        let (object, param) = self.target(param_name).unwrap();
+        let start = match object.downcast_ref::<gst::Pad>() {
+            Some(pad) => stream_time(pad, running_time),
+            None => object.downcast_ref::<gst::Element>().and_then(|element| element.static_pad("sink"))
+                .map(|pad| stream_time(&pad, running_time))
+                .unwrap_or(running_time)
+        };
+
+        let control_source = gst_controller::InterpolationControlSource::new();
+        control_source.set_property("mode", gst_controller::InterpolationMode::Linear);
+
+        let steps = (duration.mseconds() / ANIMATION_STEP_MS).max(1);
+        for step in 0..=steps {
+            let progress = step as f64 / steps as f64;
+            let timestamp = start + gst::ClockTime::from_nseconds(duration.nseconds() * step / steps);
+            control_source.set(timestamp, from + (to - from) * ease(easing, progress).unwrap());
+        }
+
+        self.cancel(param_name);
+        let binding = gst_controller::DirectControlBinding::new_absolute(&object, param.property,
+                                                                         &control_source);
+        object.add_control_binding(&binding).is_ok()
+    }
+
+    /* The parameter keeps the last value the animation gave it */
+    fn cancel(&self, param_name: &str) -> bool {
+        match self.target(param_name) {
+            Some((object, param)) => match object.control_binding(param.property) {
+                Some(binding) => object.remove_control_binding(&binding),
+                None => false
+            },
+            None => false
+        }
+    }
+
+    /* Names of the parameters whose animation got cancelled */
+    fn cancel_all(&self) -> Vec<String> {
+        ANIMATED_PARAMS.iter()
+            .filter(|param_name| self.cancel(param_name))
+            .map(|param_name| param_name.to_string())
+            .collect()
+    }
+
+    fn query(&self, param_name: &str) -> Option<glib::Variant> {
+        let param = self.param(param_name)?;
+        match param.target {
+            ParamTarget::Transform => value_to_variant(&self.transform.as_ref()?.get(param.property)?),
+            ParamTarget::Sync => Some(((self.src_pad().offset() / 1_000_000) as u32).to_variant()),
+            ParamTarget::Routing => match param.property {
+                "tracks" => Some(self.routing.as_ref()?.tracks().to_variant()),
+                _ => Some(self.routing.as_ref()?.mode().to_variant()),
+            },
+            ParamTarget::Ducking => Some(self.routing.as_ref()?.ducking.lock().unwrap().get(param.property)),
+            _ => {
+                let (object, _) = self.target(param_name)?;
+                value_to_variant(&object.property_value(param.property))
+            }
+        }
+    }
+
+    fn snapshot(&self) -> gst::Structure {
+        let mut structure = gst::Structure::new_empty(match self.kind {
+            InputKind::Video => "video-input",
+            InputKind::Audio => "audio-input",
+        });
+
+        for param in self.params().iter() {
+            if let Some(value) = self.query(param.name).as_ref().and_then(variant_to_value) {
+                structure.set_value(param.name, value);
+            }
+        }
+
+        structure
+    }
+}
+
+/* Input parameter updates waiting for the next frame boundary of a mixer, along with the sink
+ * pad name of their input and their parameter name */
+type PendingUpdates = Arc<Mutex<Vec<(String, String, InputUpdate)>>>;
+
+fn apply_updates(pending: &PendingUpdates) {
+    for (_, _, update) in pending.lock().unwrap().drain(..) {
+        update();
+    }
+}
+
+/* Drop the waiting updates of a parameter of an input, a newer value superseding them */
+fn supersede_updates(pending: &mut Vec<(String, String, InputUpdate)>, sink_pad_name: &str, param_name: &str) {
+    pending.retain(|(pending_pad_name, pending_param_name, _)|
+                   pending_pad_name != sink_pad_name || pending_param_name != param_name);
+}
+
 struct State {
+    video_filler: gst::Element,
+    video_filler_caps: gst::Element,
+    audio_filler: gst::Element,
     video_mixer: gst::Element,
     audio_mixer: gst::Element,
+    monitor_mixer: gst::Element,
+    video_convert: gst::Element,
+    video_capsfilter: gst::Element,
+    audio_capsfilter: gst::Element,
+    monitor_capsfilter: gst::Element,
+    master_level: gst::Element,
     video_tee: gst::Element,
-    audio_tee: gst::Element
+    audio_tee: gst::Element,
+    monitor_tee: gst::Element,
+    tracks: Vec<Track>,
+    inputs: HashMap<String, Input>,
+    outputs: HashMap<String, Output>,
+    video_pending: PendingUpdates,
+    audio_pending: PendingUpdates,
+    next_video_input: u32,
+    next_audio_input: u32,
 }
 
 impl State {
     fn new() -> Self {
         Self {
-            video_mixer: gst::ElementFactory::make("compositor", None)
+            video_filler: gst::ElementFactory::make("videotestsrc", None)
+                .expect("Could not create video filler element."),
+            video_filler_caps: gst::ElementFactory::make("capsfilter", None)
+                .expect("Could not create video filler capsfilter element."),
+            audio_filler: gst::ElementFactory::make("audiotestsrc", None)
+                .expect("Could not create audio filler element."),
+            video_mixer: gst::ElementFactory::make("dkcvideomixer", None)
                 .expect("Could not create video source element."),
             audio_mixer: gst::ElementFactory::make("audiomixer", None)
                 .expect("Could not create audio source element."),
+            monitor_mixer: gst::ElementFactory::make("audiomixer", None)
+                .expect("Could not create monitor mixer element."),
+            video_convert: gst::ElementFactory::make("videoconvert", None)
+                .expect("Could not create video converter element."),
+            video_capsfilter: gst::ElementFactory::make("capsfilter", None)
+                .expect("Could not create video capsfilter element."),
+            audio_capsfilter: gst::ElementFactory::make("capsfilter", None)
+                .expect("Could not create audio capsfilter element."),
+            monitor_capsfilter: gst::ElementFactory::make("capsfilter", None)
+                .expect("Could not create monitor capsfilter element."),
+            master_level: gst::ElementFactory::make("level", None)
+                .expect("Could not create master level element."),
             video_tee: gst::ElementFactory::make("tee", None)
                 .expect("Could not create video tee element."),
             audio_tee: gst::ElementFactory::make("tee", None)
                 .expect("Could not create audio tee element."),
+            monitor_tee: gst::ElementFactory::make("tee", None)
+                .expect("Could not create monitor tee element."),
+            tracks: Vec::new(),
+            inputs: HashMap::new(),
+            outputs: HashMap::new(),
+            video_pending: Arc::new(Mutex::new(Vec::new())),
+            audio_pending: Arc::new(Mutex::new(Vec::new())),
+            next_video_input: 0,
+            next_audio_input: 0,
+        }
+    }
+
+    /* Elements of the video or audio branch, from upstream to downstream */
+    fn branch(&self, kind: InputKind) -> Vec<gst::Element> {
+        match kind {
+            InputKind::Video => vec![self.video_filler.clone(), self.video_filler_caps.clone(), self.video_mixer.clone(),
+                                     self.video_convert.clone(), self.video_capsfilter.clone(), self.video_tee.clone()],
+            InputKind::Audio => {
+                let mut elements = vec![self.audio_filler.clone(), self.audio_mixer.clone(), self.audio_capsfilter.clone(),
+                                        self.master_level.clone(), self.audio_tee.clone(), self.monitor_mixer.clone(),
+                                        self.monitor_capsfilter.clone(), self.monitor_tee.clone()];
+                for track in self.tracks.iter() {
+                    elements.extend(track.elements().iter().map(|element| (*element).clone()));
+                }
+                elements
+            }
         }
     }
+
+    /* Inputs and outputs keep their branch enabled */
+    fn has_pads(&self, kind: InputKind) -> bool {
+        let prefixes: &[&str] = match kind {
+            InputKind::Video => &["video_"],
+            InputKind::Audio => &["audio_", "monitor_"],
+        };
+
+        self.inputs.values().any(|input| input.kind == kind)
+            || self.outputs.keys().any(|name| prefixes.iter().any(|prefix| name.starts_with(prefix)))
+    }
 }
 
-#[derive(Default)]
-pub struct DkcScene {
-    state: Mutex<Option<State>>
-}
+#[derive(Default)]
+pub struct DkcScene {
+    settings: Mutex<Settings>,
+    state: Mutex<Option<State>>
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcscene",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster scene element"),
+    )
+});
+
+impl DkcScene {
+    fn update_caps(&self) {
+        let settings = self.settings.lock().unwrap();
+        let state_lock = self.state.lock().unwrap();
+
+        if let Some(state) = state_lock.as_ref() {
+            state.video_capsfilter.set_property("caps", &settings.video_caps());
+            state.video_filler_caps.set_property("caps", &settings.filler_caps());
+            state.audio_capsfilter.set_property("caps", &settings.audio_caps());
+            state.monitor_capsfilter.set_property("caps", &settings.audio_caps());
+            for track in state.tracks.iter() {
+                track.capsfilter.set_property("caps", &settings.audio_caps());
+            }
+        }
+    }
+
+    /* Disabled branches are left out of the scene state changes */
+    fn update_branches(&self) {
+        let (video_enabled, audio_enabled) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.video_enabled, settings.audio_enabled)
+        };
+
+        /* Stopping a branch waits for its streaming thread, which takes the state lock to handle
+         * level messages: the lock is released before changing states */
+        let branches = match self.state.lock().unwrap().as_ref() {
+            Some(state) => vec![(state.branch(InputKind::Video), video_enabled),
+                                (state.branch(InputKind::Audio), audio_enabled)],
+            None => return
+        };
+
+        for (elements, enabled) in branches.iter() {
+            for element in elements.iter().rev() {
+                if element.is_locked_state() == *enabled {
+                    element.set_locked_state(!*enabled);
+                    let _ = if *enabled { element.sync_state_with_parent() } else { element.set_state(gst::State::Null).map(|_| ()) };
+                }
+            }
+        }
+    }
+
+    fn update_background(&self) {
+        let (background, color, location) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.background.clone(), settings.background_color, settings.background_location.clone())
+        };
+        let state_lock = self.state.lock().unwrap();
+
+        if let Some(state) = state_lock.as_ref() {
+            state.video_mixer.set_property_from_str("background", &background);
+            state.video_mixer.set_property("background-color", color);
+            /* Setting the location decodes the image again */
+            if state.video_mixer.property::<Option<String>>("background-location") != location {
+                state.video_mixer.set_property("background-location", location);
+            }
+        }
+    }
+
+    /* Track mixers past the program one, every audio input having a branch into each of them */
+    fn update_tracks(&self, obj: &super::DkcScene) {
+        let count = self.settings.lock().unwrap().audio_tracks as usize - 1;
+        let mut state_lock = self.state.lock().unwrap();
+        let state = match state_lock.as_mut() {
+            Some(state) => state,
+            None => return
+        };
+        let bin = obj.upcast_ref::<gst::Bin>();
+
+        while state.tracks.len() < count {
+            let track = Track::new();
+            bin.add_many(&track.elements()).expect("Could not add track elements to the bin");
+            gst::Element::link_many(&track.elements()).expect("Could not link track mixer to its tee element.");
+
+            for routing in state.inputs.values().filter_map(|input| input.routing.as_ref()) {
+                routing.add_track(bin, &track);
+            }
+            for element in track.elements().iter().rev() {
+                let _ = element.sync_state_with_parent();
+            }
+
+            state.tracks.push(track);
+        }
+
+        while state.tracks.len() > count {
+            let track = state.tracks.pop().unwrap();
+            let number = state.tracks.len() as u32 + 1;
+
+            for routing in state.inputs.values().filter_map(|input| input.routing.as_ref()) {
+                routing.remove_track(bin);
+            }
+
+            /* Outputs of a removed track fall back to the program mix */
+            for output in state.outputs.values_mut().filter(|output| output.track == number) {
+                move_output(output, &state.audio_tee, 0);
+            }
+
+            for element in track.elements().iter() {
+                let _ = element.set_state(gst::State::Null);
+                let _ = bin.remove(*element);
+            }
+        }
+    }
+
+    /* Level elements of the master mix and of every audio input */
+    fn update_levels(&self) {
+        let interval = self.settings.lock().unwrap().level_interval;
+        let state_lock = self.state.lock().unwrap();
+
+        if let Some(state) = state_lock.as_ref() {
+            let levels = state.inputs.values().filter_map(|input| input.level.as_ref());
+            for level in std::iter::once(&state.master_level).chain(levels) {
+                level.set_property("interval", interval);
+                level.set_property("post-messages", true);
+            }
+        }
+    }
+
+    fn parse_framerate(param_value: &glib::variant::Variant) -> Option<gst::Fraction> {
+        match param_value.get::<String>() {
+            Some(framerate) => {
+                let mut parts = framerate.splitn(2, '/');
+                let numerator = parts.next()?.trim().parse::<i32>().ok()?;
+                let denominator = match parts.next() {
+                    Some(denominator) => denominator.trim().parse::<i32>().ok()?,
+                    None => 1,
+                };
+                if numerator > 0 && denominator > 0 {
+                    Some(gst::Fraction::new(numerator, denominator))
+                } else {
+                    None
+                }
+            },
+            None => None
+        }
+    }
+
+    fn scaling_method(filter: &str) -> Option<&'static str> {
+        match filter {
+            "point" => Some("nearest-neighbour"),
+            "bilinear" => Some("bilinear"),
+            "bicubic" => Some("catrom"),
+            "lanczos" => Some("lanczos"),
+            _ => None
+        }
+    }
+
+    fn class_update_output_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                   -> Option<glib::value::Value> {
+
+        let bin = args[0].get::<super::DkcScene>().expect("signal arg");
+        let src_pad_name = args[1].get::<String>().expect("signal arg");
+        let param_name = args[2].get::<String>().expect("signal arg");
+        let param_value = args[3].get::<glib::variant::Variant>().expect("signal arg");
+
+        let scene = DkcScene::from_instance(&bin);
+        let mut state_lock = scene.state.lock().unwrap();
+        let state = state_lock.as_mut().unwrap();
+
+        /* Program audio outputs can record any track instead: queue */
+        if param_name == "track" {
+            let tee = match param_value.get::<u32>() {
+                Some(0) => Some(state.audio_tee.clone()),
+                Some(track) => state.tracks.get(track as usize - 1).map(|track| track.tee.clone()),
+                None => None
+            };
+
+            return Some(match (tee, state.outputs.get_mut(&src_pad_name)) {
+                (Some(tee), Some(output)) if src_pad_name.starts_with("audio_") => {
+                    move_output(output, &tee, param_value.get::<u32>().unwrap());
+                    true
+                },
+                _ => false
+            }.to_value());
+        }
+
+        let output = match state.outputs.get_mut(&src_pad_name) {
+            Some(output) => output,
+            None => return Some(false.to_value()) // No such output
+        };
 
-static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
-    gst::DebugCategory::new(
-        "dkcscene",
-        gst::DebugColorFlags::empty(),
-        Some("DankCaster scene element"),
-    )
-});
+        /* Only video outputs can be rescaled: queue ! videoscale ! videorate ! capsfilter */
+        let (scale, capsfilter) = match output.elements.as_slice() {
+            [_queue, scale, _rate, capsfilter] => (scale, capsfilter),
+            _ => return Some(false.to_value())
+        };
 
-impl DkcScene {
-    fn class_update_video_input_handler(pad: &gst::Pad, param_name: &str, param_value: &glib::variant::Variant)
-                                        -> Option<glib::value::Value> {
-
-        let mixer_pad = pad.downcast_ref::<gst::GhostPad>().expect("Could not cast Pad as GhostPad")
-            .target().expect("Could not get ghost pad target pad (queue sink pad)")
-            .parent_element().expect("Could not get queue")
-            .static_pad("src").expect("Could not get queue src")
-            .peer().expect("Could not get queue src peer");
-
-        match param_name {
-            "width" | "height" | "xpos" | "ypos" => match param_value.get::<i32>() {
-                Some(value) => { mixer_pad.set_property(param_name, value); Some(true.to_value()) },
-                _ => Some(false.to_value())
+        let updated = match param_name.as_str() {
+            "width" | "height" => match param_value.get::<i32>() {
+                Some(value) if value >= 0 => {
+                    /* 0 goes back to the canvas size */
+                    let value = if value == 0 { None } else { Some(value) };
+                    if param_name == "width" { output.settings.width = value; } else { output.settings.height = value; }
+                    true
+                },
+                _ => false
             },
-            "alpha" => match param_value.get::<f64>() {
-                Some(value) => { mixer_pad.set_property(param_name, value); Some(true.to_value()) },
-                _ => Some(false.to_value())
+            "framerate" => match param_value.get::<String>().as_deref() {
+                Some("") => { output.settings.framerate = None; true },
+                _ => match Self::parse_framerate(&param_value) {
+                    Some(framerate) => { output.settings.framerate = Some(framerate); true },
+                    None => false
+                }
             },
-            "zorder" => match param_value.get::<u32>() {
-                Some(value) => { mixer_pad.set_property(param_name, value); Some(true.to_value()) },
-                _ => Some(false.to_value())
+            "scaling-filter" => match param_value.get::<String>().as_deref().and_then(Self::scaling_method) {
+                Some(method) => { scale.set_property_from_str("method", method); true },
+                None => false
             },
-            _ => Some(false.to_value())
+            _ => false
+        };
+
+        if updated {
+            gst::debug!(CAT, obj: &bin, "Output {} set to {}", src_pad_name, output.settings.caps());
+            capsfilter.set_property("caps", &output.settings.caps());
         }
+
+        Some(updated.to_value())
     }
 
-    fn class_update_audio_input_handler(pad: &gst::Pad, param_name: &str, param_value: &glib::variant::Variant)
-                                        -> Option<glib::value::Value> {
+    fn class_update_input_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                  -> Option<glib::value::Value> {
+
+        let bin = args[0].get::<super::DkcScene>().expect("signal arg");
+        let sink_pad_name = args[1].get::<String>().expect("signal arg");
+        let param_name = args[2].get::<String>().expect("signal arg");
+        let param_value = args[3].get::<glib::variant::Variant>().expect("signal arg");
+
+        let scene = DkcScene::from_instance(&bin);
+        let state_lock = scene.state.lock().unwrap();
+        let state = state_lock.as_ref().unwrap();
 
-        let mixer_pad = pad.downcast_ref::<gst::GhostPad>().expect("Could not cast Pad as GhostPad")
-            .target().expect("Could not get ghost pad target pad (queue sink pad)")
-            .parent_element().expect("Could not get queue")
-            .static_pad("src").expect("Could not get queue src")
-            .peer().expect("Could not get queue src peer");
+        match state.inputs.get(&sink_pad_name) {
+            Some(input) => {
+                /* Applied right away, over any batched update of the same parameter still waiting */
+                let pending = match input.kind {
+                    InputKind::Video => &state.video_pending,
+                    InputKind::Audio => &state.audio_pending,
+                };
+                let mut pending = pending.lock().unwrap();
+                let updated = input.update(&param_name, &param_value);
+                if updated {
+                    supersede_updates(&mut pending, &sink_pad_name, &param_name);
+                }
 
-        match param_name {
-            "mute" => match param_value.get::<bool>() {
-                Some(value) => { mixer_pad.set_property(param_name, value); Some(true.to_value()) },
-                _ => Some(false.to_value())
+                Some(updated.to_value())
             },
-            "volume" => match param_value.get::<f64>() {
-                Some(value) => { mixer_pad.set_property(param_name, value); Some(true.to_value()) },
-                _ => Some(false.to_value())
-            },
-            _ => Some(false.to_value())
+            None => Some(false.to_value()) // Not an input (with this name)
         }
     }
 
-    fn class_update_input_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
-                                  -> Option<glib::value::Value> {
+    /* Whether an update would be taken, without applying it */
+    fn class_check_input_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                 -> Option<glib::value::Value> {
 
         let bin = args[0].get::<super::DkcScene>().expect("signal arg");
         let sink_pad_name = args[1].get::<String>().expect("signal arg");
         let param_name = args[2].get::<String>().expect("signal arg");
         let param_value = args[3].get::<glib::variant::Variant>().expect("signal arg");
 
-        let video_caps = gst::Caps::new_simple(
-            "video/x-raw",
-            &[],
-        );
+        let scene = DkcScene::from_instance(&bin);
+        let state_lock = scene.state.lock().unwrap();
 
-        let audio_caps = gst::Caps::new_simple(
-            "audio/x-raw",
-            &[],
-        );
+        match state_lock.as_ref().unwrap().inputs.get(&sink_pad_name) {
+            Some(input) => Some(input.check(&param_name, &param_value).is_some().to_value()),
+            None => Some(false.to_value()) // Not an input (with this name)
+        }
+    }
 
-        match bin.static_pad(&sink_pad_name) {
-            Some(pad) => match pad.direction() {
-                gst::PadDirection::Sink => {
-                    if video_caps.is_strictly_equal(pad.pad_template_caps().as_ref()) {
-                        Self::class_update_video_input_handler(&pad, &param_name, &param_value)
-                    } else if audio_caps.is_strictly_equal(pad.pad_template_caps().as_ref()) {
-                        Self::class_update_audio_input_handler(&pad, &param_name, &param_value)
-                    } else {
-                        Some(false.to_value()) // Pad is neither of the video nor the audio type
+    fn class_update_inputs_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                   -> Option<glib::value::Value> {
+
+        let bin = args[0].get::<super::DkcScene>().expect("signal arg");
+        let updates = args[1].get::<gst::Structure>().expect("signal arg");
+
+        let scene = DkcScene::from_instance(&bin);
+        let state_lock = scene.state.lock().unwrap();
+        let state = state_lock.as_ref().unwrap();
+
+        let mut video_updates = Vec::new();
+        let mut audio_updates = Vec::new();
+
+        /* Every update is validated first, so that results are known synchronously */
+        let mut results = gst::Structure::new_empty("results");
+        for (sink_pad_name, params) in updates.iter() {
+            let input = state.inputs.get(sink_pad_name);
+            let mut input_results = gst::Structure::new_empty("input-results");
+
+            if let Ok(params) = params.get::<gst::Structure>() {
+                for (param_name, value) in params.iter() {
+                    let update = input.and_then(|input| {
+                        value_to_variant(value)
+                            .and_then(|variant| input.check(param_name, &variant))
+                            .map(|update| (input, update))
+                    });
+
+                    match update {
+                        Some((input, update)) => {
+                            input.cancel(param_name);
+                            let update = (sink_pad_name.to_string(), param_name.to_string(), update);
+                            match input.kind {
+                                InputKind::Video => video_updates.push(update),
+                                InputKind::Audio => audio_updates.push(update),
+                            }
+                            input_results.set(param_name, true);
+                        },
+                        None => input_results.set(param_name, false)
                     }
-                },
-                _ => Some(false.to_value()) // Not a Sink, therefore not an input
-            },
-            None => Some(false.to_value()) // Pad not found (with this name)
+                }
+            }
+
+            results.set(sink_pad_name, input_results);
+        }
+
+        for (pending, updates) in [(&state.video_pending, video_updates), (&state.audio_pending, audio_updates)] {
+            let mut pending = pending.lock().unwrap();
+            for (sink_pad_name, param_name, update) in updates {
+                supersede_updates(&mut pending, &sink_pad_name, &param_name);
+                pending.push((sink_pad_name, param_name, update));
+            }
+        }
+
+        /* Without data flowing, there is no frame boundary to wait for */
+        if bin.current_state() < gst::State::Paused {
+            apply_updates(&state.video_pending);
+            apply_updates(&state.audio_pending);
+        }
+
+        Some(results.to_value())
+    }
+
+    fn class_animate_input_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                   -> Option<glib::value::Value> {
+
+        let bin = args[0].get::<super::DkcScene>().expect("signal arg");
+        let sink_pad_name = args[1].get::<String>().expect("signal arg");
+        let param_name = args[2].get::<String>().expect("signal arg");
+        let target = args[3].get::<glib::variant::Variant>().expect("signal arg");
+        let duration = gst::ClockTime::from_nseconds(args[4].get::<u64>().expect("signal arg"));
+        let easing = args[5].get::<String>().expect("signal arg");
+
+        let running_time = bin.current_running_time().unwrap_or(gst::ClockTime::ZERO);
+
+        let scene = DkcScene::from_instance(&bin);
+        let state_lock = scene.state.lock().unwrap();
+
+        match state_lock.as_ref().unwrap().inputs.get(&sink_pad_name) {
+            Some(input) => Some(input.animate(&param_name, &target, running_time, duration, &easing).to_value()),
+            None => Some(false.to_value()) // Not an input (with this name)
+        }
+    }
+
+    /* Run a filter chain operation on the input targeted by the first signal argument */
+    fn with_input<F>(args: &[glib::value::Value], operation: F) -> Option<glib::value::Value>
+        where F: FnOnce(&gst::Bin, &mut Input) -> bool {
+
+        let bin = args[0].get::<super::DkcScene>().expect("signal arg");
+        let sink_pad_name = args[1].get::<String>().expect("signal arg");
+
+        let scene = DkcScene::from_instance(&bin);
+        let mut state_lock = scene.state.lock().unwrap();
+
+        match state_lock.as_mut().unwrap().inputs.get_mut(&sink_pad_name) {
+            Some(input) => Some(operation(bin.upcast_ref::<gst::Bin>(), input).to_value()),
+            None => Some(false.to_value()) // Not an input (with this name)
+        }
+    }
+
+    /* Filter signals name the chain of the input they work on, "" being the one selected at first */
+    fn class_add_filter_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                -> Option<glib::value::Value> {
+
+        let chain = args[2].get::<String>().expect("signal arg");
+        let filter_name = args[3].get::<String>().expect("signal arg");
+        let kind = args[4].get::<String>().expect("signal arg");
+        let position = args[5].get::<i32>().expect("signal arg");
+
+        Self::with_input(args, |bin, input| input.add_filter(bin, &chain, &filter_name, &kind, position))
+    }
+
+    fn class_remove_filter_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                   -> Option<glib::value::Value> {
+
+        let chain = args[2].get::<String>().expect("signal arg");
+        let filter_name = args[3].get::<String>().expect("signal arg");
+
+        Self::with_input(args, |bin, input| input.remove_filter(bin, &chain, &filter_name))
+    }
+
+    fn class_move_filter_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                 -> Option<glib::value::Value> {
+
+        let chain = args[2].get::<String>().expect("signal arg");
+        let filter_name = args[3].get::<String>().expect("signal arg");
+        let position = args[4].get::<i32>().expect("signal arg");
+
+        Self::with_input(args, |bin, input| input.move_filter(bin, &chain, &filter_name, position))
+    }
+
+    fn class_update_filter_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                   -> Option<glib::value::Value> {
+
+        let chain = args[2].get::<String>().expect("signal arg");
+        let filter_name = args[3].get::<String>().expect("signal arg");
+        let param_name = args[4].get::<String>().expect("signal arg");
+        let param_value = args[5].get::<glib::variant::Variant>().expect("signal arg");
+
+        Self::with_input(args, |_, input| input.update_filter(&chain, &filter_name, &param_name, &param_value))
+    }
+
+    fn class_select_filter_chain_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                         -> Option<glib::value::Value> {
+
+        let chain = args[2].get::<String>().expect("signal arg");
+
+        Self::with_input(args, |bin, input| input.select_chain(bin, &chain))
+    }
+
+    fn class_cancel_animation_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                      -> Option<glib::value::Value> {
+
+        let bin = args[0].get::<super::DkcScene>().expect("signal arg");
+        let sink_pad_name = args[1].get::<String>().expect("signal arg");
+        let param_name = args[2].get::<String>().expect("signal arg");
+
+        let scene = DkcScene::from_instance(&bin);
+        let state_lock = scene.state.lock().unwrap();
+
+        match state_lock.as_ref().unwrap().inputs.get(&sink_pad_name) {
+            Some(input) => Some(input.cancel(&param_name).to_value()),
+            None => Some(false.to_value()) // Not an input (with this name)
+        }
+    }
+
+    fn class_cancel_animations_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                       -> Option<glib::value::Value> {
+
+        let bin = args[0].get::<super::DkcScene>().expect("signal arg");
+        let sink_pad_name = args[1].get::<String>().expect("signal arg");
+
+        let scene = DkcScene::from_instance(&bin);
+        let state_lock = scene.state.lock().unwrap();
+
+        match state_lock.as_ref().unwrap().inputs.get(&sink_pad_name) {
+            Some(input) => Some(input.cancel_all().to_value()),
+            None => Some(Vec::<String>::new().to_value()) // Not an input (with this name)
+        }
+    }
+
+    fn class_query_input_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                 -> Option<glib::value::Value> {
+
+        let bin = args[0].get::<super::DkcScene>().expect("signal arg");
+        let sink_pad_name = args[1].get::<String>().expect("signal arg");
+        let param_name = args[2].get::<String>().expect("signal arg");
+
+        let scene = DkcScene::from_instance(&bin);
+        let state_lock = scene.state.lock().unwrap();
+
+        let param_value = state_lock.as_ref().unwrap().inputs.get(&sink_pad_name)
+            .and_then(|input| input.query(&param_name));
+
+        Some(param_value.to_value())
+    }
+
+    fn class_query_inputs_handler(_token: &glib::subclass::signal::SignalClassHandlerToken, args: &[glib::value::Value])
+                                  -> Option<glib::value::Value> {
+
+        let bin = args[0].get::<super::DkcScene>().expect("signal arg");
+
+        let scene = DkcScene::from_instance(&bin);
+        let state_lock = scene.state.lock().unwrap();
+
+        /* One field per input, holding a structure of all its parameters */
+        let mut inputs = gst::Structure::new_empty("inputs");
+        for (sink_pad_name, input) in state_lock.as_ref().unwrap().inputs.iter() {
+            inputs.set(sink_pad_name, input.snapshot());
         }
+
+        Some(inputs.to_value())
     }
 }
 
@@ -136,17 +1745,255 @@ impl ObjectImpl for DkcScene {
     fn constructed(&self, obj: &Self::Type) {
         let state = State::new();
 
+        self.add_element(obj, &state.video_filler).expect("Could not add video filler to bin.");
+        self.add_element(obj, &state.video_filler_caps).expect("Could not add video filler capsfilter to bin.");
+        self.add_element(obj, &state.audio_filler).expect("Could not add audio filler to bin.");
         self.add_element(obj, &state.video_mixer).expect("Could not add video mixer to bin.");
         self.add_element(obj, &state.audio_mixer).expect("Could not add audio mixer to bin");
+        self.add_element(obj, &state.video_convert).expect("Could not add video converter to bin");
+        self.add_element(obj, &state.video_capsfilter).expect("Could not add video capsfilter to bin");
+        self.add_element(obj, &state.audio_capsfilter).expect("Could not add audio capsfilter to bin");
+        self.add_element(obj, &state.master_level).expect("Could not add master level to bin");
         self.add_element(obj, &state.video_tee).expect("Could not add video tee to bin");
         self.add_element(obj, &state.audio_tee).expect("Could not add audio tee to bin");
+        self.add_element(obj, &state.monitor_mixer).expect("Could not add monitor mixer to bin");
+        self.add_element(obj, &state.monitor_capsfilter).expect("Could not add monitor capsfilter to bin");
+        self.add_element(obj, &state.monitor_tee).expect("Could not add monitor tee to bin");
 
-        state.video_mixer.link(&state.video_tee)
+        /* The mixer works in RGBA, converted to the canvas format */
+        gst::Element::link_many(&[&state.video_mixer, &state.video_convert, &state.video_capsfilter, &state.video_tee])
             .expect("Could not link video mixer to its tee element.");
-        state.audio_mixer.link(&state.audio_tee)
+        gst::Element::link_many(&[&state.audio_mixer, &state.audio_capsfilter, &state.master_level, &state.audio_tee])
             .expect("Could not link audio mixer to its tee element.");
 
+        /* Fillers keep the mixers running without any input: transparent frames the canvas
+         * background shows through, and silence */
+        state.video_filler.set_property("is-live", true);
+        state.video_filler.set_property_from_str("pattern", "black");
+        state.audio_filler.set_property("is-live", true);
+        state.audio_filler.set_property_from_str("wave", "silence");
+        gst::Element::link_many(&[&state.video_filler, &state.video_filler_caps])
+            .expect("Could not link video filler elements.");
+        let filler_pad = state.video_mixer.request_pad_simple("sink_%u").expect("Could not get video filler mixer pad");
+        filler_pad.set_property("alpha", 0.0f64);
+        state.video_filler_caps.static_pad("src").unwrap().link(&filler_pad)
+            .expect("Could not link video filler to the mixer.");
+        state.audio_filler.link(&state.audio_mixer).expect("Could not link audio filler to the mixer.");
+
+        /* Monitoring is optional, nothing may be listening to it */
+        state.monitor_tee.set_property("allow-not-linked", true);
+        gst::Element::link_many(&[&state.monitor_mixer, &state.monitor_capsfilter, &state.monitor_tee])
+            .expect("Could not link monitor mixer to its tee element.");
+
+        /* Batched input updates are applied right after a mixer pushed a buffer,
+         * so that all of them are taken into account by the same next frame */
+        for (mixer, pending) in [(&state.video_mixer, &state.video_pending),
+                                 (&state.audio_mixer, &state.audio_pending)].iter() {
+            let pending = (*pending).clone();
+            mixer.static_pad("src").expect("Could not get mixer src pad")
+                .add_probe(gst::PadProbeType::BUFFER, move |_, _| {
+                    apply_updates(&pending);
+                    gst::PadProbeReturn::Ok
+                });
+        }
+
         *self.state.lock().unwrap() = Some(state);
+
+        self.update_caps();
+        self.update_levels();
+        self.update_background();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecInt::new(
+                    "width",
+                    "Width",
+                    "Canvas width",
+                    1,
+                    i32::MAX,
+                    DEFAULT_WIDTH,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecInt::new(
+                    "height",
+                    "Height",
+                    "Canvas height",
+                    1,
+                    i32::MAX,
+                    DEFAULT_HEIGHT,
+                    glib::ParamFlags::READWRITE,
+                ),
+                gst::ParamSpecFraction::new(
+                    "framerate",
+                    "Framerate",
+                    "Canvas framerate",
+                    gst::Fraction::new(1, i32::MAX),
+                    gst::Fraction::new(i32::MAX, 1),
+                    gst::Fraction::new(DEFAULT_FRAMERATE_NUM, DEFAULT_FRAMERATE_DEN),
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecString::new(
+                    "format",
+                    "Pixel format",
+                    "Canvas pixel format (e.g. \"I420\"), negotiated with downstream if unset",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecInt::new(
+                    "audio-rate",
+                    "Audio rate",
+                    "Sample rate of the audio mix",
+                    1,
+                    i32::MAX,
+                    DEFAULT_AUDIO_RATE,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecInt::new(
+                    "audio-channels",
+                    "Audio channels",
+                    "Number of channels of the audio mix",
+                    1,
+                    64,
+                    DEFAULT_AUDIO_CHANNELS,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecUInt::new(
+                    "audio-tracks",
+                    "Audio tracks",
+                    "Number of audio mixes, the first one being the program mix",
+                    1,
+                    MAX_AUDIO_TRACKS,
+                    DEFAULT_AUDIO_TRACKS,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecBoolean::new(
+                    "enable-video",
+                    "Enable video",
+                    "Whether the scene mixes video, it cannot be disabled while it has video pads",
+                    true,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecBoolean::new(
+                    "enable-audio",
+                    "Enable audio",
+                    "Whether the scene mixes audio, it cannot be disabled while it has audio pads",
+                    true,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecString::new(
+                    "background",
+                    "Background",
+                    "What shows where no input covers the canvas (checker, black, white, transparent, color, image)",
+                    Some(DEFAULT_BACKGROUND),
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecUInt::new(
+                    "background-color",
+                    "Background color",
+                    "Color of the color background, as 0xAARRGGBB",
+                    0,
+                    u32::MAX,
+                    DEFAULT_BACKGROUND_COLOR,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecString::new(
+                    "background-location",
+                    "Background location",
+                    "Path of the image background, stretched over the canvas",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecUInt64::new(
+                    "level-interval",
+                    "Level interval",
+                    "Interval between two audio level messages, in nanoseconds",
+                    1,
+                    u64::MAX,
+                    DEFAULT_LEVEL_INTERVAL,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        {
+            let mut settings = self.settings.lock().unwrap();
+
+            match pspec.name() {
+                "width" => settings.width = value.get().expect("type checked upstream"),
+                "height" => settings.height = value.get().expect("type checked upstream"),
+                "framerate" => settings.framerate = value.get().expect("type checked upstream"),
+                "format" => settings.format = value.get::<Option<String>>().expect("type checked upstream")
+                    .filter(|format| !format.is_empty()),
+                "audio-rate" => settings.audio_rate = value.get().expect("type checked upstream"),
+                "audio-channels" => settings.audio_channels = value.get().expect("type checked upstream"),
+                "audio-tracks" => settings.audio_tracks = value.get().expect("type checked upstream"),
+                "level-interval" => settings.level_interval = value.get().expect("type checked upstream"),
+                name @ ("enable-video" | "enable-audio") => {
+                    let enabled = value.get::<bool>().expect("type checked upstream");
+                    let kind = if name == "enable-video" { InputKind::Video } else { InputKind::Audio };
+                    let has_pads = self.state.lock().unwrap().as_ref().map_or(false, |state| state.has_pads(kind));
+
+                    if !enabled && has_pads {
+                        gst::warning!(CAT, obj: obj, "Cannot disable a branch which has pads");
+                    } else if kind == InputKind::Video {
+                        settings.video_enabled = enabled;
+                    } else {
+                        settings.audio_enabled = enabled;
+                    }
+                },
+                "background" => {
+                    let background = value.get::<Option<String>>().expect("type checked upstream")
+                        .unwrap_or_else(|| DEFAULT_BACKGROUND.to_string());
+                    let value_type = self.state.lock().unwrap().as_ref()
+                        .and_then(|state| state.video_mixer.find_property("background"))
+                        .map(|pspec| pspec.value_type());
+
+                    match value_type {
+                        Some(value_type) if glib::Value::deserialize(&background, value_type).is_ok() =>
+                            settings.background = background,
+                        _ => gst::warning!(CAT, obj: obj, "Unknown background {}", background),
+                    }
+                },
+                "background-color" => settings.background_color = value.get().expect("type checked upstream"),
+                "background-location" => settings.background_location = value.get().expect("type checked upstream"),
+                _ => unimplemented!(),
+            }
+
+            gst::debug!(CAT, obj: obj, "Canvas set to {} / {}", settings.video_caps(), settings.audio_caps());
+        }
+
+        self.update_tracks(obj);
+        self.update_caps();
+        self.update_levels();
+        self.update_branches();
+        self.update_background();
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+
+        match pspec.name() {
+            "width" => settings.width.to_value(),
+            "height" => settings.height.to_value(),
+            "framerate" => settings.framerate.to_value(),
+            "format" => settings.format.to_value(),
+            "audio-rate" => settings.audio_rate.to_value(),
+            "audio-channels" => settings.audio_channels.to_value(),
+            "audio-tracks" => settings.audio_tracks.to_value(),
+            "level-interval" => settings.level_interval.to_value(),
+            "enable-video" => settings.video_enabled.to_value(),
+            "enable-audio" => settings.audio_enabled.to_value(),
+            "background" => settings.background.to_value(),
+            "background-color" => settings.background_color.to_value(),
+            "background-location" => settings.background_location.to_value(),
+            _ => unimplemented!(),
+        }
     }
 
     fn signals() -> &'static [glib::subclass::Signal] {
@@ -160,6 +2007,117 @@ impl ObjectImpl for DkcScene {
                 )
                 .action()
                 .class_handler(&DkcScene::class_update_input_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "check-input",
+                    &[String::static_type().into(), String::static_type().into(),
+                      glib::variant::Variant::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_check_input_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "update-output",
+                    &[String::static_type().into(), String::static_type().into(),
+                      glib::variant::Variant::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_update_output_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "update-inputs",
+                    &[gst::Structure::static_type().into()],
+                    gst::Structure::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_update_inputs_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "animate-input",
+                    &[String::static_type().into(), String::static_type().into(),
+                      glib::variant::Variant::static_type().into(), u64::static_type().into(),
+                      String::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_animate_input_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "add-filter",
+                    &[String::static_type().into(), String::static_type().into(), String::static_type().into(),
+                      String::static_type().into(), i32::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_add_filter_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "remove-filter",
+                    &[String::static_type().into(), String::static_type().into(), String::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_remove_filter_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "move-filter",
+                    &[String::static_type().into(), String::static_type().into(), String::static_type().into(),
+                      i32::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_move_filter_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "update-filter",
+                    &[String::static_type().into(), String::static_type().into(), String::static_type().into(),
+                      String::static_type().into(), glib::variant::Variant::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_update_filter_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "select-filter-chain",
+                    &[String::static_type().into(), String::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_select_filter_chain_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "cancel-animation",
+                    &[String::static_type().into(), String::static_type().into()],
+                    bool::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_cancel_animation_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "cancel-animations",
+                    &[String::static_type().into()],
+                    Vec::<String>::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_cancel_animations_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "query-input",
+                    &[String::static_type().into(), String::static_type().into()],
+                    glib::variant::Variant::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_query_input_handler)
+                .build(),
+                glib::subclass::Signal::builder(
+                    "query-inputs",
+                    &[],
+                    gst::Structure::static_type().into(),
+                )
+                .action()
+                .class_handler(&DkcScene::class_query_inputs_handler)
                 .build()
             ]
         });
@@ -174,47 +2132,129 @@ fn handle_sink_request(
     element: &crate::scene::DkcScene,
     templ: &gst::PadTemplate,
     tmpl_caps: &gst::Caps,
-    video_mixer: &gst::Element,
-    audio_mixer: &gst::Element,
+    state: &mut State,
     video_caps: &gst::Caps,
     audio_caps: &gst::Caps,
 ) -> Option<gst::Pad> {
 
+    let (kind, mixer, prefix) = if tmpl_caps.is_strictly_equal(video_caps) {
+        (InputKind::Video, state.video_mixer.clone(), "video")
+    } else if tmpl_caps.is_strictly_equal(audio_caps) {
+        (InputKind::Audio, state.audio_mixer.clone(), "audio")
+    } else { return None };
+
     let queue = gst::ElementFactory::make("queue", None)
             .expect("Could not create input queue element.");
     element.add(&queue).expect("Could not add input queue element to the bin");
     let queue_sink_pad = queue.static_pad("sink").unwrap();
-    let queue_src_pad = queue.static_pad("src").unwrap();
-
-    if tmpl_caps.is_strictly_equal(video_caps) {
-
-        let mixer_pad = video_mixer.request_pad_simple("sink_%u").unwrap();
-        let ghost_pad_name = format!("video_{}", mixer_pad.name());
-        queue_src_pad.link(&mixer_pad).expect("Could not link queue element to video mixer");
-
-        /* Add ghost video sink pad to the element (targeting mixer sink) */
-        let ghost_pad = gst::GhostPad::from_template_with_target(&templ,
-                                                                 Some(&*ghost_pad_name),
-                                                                 &queue_sink_pad).unwrap();
-        element.add_pad(&ghost_pad).expect("Could not add ghost pad to element");
-
-        Some(ghost_pad.upcast::<gst::Pad>())
+    let mut elements = vec![queue];
+
+    /* Video inputs are cropped, flipped, fitted and converted before being handed to the mixer,
+     * audio inputs are metered */
+    let (video_elements, level) = match kind {
+        InputKind::Video => {
+            let crop = gst::ElementFactory::make("videocrop", None)
+                .expect("Could not create input videocrop element.");
+            let flip = gst::ElementFactory::make("videoflip", None)
+                .expect("Could not create input videoflip element.");
+            let aspect_crop = gst::ElementFactory::make("aspectratiocrop", None)
+                .expect("Could not create input aspectratiocrop element.");
+            let convert = gst::ElementFactory::make("videoconvert", None)
+                .expect("Could not create input videoconvert element.");
+            element.add_many(&[&crop, &flip, &aspect_crop, &convert]).expect("Could not add input video elements to the bin");
+            elements.extend_from_slice(&[crop.clone(), flip.clone(), aspect_crop.clone(), convert]);
+            (Some((crop, flip, aspect_crop)), None)
+        },
+        InputKind::Audio => {
+            let level = gst::ElementFactory::make("level", None)
+                .expect("Could not create input level element.");
+            level.set_property("interval", state.master_level.property::<u64>("interval"));
+            level.set_property("post-messages", true);
+            element.add(&level).expect("Could not add input level element to the bin");
+            elements.push(level.clone());
+            (None, Some(level))
+        }
+    };
+
+    let element_refs: Vec<&gst::Element> = elements.iter().collect();
+    gst::Element::link_many(&element_refs).expect("Could not link input elements");
+
+    /* Mixer pads are not named after the inputs, as the fillers take some of them */
+    let mixer_pad = mixer.request_pad_simple("sink_%u").unwrap();
+    let serial = match kind {
+        InputKind::Video => &mut state.next_video_input,
+        InputKind::Audio => &mut state.next_audio_input,
+    };
+    let ghost_pad_name = format!("{}_sink_{}", prefix, serial);
+    *serial += 1;
+
+    /* Audio inputs are split between the program and the monitor mixers, not monitored by default */
+    let routing = match kind {
+        InputKind::Video => {
+            elements.last().unwrap().static_pad("src").unwrap().link(&mixer_pad)
+                .expect("Could not link input elements to mixer");
+            None
+        },
+        InputKind::Audio => {
+            let tee = gst::ElementFactory::make("tee", None)
+                .expect("Could not create input routing tee element.");
+            let program_queue = gst::ElementFactory::make("queue", None)
+                .expect("Could not create input program queue element.");
+            let program = gst::ElementFactory::make("volume", None)
+                .expect("Could not create input program volume element.");
+            let monitor_queue = gst::ElementFactory::make("queue", None)
+                .expect("Could not create input monitor queue element.");
+            let routing_elements = vec![tee.clone(), program_queue.clone(), program.clone(), monitor_queue.clone()];
+            element.add_many(&routing_elements.iter().collect::<Vec<_>>())
+                .expect("Could not add input routing elements to the bin");
+
+            gst::Element::link_many(&[elements.last().unwrap(), &tee, &program_queue, &program])
+                .expect("Could not link input routing elements");
+            tee.link(&monitor_queue).expect("Could not link input monitor queue");
+            program.static_pad("src").unwrap().link(&mixer_pad)
+                .expect("Could not link input elements to mixer");
+
+            let monitor_pad = state.monitor_mixer.request_pad_simple("sink_%u").unwrap();
+            monitor_queue.static_pad("src").unwrap().link(&monitor_pad)
+                .expect("Could not link input elements to monitor mixer");
+
+            let gain_control = gst_controller::InterpolationControlSource::new();
+            gain_control.set_property("mode", gst_controller::InterpolationMode::Linear);
+            let routing = Routing { elements: routing_elements, program, monitor_pad, tracks: Mutex::new(Vec::new()),
+                                    ducking: Mutex::new(Ducking::default()), gain: Mutex::new(1.0), gain_control };
+            routing.set_mode(DEFAULT_MONITOR_MODE);
+            for track in state.tracks.iter() {
+                routing.add_track(element.upcast_ref(), track);
+            }
+            Some(Arc::new(routing))
+        }
+    };
 
-    } else if tmpl_caps.is_strictly_equal(audio_caps) {
+    /* Pads requested while running get a branch in the same state, downstream first */
+    let routing_elements = routing.iter().flat_map(|routing| routing.elements.iter());
+    for input_element in elements.iter().chain(routing_elements).rev() {
+        let _ = input_element.sync_state_with_parent();
+    }
 
-        let mixer_pad = audio_mixer.request_pad_simple("sink_%u").unwrap();
-        let ghost_pad_name = format!("audio_{}", mixer_pad.name());
-        queue_src_pad.link(&mixer_pad).expect("Could not link queue element to audio mixer");
+    /* Add ghost sink pad to the element (targeting input queue) */
+    let ghost_pad = gst::GhostPad::from_template_with_target(templ,
+                                                             Some(&*ghost_pad_name),
+                                                             &queue_sink_pad).unwrap();
+    element.add_pad(&ghost_pad).expect("Could not add ghost pad to element");
 
-        /* Add ghost video sink pad to the element (targeting mixer sink) */
-        let ghost_pad = gst::GhostPad::from_template_with_target(templ,
-                                                                 Some(&*ghost_pad_name),
-                                                                 &queue_sink_pad).unwrap();
-        element.add_pad(&ghost_pad).expect("Could not add ghost pad to element");
+    let (crop, transform) = match video_elements {
+        Some((crop, flip, aspect_crop)) => {
+            let transform = Arc::new(Transform::new(flip, aspect_crop, mixer_pad.clone()));
+            Transform::watch_size(&transform);
+            (Some(crop), Some(transform))
+        },
+        None => (None, None)
+    };
 
-        Some(ghost_pad.upcast::<gst::Pad>())
+    state.inputs.insert(ghost_pad_name, Input { kind, elements, filters: Vec::new(), chain: String::new(), chains: HashMap::new(),
+                                                crop, transform, level, routing, mixer_pad });
 
-    } else { None }
+    Some(ghost_pad.upcast::<gst::Pad>())
 
 }
 
@@ -222,42 +2262,65 @@ fn handle_src_request(
     element: &crate::scene::DkcScene,
     templ: &gst::PadTemplate,
     tmpl_caps: &gst::Caps,
-    video_tee: &gst::Element,
-    audio_tee: &gst::Element,
+    state: &mut State,
     video_caps: &gst::Caps,
     audio_caps: &gst::Caps,
 ) -> Option<gst::Pad> {
 
     if tmpl_caps.is_strictly_equal(video_caps) {
 
-        let tee_pad = video_tee.request_pad_simple("src_%u").unwrap();
+        let tee_pad = state.video_tee.request_pad_simple("src_%u").unwrap();
         let ghost_pad_name = format!("video_{}", tee_pad.name());
 
-        /* Create, add and link tee queue */
+        /* Create, add and link tee queue, followed by this output scaling and rate conversion */
         let queue = gst::ElementFactory::make("queue", None)
             .expect("Could not create queue element for video mixer tee.");
-        element.add(&queue).expect("Could not add queue element for video mixer to the bin");
-        video_tee.link(&queue).expect("Could not link queue element to video tee");
+        let scale = gst::ElementFactory::make("videoscale", None)
+            .expect("Could not create video scaler element for video mixer tee.");
+        let rate = gst::ElementFactory::make("videorate", None)
+            .expect("Could not create video rate element for video mixer tee.");
+        let capsfilter = gst::ElementFactory::make("capsfilter", None)
+            .expect("Could not create capsfilter element for video mixer tee.");
+
+        let elements = vec![queue, scale, rate, capsfilter];
+        element.add_many(&elements.iter().collect::<Vec<_>>())
+            .expect("Could not add output elements for video mixer to the bin");
+        gst::Element::link_many(&elements.iter().collect::<Vec<_>>())
+            .expect("Could not link output elements for video mixer");
+        tee_pad.link(&elements[0].static_pad("sink").unwrap())
+            .expect("Could not link queue element to video tee");
+        for output_element in elements.iter().rev() {
+            let _ = output_element.sync_state_with_parent();
+        }
 
-        /* Add ghost video src pad to the element (targeting queue src) */
-        let queue_pad = queue.static_pad("src").unwrap();
+        /* Add ghost video src pad to the element (targeting capsfilter src) */
+        let output_pad = elements[3].static_pad("src").unwrap();
         let ghost_pad = gst::GhostPad::from_template_with_target(templ,
                                                                  Some(&*ghost_pad_name),
-                                                                 &queue_pad).unwrap();
+                                                                 &output_pad).unwrap();
         element.add_pad(&ghost_pad).expect("Could not add ghost pad to element");
 
+        state.outputs.insert(ghost_pad_name, Output { tee_pad, elements, settings: OutputSettings::default(), track: 0 });
+
         Some(ghost_pad.upcast::<gst::Pad>())
 
     } else if tmpl_caps.is_strictly_equal(audio_caps) {
 
-        let tee_pad = audio_tee.request_pad_simple("src_%u").unwrap();
-        let ghost_pad_name = format!("audio_{}", tee_pad.name());
+        /* Monitor outputs hang off their own mix */
+        let (tee, prefix) = match templ.name_template().as_str() {
+            "monitor_src_%u" => (&state.monitor_tee, "monitor"),
+            _ => (&state.audio_tee, "audio"),
+        };
+        let tee_pad = tee.request_pad_simple("src_%u").unwrap();
+        let ghost_pad_name = format!("{}_{}", prefix, tee_pad.name());
 
         /* Create, add and link tee queue */
         let queue = gst::ElementFactory::make("queue", None)
             .expect("Could not create queue element for audio mixer tee.");
         element.add(&queue).expect("Could not add queue element for audio mixer to the bin");
-        audio_tee.link(&queue).expect("Could not link queue element to audio tee");
+        tee_pad.link(&queue.static_pad("sink").unwrap())
+            .expect("Could not link queue element to audio tee");
+        let _ = queue.sync_state_with_parent();
 
         /* Add ghost audio src pad to the element (targeting queue src) */
         let queue_pad = queue.static_pad("src").unwrap();
@@ -266,6 +2329,8 @@ fn handle_src_request(
                                                                  &queue_pad).unwrap();
         element.add_pad(&ghost_pad).expect("Could not add ghost pad to element");
 
+        state.outputs.insert(ghost_pad_name, Output { tee_pad, elements: vec![queue], settings: OutputSettings::default(), track: 0 });
+
         Some(ghost_pad.upcast::<gst::Pad>())
 
     } else { None }
@@ -312,6 +2377,13 @@ impl ElementImpl for DkcScene {
                 &audio_caps,
             ).expect("");
 
+            let monitor_src_pad_template = gst::PadTemplate::new(
+                "monitor_src_%u",
+                gst::PadDirection::Src,
+                gst::PadPresence::Request,
+                &audio_caps,
+            ).expect("");
+
             let video_sink_pad_template = gst::PadTemplate::new(
                 "video_sink_%u",
                 gst::PadDirection::Sink,
@@ -326,7 +2398,8 @@ impl ElementImpl for DkcScene {
                 &audio_caps,
             ).expect("");
 
-            vec![video_src_pad_template, audio_src_pad_template, video_sink_pad_template, audio_sink_pad_template]
+            vec![video_src_pad_template, audio_src_pad_template, monitor_src_pad_template,
+                 video_sink_pad_template, audio_sink_pad_template]
         });
 
         PAD_TEMPLATES.as_ref()
@@ -349,38 +2422,43 @@ impl ElementImpl for DkcScene {
             &[],
         );
 
-        let state_lock = self.state.lock().unwrap();
-        let state = state_lock.as_ref().unwrap();
-
         let tmpl_caps = templ.caps();
 
+        /* Disabled branches take no pads */
+        {
+            let settings = self.settings.lock().unwrap();
+            if (tmpl_caps.is_strictly_equal(&video_caps) && !settings.video_enabled)
+                || (tmpl_caps.is_strictly_equal(&audio_caps) && !settings.audio_enabled) {
+                return None;
+            }
+        }
+
+        let mut state_lock = self.state.lock().unwrap();
+        let state = state_lock.as_mut().unwrap();
+
         match templ.direction() {
             gst::PadDirection::Sink =>
                 match caps {
                     Some(caps_ref) =>
                         if tmpl_caps.is_always_compatible(caps_ref) {
-                            handle_sink_request(element, templ, &tmpl_caps,
-                                                &state.video_mixer, &state.audio_mixer,
+                            handle_sink_request(element, templ, &tmpl_caps, state,
                                                 &video_caps, &audio_caps)
                         } else {
                             None
                         },
-                    None => handle_sink_request(element, templ, &tmpl_caps,
-                                                &state.video_mixer, &state.audio_mixer,
+                    None => handle_sink_request(element, templ, &tmpl_caps, state,
                                                 &video_caps, &audio_caps)
                 },
             gst::PadDirection::Src =>
                 match caps {
                     Some(caps_ref) =>
                         if tmpl_caps.is_always_compatible(caps_ref) {
-                            handle_src_request(element, templ, &tmpl_caps,
-                                               &state.video_tee, &state.audio_tee,
+                            handle_src_request(element, templ, &tmpl_caps, state,
                                                &video_caps, &audio_caps)
                         } else {
                             None
                         },
-                    None => handle_src_request(element, templ, &tmpl_caps,
-                                               &state.video_tee, &state.audio_tee,
+                    None => handle_src_request(element, templ, &tmpl_caps, state,
                                                &video_caps, &audio_caps)
                 },
             _ => None,
@@ -390,35 +2468,55 @@ impl ElementImpl for DkcScene {
     fn release_pad(&self, element: &Self::Type, pad: &gst::Pad) {
         match pad.direction() {
             gst::PadDirection::Sink => {
-                match pad.downcast_ref::<gst::GhostPad>().unwrap().target() {
-                    Some(queue_sink_pad) => {
-                        let queue = queue_sink_pad.parent_element().expect("Could not get queue element from its sink pad");
-                        let mixer_sink_pad = queue.static_pad("src").expect("Could not get queue src")
-                            .peer().expect("Could not get queue src peer");
-                        let mixer = mixer_sink_pad.parent_element().expect("Could not get mixer");
-
-                        // release mixer sink pad
-                        mixer.release_request_pad(&mixer_sink_pad);
-
-                        // remove queue
-                        element.remove(&queue).expect("Could not remove sink queue from bin");
+                let input = self.state.lock().unwrap().as_mut().unwrap().inputs.remove(pad.name().as_str());
+                match input {
+                    Some(input) => {
+                        let mixer = input.mixer_pad.parent_element().expect("Could not get mixer");
+
+                        // release mixer sink pads
+                        mixer.release_request_pad(&input.mixer_pad);
+                        if let Some(routing) = input.routing.as_ref() {
+                            if let Some(monitor_mixer) = routing.monitor_pad.parent_element() {
+                                monitor_mixer.release_request_pad(&routing.monitor_pad);
+                            }
+                            routing.remove_tracks(element.upcast_ref());
+                        }
+
+                        // inputs ducked by this one stop ducking, back to their full volume
+                        if let Some(state) = self.state.lock().unwrap().as_ref() {
+                            for routing in state.inputs.values().filter_map(|input| input.routing.as_ref()) {
+                                if routing.ducking.lock().unwrap().source == pad.name().as_str() {
+                                    routing.set_ducking("source", &"".to_value());
+                                }
+                            }
+                        }
+
+                        // remove queue, filters, input and routing elements, filters waiting to be linked
+                        // being out of the bin yet
+                        let filters = input.filters.iter().map(|filter| &filter.bin).filter(|bin| bin.parent().is_some());
+                        let routing_elements = input.routing.iter().flat_map(|routing| routing.elements.iter());
+                        for input_element in input.elements.iter().chain(filters).chain(routing_elements) {
+                            let _ = input_element.set_state(gst::State::Null);
+                            element.remove(input_element).expect("Could not remove input element from bin");
+                        }
                     },
                     None => ()
                 }
             },
             gst::PadDirection::Src => {
-                match pad.downcast_ref::<gst::GhostPad>().unwrap().target() {
-                    Some(queue_src_pad) => {
-                        let queue = queue_src_pad.parent_element().expect("Could not get queue element from its src pad");
-                        let tee_src_pad = queue.static_pad("sink").expect("Could not get queue sink pad")
-                            .peer().expect("Could not get tee src pad its peer");
-                        let tee = tee_src_pad.parent_element().expect("Could not get tee element from its src pad");
+                let output = self.state.lock().unwrap().as_mut().unwrap().outputs.remove(pad.name().as_str());
+                match output {
+                    Some(output) => {
+                        let tee = output.tee_pad.parent_element().expect("Could not get tee element from its src pad");
 
                         // release tee src pad
-                        tee.release_request_pad(&tee_src_pad);
+                        tee.release_request_pad(&output.tee_pad);
 
-                        // remove queue
-                        element.remove(&queue).expect("Could not remove src queue from bin");
+                        // remove queue and output conversion elements
+                        for output_element in output.elements.iter() {
+                            let _ = output_element.set_state(gst::State::Null);
+                            element.remove(output_element).expect("Could not remove output element from bin");
+                        }
                     },
                     None => ()
                 }
@@ -431,4 +2529,66 @@ impl ElementImpl for DkcScene {
     }
 }
 
-impl BinImpl for DkcScene {}
+impl BinImpl for DkcScene {
+    /* Level messages are forwarded as "dkc-audio-level" ones, telling which input they
+     * measure ("master" for the audio mix) with peak, rms and decay arrays in dBFS */
+    fn handle_message(&self, bin: &Self::Type, message: gst::Message) {
+        let structure = match (message.view(), message.structure(), message.src()) {
+            (gst::MessageView::Element(_), Some(structure), Some(src)) if structure.name() == "level" => {
+                let state_lock = self.state.lock().unwrap();
+                let input_name = state_lock.as_ref().and_then(|state| {
+                    if src == *state.master_level.upcast_ref::<gst::Object>() {
+                        return Some("master".to_string());
+                    }
+                    state.inputs.iter()
+                        .find(|(_, input)| input.level.as_ref().map_or(false, |level| src == *level.upcast_ref::<gst::Object>()))
+                        .map(|(name, _)| name.clone())
+                });
+
+                /* Inputs ducked by this one follow its loudest channel, the master mix ducks nothing */
+                let rms = structure.get::<glib::ValueArray>("rms").ok()
+                    .filter(|_| input_name.as_deref() != Some("master"))
+                    .and_then(|values| values.iter().filter_map(|value| value.get::<f64>().ok()).reduce(f64::max));
+                if let (Some(state), Some(input_name), Some(rms)) = (state_lock.as_ref(), input_name.as_ref(), rms) {
+                    /* Muted inputs, though still measured, duck nothing */
+                    let muted = state.inputs.get(input_name)
+                        .and_then(|input| input.query("mute"))
+                        .and_then(|mute| mute.get::<bool>())
+                        .unwrap_or(false);
+                    let rms = if muted { f64::NEG_INFINITY } else { rms };
+
+                    let interval = gst::ClockTime::from_nseconds(state.master_level.property::<u64>("interval"));
+                    let running_time = bin.current_running_time().unwrap_or(gst::ClockTime::ZERO);
+                    for routing in state.inputs.values().filter_map(|input| input.routing.as_ref()) {
+                        if routing.ducking.lock().unwrap().source == *input_name {
+                            routing.duck(rms, running_time, interval);
+                        }
+                    }
+                }
+
+                input_name.map(|input_name| {
+                    let mut levels = gst::Structure::builder("dkc-audio-level")
+                        .field("input", input_name)
+                        .build();
+                    for field in ["peak", "rms", "decay"].iter() {
+                        let values = structure.get::<glib::ValueArray>(field).ok()
+                            .map(|values| values.iter().filter_map(|value| value.get::<f64>().ok())
+                                 .map(|value| value.to_send_value()).collect::<Vec<_>>())
+                            .unwrap_or_default();
+                        levels.set(field, gst::Array::from_owned(values));
+                    }
+                    if let Ok(running_time) = structure.get::<u64>("running-time") {
+                        levels.set("running-time", running_time);
+                    }
+                    levels
+                })
+            },
+            _ => None
+        };
+
+        match structure {
+            Some(structure) => self.parent_handle_message(bin, gst::message::Element::builder(structure).src(bin).build()),
+            None => self.parent_handle_message(bin, message),
+        }
+    }
+}
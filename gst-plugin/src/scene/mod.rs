@@ -26,6 +26,7 @@ mod tests {
 
     use super::super::*;
     use gst::prelude::*;
+    use scene::imp::Ducking;
 
     fn set_up() {
         use std::sync::{Once};
@@ -37,6 +38,13 @@ mod tests {
         });
     }
 
+    fn video_mixer(scene: &gst::Element) -> gst::Element {
+        scene.downcast_ref::<gst::Bin>().unwrap().iterate_elements().into_iter()
+            .map(|element| element.unwrap())
+            .find(|element| element.factory().unwrap().name() == "dkcvideomixer")
+            .expect("Could not find video mixer")
+    }
+
     #[test]
     fn test_new() {
         set_up();
@@ -185,6 +193,77 @@ mod tests {
         scene.release_request_pad(&audio_src_1);
     }
 
+    #[test]
+    fn test_canvas_properties() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        assert_eq!(scene.property::<i32>("width"), 1280);
+        assert_eq!(scene.property::<i32>("height"), 720);
+        assert_eq!(scene.property::<Option<String>>("format"), None);
+
+        scene.set_property("width", 1920i32);
+        scene.set_property("height", 1080i32);
+        scene.set_property("framerate", gst::Fraction::new(60, 1));
+        scene.set_property("format", "I420");
+        scene.set_property("audio-rate", 44100i32);
+        scene.set_property("audio-channels", 1i32);
+
+        assert_eq!(scene.property::<i32>("width"), 1920);
+        assert_eq!(scene.property::<i32>("height"), 1080);
+        assert_eq!(scene.property::<gst::Fraction>("framerate"), gst::Fraction::new(60, 1));
+        assert_eq!(scene.property::<Option<String>>("format"), Some("I420".to_string()));
+        assert_eq!(scene.property::<i32>("audio-rate"), 44100);
+        assert_eq!(scene.property::<i32>("audio-channels"), 1);
+
+        // The canvas caps are what the scene outputs.
+        let video_src_0 = scene.request_pad_simple("video_src_%u")
+            .expect("Could not get request pad 0");
+        let caps = video_src_0.query_caps(None);
+        let structure = caps.structure(0).expect("Video caps without structure");
+        assert_eq!(structure.get::<i32>("width").unwrap(), 1920);
+        assert_eq!(structure.get::<i32>("height").unwrap(), 1080);
+    }
+
+    #[test]
+    fn test_update_output_action() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_src_0 = scene.request_pad_simple("video_src_%u")
+            .expect("Could not get request pad 0");
+        let _audio_src_0 = scene.request_pad_simple("audio_src_%u")
+            .expect("Could not get request pad 0");
+
+        // Those parameters have valid values.
+        assert!(scene.emit_by_name_with_values("update-output", &["video_src_0".into(), "width".into(), (640 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-output", &["video_src_0".into(), "height".into(), (360 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-output", &["video_src_0".into(), "framerate".into(), "30000/1001".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-output", &["video_src_0".into(), "scaling-filter".into(), "lanczos".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Those parameters have invalid values.
+        assert!(!scene.emit_by_name_with_values("update-output", &["video_src_0".into(), "width".into(), (3.0 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-output", &["video_src_0".into(), "framerate".into(), "fast".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-output", &["video_src_0".into(), "scaling-filter".into(), "blurry".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Audio outputs cannot be scaled, unknown outputs cannot be updated.
+        assert!(!scene.emit_by_name_with_values("update-output", &["audio_src_0".into(), "width".into(), (640 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-output", &["video_src_1".into(), "width".into(), (640 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+    }
+
     #[test]
     fn test_update_input_action() {
         set_up();
@@ -210,6 +289,14 @@ mod tests {
                 .unwrap().get::<bool>().unwrap());
         assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "zorder".into(), (3 as u32).to_variant().to_value()])
                 .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "crop-top".into(), (10 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "crop-bottom".into(), (10 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "crop-left".into(), (10 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "crop-right".into(), (10 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
 
         // Those parameters have invalid value types.
         assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "width".into(), (3.0 as f64).to_variant().to_value()])
@@ -224,6 +311,8 @@ mod tests {
                 .unwrap().get::<bool>().unwrap());
         assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "zorder".into(), (3.0 as f64).to_variant().to_value()])
                 .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "crop-top".into(), (3 as u32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
 
         // // This parameter does not exist.
         assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "what".into(), (3.0 as f64).to_variant().to_value()])
@@ -250,9 +339,842 @@ mod tests {
         assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "volume".into(), (1 as i32).to_variant().to_value()])
                 .unwrap().get::<bool>().unwrap());
 
+        // Audio inputs cannot be cropped.
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "crop-top".into(), (10 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
         // // This parameter does not exist.
         assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "what".into(), (3.0 as f64).to_variant().to_value()])
                 .unwrap().get::<bool>().unwrap());
     }
 
+    #[test]
+    fn test_query_input_action() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        let _audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "xpos".into(), (300 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "volume".into(), (0.5 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Those parameters are read back with the type they are set with.
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "xpos".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<i32>(), Some(300));
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "alpha".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<f64>(), Some(1.0));
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_0".into(), "volume".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<f64>(), Some(0.5));
+
+        // This parameter or input does not exist.
+        assert!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "what".into()])
+                .unwrap().get::<Option<gst::glib::Variant>>().unwrap().is_none());
+        assert!(scene.emit_by_name_with_values("query-input", &["video_sink_1".into(), "xpos".into()])
+                .unwrap().get::<Option<gst::glib::Variant>>().unwrap().is_none());
+
+        let inputs = scene.emit_by_name_with_values("query-inputs", &[])
+            .unwrap().get::<gst::Structure>().unwrap();
+        assert_eq!(inputs.n_fields(), 2);
+        let video_input = inputs.get::<gst::Structure>("video_sink_0").unwrap();
+        assert_eq!(video_input.get::<i32>("xpos").unwrap(), 300);
+        assert_eq!(video_input.get::<u32>("zorder").unwrap(), 0);
+        assert_eq!(video_input.get::<i32>("crop-left").unwrap(), 0);
+        let audio_input = inputs.get::<gst::Structure>("audio_sink_0").unwrap();
+        assert_eq!(audio_input.get::<f64>("volume").unwrap(), 0.5);
+        assert!(!audio_input.get::<bool>("mute").unwrap());
+    }
+
+    #[test]
+    fn test_check_input_action() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        let mixer_pad = video_mixer(&scene).static_pad("sink_1").unwrap();
+
+        // Valid updates are accepted without being applied.
+        assert!(scene.emit_by_name_with_values("check-input", &["video_sink_0".into(), "xpos".into(), (300 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(mixer_pad.property::<i32>("xpos"), 0);
+
+        // Those are not: invalid value type, unknown parameter and input.
+        assert!(!scene.emit_by_name_with_values("check-input", &["video_sink_0".into(), "xpos".into(), (1.0 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("check-input", &["video_sink_0".into(), "volume".into(), (1.0 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("check-input", &["video_sink_1".into(), "xpos".into(), (300 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+    }
+
+    #[test]
+    fn test_update_inputs_action() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        let _audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+
+        let updates = gst::Structure::builder("updates")
+            .field("video_sink_0", gst::Structure::builder("params")
+                   .field("xpos", 10 as i32)
+                   .field("ypos", 20 as i32)
+                   .field("width", 640 as i32)
+                   .field("alpha", 10 as i32) // Invalid value type
+                   .field("what", 1.0 as f64) // Unknown parameter
+                   .build())
+            .field("audio_sink_0", gst::Structure::builder("params")
+                   .field("mute", true)
+                   .build())
+            .field("video_sink_1", gst::Structure::builder("params") // Unknown input
+                   .field("xpos", 10 as i32)
+                   .build())
+            .build();
+
+        let results = scene.emit_by_name_with_values("update-inputs", &[updates.to_value()])
+            .unwrap().get::<gst::Structure>().unwrap();
+
+        let video_results = results.get::<gst::Structure>("video_sink_0").unwrap();
+        assert!(video_results.get::<bool>("xpos").unwrap());
+        assert!(video_results.get::<bool>("ypos").unwrap());
+        assert!(video_results.get::<bool>("width").unwrap());
+        assert!(!video_results.get::<bool>("alpha").unwrap());
+        assert!(!video_results.get::<bool>("what").unwrap());
+        assert!(results.get::<gst::Structure>("audio_sink_0").unwrap().get::<bool>("mute").unwrap());
+        assert!(!results.get::<gst::Structure>("video_sink_1").unwrap().get::<bool>("xpos").unwrap());
+
+        // Not playing: the valid updates are applied right away.
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "ypos".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<i32>(), Some(20));
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "alpha".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<f64>(), Some(1.0));
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_0".into(), "mute".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<bool>(), Some(true));
+    }
+
+    #[test]
+    fn test_update_input_supersedes_batch() {
+        set_up();
+
+        let pipeline = gst::Pipeline::new(None);
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+        pipeline.add(&scene).unwrap();
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        for template in ["video_src_%u", "audio_src_%u"] {
+            let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+            pipeline.add(&sink).unwrap();
+            scene.request_pad_simple(template).unwrap().link(&sink.static_pad("sink").unwrap()).unwrap();
+        }
+
+        // Live, no frame flows while paused: batched updates wait for one.
+        pipeline.set_state(gst::State::Paused).unwrap();
+        let updates = gst::Structure::builder("updates")
+            .field("video_sink_0", gst::Structure::builder("params").field("xpos", 10 as i32).build())
+            .build();
+        scene.emit_by_name_with_values("update-inputs", &[updates.to_value()]);
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "xpos".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<i32>(), Some(0));
+
+        // A later single update is not overridden by the older batch.
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "xpos".into(), (20 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        pipeline.set_state(gst::State::Playing).unwrap();
+        assert!(pipeline.state(gst::ClockTime::from_seconds(10)).0.is_ok());
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "xpos".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<i32>(), Some(20));
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[test]
+    fn test_animate_input_action() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        let mixer_pad = video_mixer(&scene).static_pad("sink_1").unwrap();
+
+        let duration = gst::ClockTime::SECOND.nseconds();
+
+        // Those parameters can be animated with valid value types and easing curves.
+        assert!(scene.emit_by_name_with_values("animate-input", &["video_sink_0".into(), "xpos".into(), (100 as i32).to_variant().to_value(),
+                                                                  duration.to_value(), "ease-in-out".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("animate-input", &["video_sink_0".into(), "alpha".into(), (0.0 as f64).to_variant().to_value(),
+                                                                  duration.to_value(), "linear".into()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Those are not: invalid value type, discrete parameter, unknown easing curve and input.
+        assert!(!scene.emit_by_name_with_values("animate-input", &["video_sink_0".into(), "ypos".into(), (1.0 as f64).to_variant().to_value(),
+                                                                   duration.to_value(), "linear".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("animate-input", &["video_sink_0".into(), "zorder".into(), (1 as u32).to_variant().to_value(),
+                                                                   duration.to_value(), "linear".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("animate-input", &["video_sink_0".into(), "ypos".into(), (1 as i32).to_variant().to_value(),
+                                                                   duration.to_value(), "bouncy".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("animate-input", &["video_sink_1".into(), "ypos".into(), (1 as i32).to_variant().to_value(),
+                                                                   duration.to_value(), "linear".into()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Values are synced by the mixer against the stream time (the running time, without segment).
+        mixer_pad.sync_values(gst::ClockTime::from_mseconds(500)).unwrap();
+        assert_eq!(mixer_pad.property::<i32>("xpos"), 50);
+        assert_eq!(mixer_pad.property::<f64>("alpha"), 0.5);
+        mixer_pad.sync_values(gst::ClockTime::SECOND).unwrap();
+        assert_eq!(mixer_pad.property::<i32>("xpos"), 100);
+
+        assert!(scene.emit_by_name_with_values("cancel-animation", &["video_sink_0".into(), "xpos".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("cancel-animation", &["video_sink_0".into(), "xpos".into()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Updating a parameter stops its animation.
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "alpha".into(), (0.8 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("cancel-animation", &["video_sink_0".into(), "alpha".into()])
+                .unwrap().get::<bool>().unwrap());
+
+        // All the animations of an input can be cancelled at once.
+        for param in ["xpos", "ypos"].iter() {
+            assert!(scene.emit_by_name_with_values("animate-input", &["video_sink_0".into(), (*param).into(), (10 as i32).to_variant().to_value(),
+                                                                      duration.to_value(), "linear".into()])
+                    .unwrap().get::<bool>().unwrap());
+        }
+        assert_eq!(scene.emit_by_name_with_values("cancel-animations", &["video_sink_0".into()])
+                   .unwrap().get::<Vec<String>>().unwrap(), vec!["xpos".to_string(), "ypos".to_string()]);
+        assert!(scene.emit_by_name_with_values("cancel-animations", &["video_sink_0".into()])
+                .unwrap().get::<Vec<String>>().unwrap().is_empty());
+        assert!(scene.emit_by_name_with_values("cancel-animations", &["video_sink_1".into()])
+                .unwrap().get::<Vec<String>>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_animate_filled_input() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        let mixer_pad = video_mixer(&scene).static_pad("sink_1").unwrap();
+        let aspect_crop = scene.downcast_ref::<gst::Bin>().unwrap().iterate_elements().into_iter()
+            .map(|element| element.unwrap())
+            .find(|element| element.factory().unwrap().name() == "aspectratiocrop")
+            .expect("Could not find the input aspectratiocrop");
+
+        for (param, value) in [("width", 100i32), ("height", 100i32)].iter() {
+            assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), (*param).into(), value.to_variant().to_value()])
+                    .unwrap().get::<bool>().unwrap());
+        }
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "scale-mode".into(), "fill".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(aspect_crop.property::<gst::Fraction>("aspect-ratio"), gst::Fraction::new(1, 1));
+
+        // The source is cropped to the bounding box along the animation.
+        assert!(scene.emit_by_name_with_values("animate-input", &["video_sink_0".into(), "width".into(), (200 as i32).to_variant().to_value(),
+                                                                  gst::ClockTime::SECOND.nseconds().to_value(), "linear".into()])
+                .unwrap().get::<bool>().unwrap());
+        mixer_pad.sync_values(gst::ClockTime::from_mseconds(500)).unwrap();
+        assert_eq!(aspect_crop.property::<gst::Fraction>("aspect-ratio"), gst::Fraction::new(3, 2));
+        mixer_pad.sync_values(gst::ClockTime::SECOND).unwrap();
+        assert_eq!(aspect_crop.property::<gst::Fraction>("aspect-ratio"), gst::Fraction::new(2, 1));
+    }
+
+    #[test]
+    fn test_audio_levels() {
+        set_up();
+
+        let pipeline = gst::Pipeline::new(None);
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+        pipeline.add(&scene).unwrap();
+
+        scene.set_property("level-interval", 50_000_000u64);
+        let _audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+
+        let queue = scene.static_pad("audio_sink_0").unwrap()
+            .downcast::<gst::GhostPad>().unwrap().target().unwrap().parent_element().unwrap();
+        let level = queue.static_pad("src").unwrap().peer().unwrap().parent_element().unwrap();
+        assert_eq!(level.factory().unwrap().name(), "level");
+        assert_eq!(level.property::<u64>("interval"), 50_000_000);
+
+        // Level messages are forwarded along with the input they measure.
+        let mut peak = gst::glib::ValueArray::new(2);
+        peak.append(&(-6.0f64).to_value());
+        peak.append(&(-12.0f64).to_value());
+        level.post_message(gst::message::Element::builder(
+            gst::Structure::builder("level").field("peak", peak).build()).src(&level).build()).unwrap();
+
+        let message = pipeline.bus().unwrap()
+            .timed_pop_filtered(gst::ClockTime::ZERO, &[gst::MessageType::Element])
+            .expect("No level message");
+        let structure = message.structure().unwrap();
+        assert_eq!(structure.name(), "dkc-audio-level");
+        assert_eq!(structure.get::<String>("input").unwrap(), "audio_sink_0");
+        assert_eq!(structure.get::<gst::Array>("peak").unwrap().as_slice().iter()
+                   .map(|value| value.get::<f64>().unwrap()).collect::<Vec<_>>(), vec![-6.0, -12.0]);
+        assert!(structure.get::<gst::Array>("rms").unwrap().as_slice().is_empty());
+    }
+
+    #[test]
+    fn test_blend_mode() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "blend-mode".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>().as_deref(), Some("normal"));
+
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "blend-mode".into(), "multiply".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "blend-mode".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>().as_deref(), Some("multiply"));
+
+        // Unknown blend modes are refused.
+        assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "blend-mode".into(), "overlay".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("animate-input", &["video_sink_0".into(), "blend-mode".into(), "screen".to_variant().to_value(),
+                                                                   gst::ClockTime::SECOND.nseconds().to_value(), "linear".into()])
+                .unwrap().get::<bool>().unwrap());
+    }
+
+    #[test]
+    fn test_sync_offset() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        let _audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+
+        for (sink_pad_name, param) in [("video_sink_0", "video-offset"), ("audio_sink_0", "audio-offset")].iter() {
+            assert_eq!(scene.emit_by_name_with_values("query-input", &[sink_pad_name.to_value(), param.to_value()])
+                       .unwrap().get::<gst::glib::Variant>().unwrap().get::<u32>(), Some(0));
+
+            assert!(scene.emit_by_name_with_values("update-input", &[sink_pad_name.to_value(), param.to_value(), (250 as u32).to_variant().to_value()])
+                    .unwrap().get::<bool>().unwrap());
+            assert_eq!(scene.emit_by_name_with_values("query-input", &[sink_pad_name.to_value(), param.to_value()])
+                       .unwrap().get::<gst::glib::Variant>().unwrap().get::<u32>(), Some(250));
+
+            // Offsets are positive and bounded, and cannot be animated.
+            assert!(!scene.emit_by_name_with_values("update-input", &[sink_pad_name.to_value(), param.to_value(), (-250 as i32).to_variant().to_value()])
+                    .unwrap().get::<bool>().unwrap());
+            assert!(!scene.emit_by_name_with_values("update-input", &[sink_pad_name.to_value(), param.to_value(), (60_000 as u32).to_variant().to_value()])
+                    .unwrap().get::<bool>().unwrap());
+            assert!(!scene.emit_by_name_with_values("animate-input", &[sink_pad_name.to_value(), param.to_value(), (500 as u32).to_variant().to_value(),
+                                                                       gst::ClockTime::SECOND.nseconds().to_value(), "linear".into()])
+                    .unwrap().get::<bool>().unwrap());
+        }
+
+        // Each input has its own offset.
+        assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "audio-offset".into(), (250 as u32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Input queues hold the delayed data, on top of their usual second.
+        let mixer_pad = video_mixer(&scene).static_pad("sink_1").unwrap();
+        assert_eq!(mixer_pad.peer().unwrap().offset(), 250_000_000);
+        let queues = scene.downcast_ref::<gst::Bin>().unwrap().iterate_elements().into_iter()
+            .map(|element| element.unwrap())
+            .filter(|element| element.factory().unwrap().name() == "queue")
+            .map(|queue| queue.property::<u64>("max-size-time"))
+            .collect::<Vec<u64>>();
+        assert_eq!(queues, vec![1_250_000_000; 4]); // Audio inputs have program and monitor queues
+    }
+
+    #[test]
+    fn test_monitor_routing() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        let audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+        let monitor_src_0 = scene.request_pad_simple("monitor_src_%u")
+            .expect("Could not get monitor pad 0");
+        assert_eq!(monitor_src_0.name(), "monitor_src_0");
+
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_0".into(), "monitor".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>().as_deref(), Some("off"));
+
+        for mode in ["monitor-only", "monitor-and-output", "off"].iter() {
+            assert!(scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "monitor".into(), mode.to_variant().to_value()])
+                    .unwrap().get::<bool>().unwrap());
+            assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_0".into(), "monitor".into()])
+                       .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>().as_deref(), Some(*mode));
+        }
+
+        // Unknown modes and video inputs are refused.
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "monitor".into(), "cue".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "monitor".into(), "monitor-only".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Inputs feed both the program and the monitor mixers, until they are released.
+        let audio_mixers = || scene.downcast_ref::<gst::Bin>().unwrap().iterate_elements().into_iter()
+            .map(|element| element.unwrap())
+            .filter(|element| element.factory().unwrap().name() == "audiomixer")
+            .map(|mixer| mixer.num_sink_pads())
+            .collect::<Vec<u16>>();
+        let sorted = |mut counts: Vec<u16>| { counts.sort_unstable(); counts };
+        // The program mixer also has the silence filler.
+        assert_eq!(sorted(audio_mixers()), vec![1, 2]);
+        scene.release_request_pad(&audio_sink_0);
+        assert_eq!(sorted(audio_mixers()), vec![0, 1]);
+
+        scene.release_request_pad(&monitor_src_0);
+    }
+
+    #[test]
+    fn test_audio_tracks() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+        let audio_mixers = || scene.downcast_ref::<gst::Bin>().unwrap().iterate_elements().into_iter()
+            .map(|element| element.unwrap())
+            .filter(|element| element.factory().unwrap().name() == "audiomixer")
+            .count();
+
+        let _audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+        let _audio_src_0 = scene.request_pad_simple("audio_src_%u")
+            .expect("Could not get audio src pad 0");
+
+        // Program and monitor mixers, then two more tracks.
+        assert_eq!(audio_mixers(), 2);
+        scene.set_property("audio-tracks", 3u32);
+        assert_eq!(audio_mixers(), 4);
+
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_0".into(), "tracks".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>().as_deref(), Some(""));
+        assert!(scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "tracks".into(), "2, 1".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_0".into(), "tracks".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>().as_deref(), Some("1,2"));
+
+        // Inputs added afterwards get a branch into every track.
+        let _audio_sink_1 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 1");
+        assert!(scene.emit_by_name_with_values("update-input", &["audio_sink_1".into(), "tracks".into(), "2".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Tracks go from 1 to the number of tracks past the program one.
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "tracks".into(), "3".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "tracks".into(), "0".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "tracks".into(), "music".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Audio outputs record one of the tracks.
+        assert!(scene.emit_by_name_with_values("update-output", &["audio_src_0".into(), "track".into(), (2 as u32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-output", &["audio_src_0".into(), "track".into(), (3 as u32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Removed tracks are left by the inputs and the outputs.
+        scene.set_property("audio-tracks", 2u32);
+        assert_eq!(audio_mixers(), 3);
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_0".into(), "tracks".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>().as_deref(), Some("1"));
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_1".into(), "tracks".into(), "2".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+    }
+
+    #[test]
+    fn test_ducking_gain() {
+        let ducking = Ducking { source: "audio_sink_0".to_string(), threshold: -30.0, ratio: 4.0, attack: 100, release: 400 };
+
+        // 20 dB over the threshold are brought down to 5 dB over it.
+        assert_eq!(ducking.target_gain(-40.0), 1.0);
+        assert!((ducking.target_gain(-10.0) - 10f64.powf(-15.0 / 20.0)).abs() < 1e-9);
+
+        // Attack and release take several level intervals.
+        let gain = ducking.step(1.0, -10.0, 50.0);
+        assert!(gain < 1.0 && gain > ducking.target_gain(-10.0));
+        assert!((ducking.step(gain, -10.0, 100.0) - ducking.target_gain(-10.0)).abs() < 1e-9);
+        assert!((ducking.step(0.5, -60.0, 100.0) - 0.625).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ducking_params() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+        let _audio_sink_1 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 1");
+
+        let mut params = gst::Structure::new_empty("params");
+        params.set("duck-source", "audio_sink_0");
+        params.set("duck-threshold", -40.0f64);
+        params.set("duck-ratio", 8.0f64);
+        params.set("duck-attack", 20u32);
+        params.set("duck-release", 1000u32);
+        let updates = gst::Structure::builder("updates").field("audio_sink_1", params).build();
+
+        let results = scene.emit_by_name_with_values("update-inputs", &[updates.to_value()])
+            .unwrap().get::<gst::Structure>().unwrap();
+        let results = results.get::<gst::Structure>("audio_sink_1").unwrap();
+        assert!(results.iter().all(|(_, updated)| updated.get::<bool>().unwrap()));
+
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_1".into(), "duck-source".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>().as_deref(), Some("audio_sink_0"));
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_1".into(), "duck-ratio".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<f64>(), Some(8.0));
+
+        // Thresholds are in dBFS, ratios from 1 and times up to 10 seconds.
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_1".into(), "duck-threshold".into(), (6.0 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_1".into(), "duck-ratio".into(), (0.5 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_1".into(), "duck-release".into(), (60_000 as u32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Releasing the sidechain input stops the ducking.
+        scene.release_request_pad(&audio_sink_0);
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["audio_sink_1".into(), "duck-source".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>().as_deref(), Some(""));
+    }
+
+    fn sizing_policy(mixer_pad: &gst::Pad) -> i32 {
+        mixer_pad.property_value("sizing-policy").transform::<i32>().unwrap().get::<i32>().unwrap()
+    }
+
+    #[test]
+    fn test_transform_input() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        let mixer_pad = video_mixer(&scene).static_pad("sink_1").unwrap();
+
+        // Those parameters have valid values.
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "width".into(), (640 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "height".into(), (480 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "scale-mode".into(), "fit".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "rotation".into(), (90 as u32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "flip".into(), "horizontal".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Those parameters have invalid values.
+        assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "scale-mode".into(), "zoom".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "rotation".into(), (45 as u32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "flip".into(), (1 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "scale-mode".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<String>(), Some("fit".to_string()));
+        assert_eq!(scene.emit_by_name_with_values("query-input", &["video_sink_0".into(), "rotation".into()])
+                   .unwrap().get::<gst::glib::Variant>().unwrap().get::<u32>(), Some(90));
+        assert_eq!(sizing_policy(&mixer_pad), 1); // keep-aspect-ratio
+
+        // Unscaled sources are drawn at their natural size.
+        assert!(scene.emit_by_name_with_values("update-input", &["video_sink_0".into(), "scale-mode".into(), "none".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(mixer_pad.property::<i32>("width"), 0);
+        assert_eq!(sizing_policy(&mixer_pad), 0); // none
+
+        // Audio inputs cannot be transformed.
+        let _audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+        assert!(!scene.emit_by_name_with_values("update-input", &["audio_sink_0".into(), "rotation".into(), (90 as u32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+    }
+
+    /* Factory names of the elements linked after the input queue, up to the crop */
+    fn filter_chain(scene: &gst::Element, sink_pad_name: &str) -> Vec<String> {
+        let mut element = scene.static_pad(sink_pad_name).unwrap()
+            .downcast::<gst::GhostPad>().unwrap().target().unwrap().parent_element().unwrap();
+        let mut chain = Vec::new();
+
+        loop {
+            element = element.static_pad("src").unwrap().peer().unwrap().parent_element().unwrap();
+            match element.downcast_ref::<gst::Bin>() {
+                Some(bin) => chain.push(bin.iterate_elements().into_iter()
+                                        .map(|element| element.unwrap().factory().unwrap().name().to_string())
+                                        .find(|name| name != "videoconvert" && name != "audioconvert").unwrap()),
+                None => return chain
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_chain() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        let _audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+
+        assert!(scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "".into(), "balance".into(), "color-balance".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "".into(), "lut".into(), "lut".into(), (0 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "".into(), "blur".into(), "blur".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "video_sink_0"), vec!["dkclut", "videobalance", "gaussianblur"]);
+
+        // Duplicate names, unknown kinds and video filters on audio inputs are refused.
+        assert!(!scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "".into(), "lut".into(), "gamma".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "".into(), "what".into(), "what".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("add-filter", &["audio_sink_0".into(), "".into(), "gamma".into(), "gamma".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+
+        assert!(scene.emit_by_name_with_values("update-filter", &["video_sink_0".into(), "".into(), "balance".into(), "saturation".into(), (1.5 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-filter", &["video_sink_0".into(), "".into(), "lut".into(), "intensity".into(), (1 as i32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-filter", &["video_sink_0".into(), "".into(), "balance".into(), "what".into(), (1.5 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        assert!(scene.emit_by_name_with_values("move-filter", &["video_sink_0".into(), "".into(), "lut".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "video_sink_0"), vec!["videobalance", "gaussianblur", "dkclut"]);
+
+        assert!(scene.emit_by_name_with_values("remove-filter", &["video_sink_0".into(), "".into(), "blur".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("remove-filter", &["video_sink_0".into(), "".into(), "blur".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "video_sink_0"), vec!["videobalance", "dkclut"]);
+
+        // Keying filters are adjusted live like any other.
+        assert!(scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "".into(), "key".into(), "chroma-key".into(), (0 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "".into(), "luma".into(), "luma-key".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-filter", &["video_sink_0".into(), "".into(), "key".into(), "key-color".into(), (0x0000ff as u32).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-filter", &["video_sink_0".into(), "".into(), "luma".into(), "luma-min".into(), (0.1 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "video_sink_0"), vec!["dkcchromakey", "videobalance", "dkclut", "dkclumakey"]);
+
+        assert!(scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "".into(), "mask".into(), "alpha-mask".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-filter", &["video_sink_0".into(), "".into(), "mask".into(), "mode".into(), "luma".to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "video_sink_0"), vec!["dkcchromakey", "videobalance", "dkclut", "dkclumakey", "dkcalphamask"]);
+    }
+
+    #[test]
+    fn test_select_filter_chain() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+
+        // Chains are kept apart, filter names being unique within a chain only.
+        assert!(scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "".into(), "look".into(), "gamma".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "other".into(), "look".into(), "lut".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("add-filter", &["video_sink_0".into(), "other".into(), "blur".into(), "blur".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-filter", &["video_sink_0".into(), "other".into(), "look".into(), "intensity".into(), (0.5 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "video_sink_0"), vec!["gamma"]);
+
+        // Only the selected chain is linked, and in the bin.
+        let elements = |scene: &gst::Element| scene.downcast_ref::<gst::Bin>().unwrap().children().len();
+        let element_count = elements(&scene);
+        assert!(scene.emit_by_name_with_values("select-filter-chain", &["video_sink_0".into(), "other".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "video_sink_0"), vec!["dkclut", "gaussianblur"]);
+        assert_eq!(elements(&scene), element_count + 1);
+
+        // Back to back operations on the selected chain end up linked in order.
+        assert!(scene.emit_by_name_with_values("remove-filter", &["video_sink_0".into(), "other".into(), "blur".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("move-filter", &["video_sink_0".into(), "".into(), "look".into(), (0 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("select-filter-chain", &["video_sink_0".into(), "".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("select-filter-chain", &["video_sink_0".into(), "other".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "video_sink_0"), vec!["dkclut"]);
+
+        assert!(scene.emit_by_name_with_values("select-filter-chain", &["video_sink_0".into(), "empty".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(filter_chain(&scene, "video_sink_0").is_empty());
+        assert!(!scene.emit_by_name_with_values("select-filter-chain", &["video_sink_1".into(), "other".into()])
+                .unwrap().get::<bool>().unwrap());
+    }
+
+    #[test]
+    fn test_audio_filter_chain() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        let _audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+
+        for (name, kind) in [("gate", "noise-gate"), ("compressor", "compressor"), ("eq", "equalizer"),
+                             ("high-pass", "high-pass")].iter() {
+            assert!(scene.emit_by_name_with_values("add-filter", &["audio_sink_0".into(), "".into(), name.to_value(), kind.to_value(), (-1 as i32).into()])
+                    .unwrap().get::<bool>().unwrap());
+        }
+        assert!(scene.emit_by_name_with_values("add-filter", &["audio_sink_0".into(), "".into(), "gain".into(), "gain".into(), (0 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "audio_sink_0"),
+                   vec!["volume", "audiodynamic", "audiodynamic", "equalizer-nbands", "audiocheblimit"]);
+
+        // Filter properties, including the ones of equalizer bands, are adjusted live.
+        assert!(scene.emit_by_name_with_values("update-filter", &["audio_sink_0".into(), "".into(), "gain".into(), "volume".into(), (2.0 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-filter", &["audio_sink_0".into(), "".into(), "eq".into(), "band0::gain".into(), (-6.0 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(scene.emit_by_name_with_values("update-filter", &["audio_sink_0".into(), "".into(), "high-pass".into(), "cutoff".into(), (120.0 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+        assert!(!scene.emit_by_name_with_values("update-filter", &["audio_sink_0".into(), "".into(), "eq".into(), "band9::gain".into(), (-6.0 as f64).to_variant().to_value()])
+                .unwrap().get::<bool>().unwrap());
+
+        // Video filters are refused.
+        assert!(!scene.emit_by_name_with_values("add-filter", &["audio_sink_0".into(), "".into(), "gamma".into(), "gamma".into(), (-1 as i32).into()])
+                .unwrap().get::<bool>().unwrap());
+
+        assert!(scene.emit_by_name_with_values("remove-filter", &["audio_sink_0".into(), "".into(), "compressor".into()])
+                .unwrap().get::<bool>().unwrap());
+        assert_eq!(filter_chain(&scene, "audio_sink_0"),
+                   vec!["volume", "audiodynamic", "equalizer-nbands", "audiocheblimit"]);
+    }
+
+
+    #[test]
+    fn test_enable_branches() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        // Disabled branches take no pads.
+        scene.set_property("enable-video", false);
+        assert!(!scene.property::<bool>("enable-video"));
+        assert!(scene.request_pad_simple("video_sink_%u").is_none());
+        assert!(scene.request_pad_simple("video_src_%u").is_none());
+        let audio_sink_0 = scene.request_pad_simple("audio_sink_%u")
+            .expect("Could not get request pad 0");
+        assert_eq!(audio_sink_0.name(), "audio_sink_0");
+
+        // Branches with pads cannot be disabled.
+        scene.set_property("enable-audio", false);
+        assert!(scene.property::<bool>("enable-audio"));
+        scene.release_request_pad(&audio_sink_0);
+        scene.set_property("enable-audio", false);
+        assert!(!scene.property::<bool>("enable-audio"));
+
+        // Inputs are named after their own kind, not the mixer pads the fillers share.
+        scene.set_property("enable-video", true);
+        let video_sink_0 = scene.request_pad_simple("video_sink_%u")
+            .expect("Could not get request pad 0");
+        assert_eq!(video_sink_0.name(), "video_sink_0");
+        scene.release_request_pad(&video_sink_0);
+    }
+
+    #[test]
+    fn test_disable_audio_while_playing() {
+        set_up();
+
+        let pipeline = gst::Pipeline::new(None);
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+        let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+        pipeline.add_many(&[&scene, &sink]).unwrap();
+        scene.request_pad_simple("video_src_%u").unwrap().link(&sink.static_pad("sink").unwrap()).unwrap();
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+        assert!(pipeline.state(gst::ClockTime::from_seconds(10)).0.is_ok());
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        // Stopping the audio branch does not wait on its own level messages.
+        let (done_sender, done) = std::sync::mpsc::channel();
+        let disabled_scene = scene.clone();
+        std::thread::spawn(move || {
+            disabled_scene.set_property("enable-audio", false);
+            let _ = done_sender.send(());
+        });
+        assert!(done.recv_timeout(std::time::Duration::from_secs(10)).is_ok());
+        assert!(!scene.property::<bool>("enable-audio"));
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[test]
+    fn test_background() {
+        set_up();
+
+        let scene = gst::ElementFactory::make("dkcscene", Some("scene"))
+            .expect("Could not make dkcscene element");
+
+        assert_eq!(scene.property::<String>("background"), "black");
+        scene.set_property("background", "white");
+        assert_eq!(scene.property::<String>("background"), "white");
+        assert_eq!(video_mixer(&scene).property_value("background").serialize().unwrap(), "white");
+
+        // Unknown backgrounds are refused.
+        scene.set_property("background", "purple");
+        assert_eq!(scene.property::<String>("background"), "white");
+
+        scene.set_property("background", "color");
+        scene.set_property("background-color", 0xff336699u32);
+        assert_eq!(video_mixer(&scene).property_value("background").serialize().unwrap(), "color");
+        assert_eq!(video_mixer(&scene).property::<u32>("background-color"), 0xff336699);
+    }
+
 }
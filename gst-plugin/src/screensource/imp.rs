@@ -0,0 +1,124 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+#[derive(Default)]
+pub struct DkcScreenSource {
+
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcscreensource",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster screen capture source element"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcScreenSource {
+    const NAME: &'static str = "DkcScreenSource";
+    type Type = super::DkcScreenSource;
+    type ParentType = gst::Bin;
+}
+
+impl ObjectImpl for DkcScreenSource {
+    fn constructed(&self, obj: &Self::Type) {
+        let video_elem = gst::ElementFactory::make("ximagesrc", Some("screensource"))
+            .expect("Could not create screen capture element.");
+        let convert_elem = gst::ElementFactory::make("videoconvert", Some("screenconverter"))
+            .expect("Could not create video converter element.");
+
+        video_elem.set_property("use-damage", false);
+
+        self.add_element(obj, &video_elem).expect("Could not add video element to this source");
+        self.add_element(obj, &convert_elem).expect("Could not add converter element to this source");
+
+        video_elem.link(&convert_elem).expect("Could not link video element to converter element.");
+
+        let video_pad = convert_elem.static_pad("src").unwrap();
+        let video_ghost_pad = gst::GhostPad::with_target(Some("video_src"), &video_pad).unwrap();
+
+        obj.add_pad(&video_ghost_pad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecBoolean::new(
+                    "show-pointer",
+                    "Show pointer",
+                    "Whether the mouse pointer is captured along with the screen",
+                    true,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecString::new(
+                    "display-name",
+                    "Display name",
+                    "Name of the X display to capture, the default one if unset",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "show-pointer" | "display-name" =>
+                obj.by_name("screensource").unwrap().set_property_from_value(pspec.name(), value),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "show-pointer" | "display-name" =>
+                obj.by_name("screensource").unwrap().property_value(pspec.name()),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcScreenSource {}
+
+impl ElementImpl for DkcScreenSource {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Screen Source",
+                "Video",
+                "DankCaster screen capture source element",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // src pad capabilities
+            let video_caps = gst::Caps::builder("video/x-raw")
+                .build();
+
+            let video_src_pad_template = gst::PadTemplate::new(
+                "video_src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &video_caps,
+            )
+            .unwrap();
+
+            vec![video_src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for DkcScreenSource {}
@@ -0,0 +1,73 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcScreenSource(ObjectSubclass<imp::DkcScreenSource>) @extends gst::Bin, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcScreenSource {}
+unsafe impl Sync for DkcScreenSource {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkcscreensource",
+        gst::Rank::None,
+        DkcScreenSource::static_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    /* Play the source into fake sinks, true once every one of them got a first buffer */
+    fn preroll(source: &gst::Element) -> bool {
+        let pipeline = gst::Pipeline::new(None);
+        pipeline.add(source).unwrap();
+
+        for pad in source.src_pads() {
+            let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+            pipeline.add(&sink).unwrap();
+            pad.link(&sink.static_pad("sink").unwrap()).unwrap();
+        }
+
+        let prerolled = pipeline.set_state(gst::State::Playing).is_ok()
+            && pipeline.state(gst::ClockTime::from_seconds(10)).0.is_ok();
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        prerolled
+    }
+
+    #[test]
+    fn test_preroll() {
+        set_up();
+
+        // Screen capture needs an X display to capture.
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+
+        let source = gst::ElementFactory::make("dkcscreensource", Some("screen"))
+            .expect("Could not make dkcscreensource element");
+
+        assert!(preroll(&source));
+    }
+
+}
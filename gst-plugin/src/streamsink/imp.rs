@@ -0,0 +1,163 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use crate::recordsink::make_first;
+
+#[derive(Default)]
+pub struct DkcStreamSink {
+
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcstreamsink",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster streaming sink element"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcStreamSink {
+    const NAME: &'static str = "DkcStreamSink";
+    type Type = super::DkcStreamSink;
+    type ParentType = gst::Bin;
+}
+
+impl ObjectImpl for DkcStreamSink {
+    fn constructed(&self, obj: &Self::Type) {
+        /* FLV for RTMP servers:
+         * videoconvert ! queue ! h264 ! mux, audioconvert ! audioresample ! aac ! queue ! mux */
+        let video_convert = gst::ElementFactory::make("videoconvert", Some("videoconverter"))
+            .expect("Could not create video converter element.");
+        let video_queue = gst::ElementFactory::make("queue", Some("videoqueue"))
+            .expect("Could not create video queue element.");
+        let video_enc = make_first(&["x264enc", "openh264enc"], "videoencoder");
+        let video_parse = gst::ElementFactory::make("h264parse", Some("videoparser"))
+            .expect("Could not create video parser element.");
+        let audio_convert = gst::ElementFactory::make("audioconvert", Some("audioconverter"))
+            .expect("Could not create audio converter element.");
+        let audio_resample = gst::ElementFactory::make("audioresample", Some("audioresampler"))
+            .expect("Could not create audio resampler element.");
+        let audio_enc = make_first(&["fdkaacenc", "avenc_aac", "voaacenc"], "audioencoder");
+        let audio_queue = gst::ElementFactory::make("queue", Some("audioqueue"))
+            .expect("Could not create audio queue element.");
+        let mux_elem = gst::ElementFactory::make("flvmux", Some("muxer"))
+            .expect("Could not create muxer element.");
+        let server_elem = make_first(&["rtmp2sink", "rtmpsink"], "streamserver");
+
+        if video_enc.find_property("tune").is_some() {
+            video_enc.set_property_from_str("tune", "zerolatency");
+        }
+        if video_enc.find_property("key-int-max").is_some() {
+            video_enc.set_property("key-int-max", 60u32);
+        }
+        mux_elem.set_property("streamable", true);
+
+        let elements = [&video_convert, &video_queue, &video_enc, &video_parse,
+                        &audio_convert, &audio_resample, &audio_enc, &audio_queue,
+                        &mux_elem, &server_elem];
+        for element in elements {
+            self.add_element(obj, element).expect("Could not add element to this sink");
+        }
+
+        gst::Element::link_many(&[&video_convert, &video_queue, &video_enc, &video_parse, &mux_elem])
+            .expect("Could not link video elements.");
+        gst::Element::link_many(&[&audio_convert, &audio_resample, &audio_enc, &audio_queue, &mux_elem])
+            .expect("Could not link audio elements.");
+        mux_elem.link(&server_elem).expect("Could not link muxer element to server element.");
+
+        let video_pad = video_convert.static_pad("sink").unwrap();
+        let audio_pad = audio_convert.static_pad("sink").unwrap();
+
+        let video_ghost_pad = gst::GhostPad::with_target(Some("video_sink"), &video_pad).unwrap();
+        let audio_ghost_pad = gst::GhostPad::with_target(Some("audio_sink"), &audio_pad).unwrap();
+
+        obj.add_pad(&video_ghost_pad).unwrap();
+        obj.add_pad(&audio_ghost_pad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::new(
+                    "location",
+                    "Location",
+                    "RTMP URL to stream to, holding the stream key",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "location" => {
+                gst::debug!(CAT, obj: obj, "Streaming to {:?}", value.get::<Option<String>>());
+                obj.by_name("streamserver").unwrap().set_property_from_value("location", value)
+            },
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "location" => obj.by_name("streamserver").unwrap().property_value("location"),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcStreamSink {}
+
+impl ElementImpl for DkcStreamSink {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Stream Sink",
+                "Audio/Video",
+                "DankCaster sink element streaming to an RTMP server",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // sink pad capabilities
+            let video_caps = gst::Caps::builder("video/x-raw")
+                .build();
+            let audio_caps = gst::Caps::builder("audio/x-raw")
+                .build();
+
+            let video_sink_pad_template = gst::PadTemplate::new(
+                "video_sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &video_caps,
+            )
+            .unwrap();
+
+            let audio_sink_pad_template = gst::PadTemplate::new(
+                "audio_sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &audio_caps,
+            )
+            .unwrap();
+
+            vec![video_sink_pad_template, audio_sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for DkcStreamSink {}
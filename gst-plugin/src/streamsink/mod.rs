@@ -0,0 +1,54 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcStreamSink(ObjectSubclass<imp::DkcStreamSink>) @extends gst::Bin, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcStreamSink {}
+unsafe impl Sync for DkcStreamSink {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkcstreamsink",
+        gst::Rank::None,
+        DkcStreamSink::static_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    #[test]
+    fn test_properties() {
+        set_up();
+
+        let sink = gst::ElementFactory::make("dkcstreamsink", Some("stream"))
+            .expect("Could not make dkcstreamsink element");
+
+        assert!(sink.static_pad("video_sink").is_some());
+        assert!(sink.static_pad("audio_sink").is_some());
+
+        sink.set_property("location", "rtmp://localhost/live/key");
+        assert_eq!(sink.property::<Option<String>>("location").as_deref(), Some("rtmp://localhost/live/key"));
+    }
+
+}
@@ -0,0 +1,206 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+const DEFAULT_COLOR: u32 = 0xffffffff;
+const DEFAULT_WIDTH: i32 = 1280;
+const DEFAULT_HEIGHT: i32 = 720;
+
+struct Settings {
+    width: i32,
+    height: i32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DkcTextSource {
+    settings: Mutex<Settings>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkctextsource",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster text source element"),
+    )
+});
+
+impl DkcTextSource {
+    fn update_caps(&self, obj: &super::DkcTextSource) {
+        let settings = self.settings.lock().unwrap();
+        let caps = gst::Caps::builder("video/x-raw")
+            .field("format", "BGRA")
+            .field("width", settings.width)
+            .field("height", settings.height)
+            .build();
+
+        gst::debug!(CAT, obj: obj, "Setting caps to {}", caps);
+        obj.by_name("textcapsfilter").unwrap().set_property("caps", &caps);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcTextSource {
+    const NAME: &'static str = "DkcTextSource";
+    type Type = super::DkcTextSource;
+    type ParentType = gst::Bin;
+}
+
+impl ObjectImpl for DkcTextSource {
+    fn constructed(&self, obj: &Self::Type) {
+        /* Text is rendered over a fully transparent frame */
+        let video_elem = gst::ElementFactory::make("videotestsrc", Some("textbackground"))
+            .expect("Could not create video source element.");
+        let video_capsf = gst::ElementFactory::make("capsfilter", Some("textcapsfilter"))
+            .expect("Could not create video capsfilter element.");
+        let text_elem = gst::ElementFactory::make("textoverlay", Some("textoverlay"))
+            .expect("Could not create text overlay element.");
+
+        video_elem.set_property_from_str("pattern", "solid-color");
+        video_elem.set_property("foreground-color", 0u32);
+        video_elem.set_property("is-live", true);
+        text_elem.set_property_from_str("valignment", "top");
+        text_elem.set_property_from_str("halignment", "left");
+        text_elem.set_property("color", DEFAULT_COLOR);
+
+        self.add_element(obj, &video_elem).expect("Could not add video element to this source");
+        self.add_element(obj, &video_capsf).expect("Could not add video caps filter to this source");
+        self.add_element(obj, &text_elem).expect("Could not add text element to this source");
+
+        video_elem.link(&video_capsf).expect("Could not link video element to video capsfilter.");
+        video_capsf.link_pads(Some("src"), &text_elem, Some("video_sink"))
+            .expect("Could not link video capsfilter to text element.");
+        self.update_caps(obj);
+
+        let video_pad = text_elem.static_pad("src").unwrap();
+        let video_ghost_pad = gst::GhostPad::with_target(Some("video_src"), &video_pad).unwrap();
+
+        obj.add_pad(&video_ghost_pad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::new(
+                    "text",
+                    "Text",
+                    "Text to display",
+                    Some(""),
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecString::new(
+                    "font-desc",
+                    "Font description",
+                    "Pango font description of the text, e.g. \"Sans Bold 24\"",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecUInt::new(
+                    "color",
+                    "Color",
+                    "Text color, as 0xAARRGGBB",
+                    0,
+                    u32::MAX,
+                    DEFAULT_COLOR,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecInt::new(
+                    "width",
+                    "Width",
+                    "Frame width",
+                    1,
+                    i32::MAX,
+                    DEFAULT_WIDTH,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecInt::new(
+                    "height",
+                    "Height",
+                    "Frame height",
+                    1,
+                    i32::MAX,
+                    DEFAULT_HEIGHT,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "text" | "font-desc" | "color" =>
+                obj.by_name("textoverlay").unwrap().set_property_from_value(pspec.name(), value),
+            "width" => {
+                self.settings.lock().unwrap().width = value.get().expect("type checked upstream");
+                self.update_caps(obj);
+            },
+            "height" => {
+                self.settings.lock().unwrap().height = value.get().expect("type checked upstream");
+                self.update_caps(obj);
+            },
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "text" | "font-desc" | "color" =>
+                obj.by_name("textoverlay").unwrap().property_value(pspec.name()),
+            "width" => self.settings.lock().unwrap().width.to_value(),
+            "height" => self.settings.lock().unwrap().height.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcTextSource {}
+
+impl ElementImpl for DkcTextSource {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Text Source",
+                "Video",
+                "DankCaster text source element",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // src pad capabilities
+            let video_caps = gst::Caps::builder("video/x-raw")
+                .build();
+
+            let video_src_pad_template = gst::PadTemplate::new(
+                "video_src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &video_caps,
+            )
+            .unwrap();
+
+            vec![video_src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for DkcTextSource {}
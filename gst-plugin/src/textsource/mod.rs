@@ -0,0 +1,69 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DkcTextSource(ObjectSubclass<imp::DkcTextSource>) @extends gst::Bin, gst::Element, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcTextSource {}
+unsafe impl Sync for DkcTextSource {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkctextsource",
+        gst::Rank::None,
+        DkcTextSource::static_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    /* Play the source into fake sinks, true once every one of them got a first buffer */
+    fn preroll(source: &gst::Element) -> bool {
+        let pipeline = gst::Pipeline::new(None);
+        pipeline.add(source).unwrap();
+
+        for pad in source.src_pads() {
+            let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+            pipeline.add(&sink).unwrap();
+            pad.link(&sink.static_pad("sink").unwrap()).unwrap();
+        }
+
+        let prerolled = pipeline.set_state(gst::State::Playing).is_ok()
+            && pipeline.state(gst::ClockTime::from_seconds(10)).0.is_ok();
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        prerolled
+    }
+
+    #[test]
+    fn test_preroll() {
+        set_up();
+
+        let source = gst::ElementFactory::make("dkctextsource", Some("text"))
+            .expect("Could not make dkctextsource element");
+        source.set_property("text", "Hello");
+
+        assert!(preroll(&source));
+    }
+
+}
@@ -0,0 +1,713 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::prelude::*;
+use gst_base::subclass::prelude::*;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::alphamask::{decode, Mask};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "DkcVideoMixerBlendMode")]
+pub enum BlendMode {
+    #[enum_value(name = "Normal: draw the input over what is below it", nick = "normal")]
+    Normal,
+    #[enum_value(name = "Additive: add the input colors, brightening", nick = "additive")]
+    Additive,
+    #[enum_value(name = "Multiply: multiply the colors, darkening", nick = "multiply")]
+    Multiply,
+    #[enum_value(name = "Screen: multiply the inverted colors, brightening", nick = "screen")]
+    Screen,
+    #[enum_value(name = "Subtract: subtract the input colors, darkening", nick = "subtract")]
+    Subtract,
+}
+
+impl Default for BlendMode {
+    fn default() -> Self {
+        BlendMode::Normal
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "DkcVideoMixerSizingPolicy")]
+pub enum SizingPolicy {
+    #[enum_value(name = "None: scale to the pad width and height", nick = "none")]
+    None,
+    #[enum_value(name = "Keep aspect ratio: fit, centered, within the pad width and height", nick = "keep-aspect-ratio")]
+    KeepAspectRatio,
+}
+
+impl Default for SizingPolicy {
+    fn default() -> Self {
+        SizingPolicy::None
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "DkcVideoMixerBackground")]
+pub enum Background {
+    #[enum_value(name = "Checker pattern", nick = "checker")]
+    Checker,
+    #[enum_value(name = "Black", nick = "black")]
+    Black,
+    #[enum_value(name = "White", nick = "white")]
+    White,
+    #[enum_value(name = "Transparent", nick = "transparent")]
+    Transparent,
+    #[enum_value(name = "Solid color, see background-color", nick = "color")]
+    Color,
+    #[enum_value(name = "Image stretched over the canvas, see background-location", nick = "image")]
+    Image,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Checker
+    }
+}
+
+const CHECKER_SIZE: usize = 8;
+const DEFAULT_BACKGROUND_COLOR: u32 = 0xff000000;
+const DEFAULT_FRAMERATE: i32 = 30;
+
+/* Blend function of a mode, on straight colors */
+pub(crate) fn blend_channel(mode: BlendMode, src: f32, dst: f32) -> f32 {
+    match mode {
+        BlendMode::Normal => src,
+        BlendMode::Additive => (src + dst).min(1.0),
+        BlendMode::Multiply => src * dst,
+        BlendMode::Screen => 1.0 - (1.0 - src) * (1.0 - dst),
+        BlendMode::Subtract => (dst - src).max(0.0),
+    }
+}
+
+/* Composite a straight alpha RGBA source pixel over a destination one, the blended color
+ * only showing where the destination is opaque (W3C compositing and blending model) */
+pub(super) fn blend(mode: BlendMode, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+    let (src_alpha, dst_alpha) = (src[3], dst[3]);
+    let alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+    if alpha <= 0.0 {
+        return [0.0; 4];
+    }
+
+    let mut out = [0.0, 0.0, 0.0, alpha];
+    for c in 0..3 {
+        let color = (1.0 - dst_alpha) * src[c] + dst_alpha * blend_channel(mode, src[c], dst[c]);
+        out[c] = (src_alpha * color + (1.0 - src_alpha) * dst_alpha * dst[c]) / alpha;
+    }
+
+    out
+}
+
+/* Normal blending of a straight alpha RGBA pixel over another, scaled by an opacity out of 255,
+ * in integers as most inputs are drawn this way */
+pub(super) fn over(src: &[u8], opacity: u32, dst: &mut [u8]) {
+    let src_alpha = (src[3] as u32 * opacity + 127) / 255;
+    if src_alpha == 0 {
+        return;
+    }
+    if src_alpha == 255 {
+        dst[..3].copy_from_slice(&src[..3]);
+        dst[3] = 255;
+        return;
+    }
+
+    let dst_weight = (dst[3] as u32 * (255 - src_alpha) + 127) / 255;
+    let alpha = src_alpha + dst_weight;
+    for c in 0..3 {
+        dst[c] = ((src[c] as u32 * src_alpha + dst[c] as u32 * dst_weight + alpha / 2) / alpha) as u8;
+    }
+    dst[3] = alpha as u8;
+}
+
+/* Area of the canvas an input is drawn to, given its natural size and the pad settings */
+pub(super) fn placement(settings: &PadSettings, natural_width: i32, natural_height: i32) -> (i32, i32, i32, i32) {
+    let width = if settings.width > 0 { settings.width } else { natural_width };
+    let height = if settings.height > 0 { settings.height } else { natural_height };
+
+    match settings.sizing_policy {
+        SizingPolicy::KeepAspectRatio if natural_width > 0 && natural_height > 0 => {
+            let scale = f64::min(width as f64 / natural_width as f64, height as f64 / natural_height as f64);
+            let (fit_width, fit_height) = ((natural_width as f64 * scale).round() as i32,
+                                           (natural_height as f64 * scale).round() as i32);
+            (settings.xpos + (width - fit_width) / 2, settings.ypos + (height - fit_height) / 2,
+             fit_width, fit_height)
+        },
+        _ => (settings.xpos, settings.ypos, width, height),
+    }
+}
+
+/* Images not loaded (yet) show the background color instead */
+pub(super) fn fill_background(background: Background, color: u32, image: Option<&Mask>,
+                              data: &mut [u8], width: usize, height: usize, stride: usize) {
+    let [alpha, red, green, blue] = color.to_be_bytes();
+
+    for (y, line) in data.chunks_exact_mut(stride).take(height).enumerate() {
+        for (x, pixel) in line[..width * 4].chunks_exact_mut(4).enumerate() {
+            let value = match (background, image) {
+                (Background::Checker, _) => if (x / CHECKER_SIZE + y / CHECKER_SIZE) % 2 == 0 { [0x66, 0x66, 0x66, 0xff] } else { [0x99, 0x99, 0x99, 0xff] },
+                (Background::Black, _) => [0x00, 0x00, 0x00, 0xff],
+                (Background::White, _) => [0xff, 0xff, 0xff, 0xff],
+                (Background::Transparent, _) => [0x00, 0x00, 0x00, 0x00],
+                (Background::Image, Some(image)) => {
+                    pixel.copy_from_slice(image.pixel(x, y, width, height));
+                    continue;
+                },
+                (Background::Color, _) | (Background::Image, None) => [red, green, blue, alpha],
+            };
+            pixel.copy_from_slice(&value);
+        }
+    }
+}
+
+/* Bilinear sample of an RGBA frame, at a position in source pixels */
+fn sample(data: &[u8], width: usize, height: usize, stride: usize, x: f32, y: f32) -> [f32; 4] {
+    let (x, y) = (x.clamp(0.0, (width - 1) as f32), y.clamp(0.0, (height - 1) as f32));
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let pixel = |px: usize, py: usize, c: usize| data[py * stride + px * 4 + c] as f32 / 255.0;
+
+    let mut out = [0.0; 4];
+    for (c, value) in out.iter_mut().enumerate() {
+        let top = pixel(x0, y0, c) * (1.0 - fx) + pixel(x1, y0, c) * fx;
+        let bottom = pixel(x0, y1, c) * (1.0 - fx) + pixel(x1, y1, c) * fx;
+        *value = top * (1.0 - fy) + bottom * fy;
+    }
+
+    out
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct PadSettings {
+    pub(super) xpos: i32,
+    pub(super) ypos: i32,
+    pub(super) width: i32,
+    pub(super) height: i32,
+    pub(super) alpha: f64,
+    pub(super) zorder: u32,
+    pub(super) sizing_policy: SizingPolicy,
+    pub(super) blend_mode: BlendMode,
+}
+
+impl Default for PadSettings {
+    fn default() -> Self {
+        Self {
+            xpos: 0,
+            ypos: 0,
+            width: 0,
+            height: 0,
+            alpha: 1.0,
+            zorder: 0,
+            sizing_policy: SizingPolicy::default(),
+            blend_mode: BlendMode::default(),
+        }
+    }
+}
+
+/* Last frame received on an input, drawn until a newer one is due */
+#[derive(Default)]
+struct PadState {
+    info: Option<gst_video::VideoInfo>,
+    current: Option<gst::Buffer>,
+}
+
+#[derive(Default)]
+pub struct DkcVideoMixerPad {
+    settings: Mutex<PadSettings>,
+    state: Mutex<PadState>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcVideoMixerPad {
+    const NAME: &'static str = "DkcVideoMixerPad";
+    type Type = super::DkcVideoMixerPad;
+    type ParentType = gst_base::AggregatorPad;
+}
+
+impl ObjectImpl for DkcVideoMixerPad {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            let flags = glib::ParamFlags::READWRITE | gst::PARAM_FLAG_CONTROLLABLE;
+            vec![
+                glib::ParamSpecInt::new("xpos", "X position", "Horizontal position of the input on the canvas",
+                                        i32::MIN, i32::MAX, 0, flags),
+                glib::ParamSpecInt::new("ypos", "Y position", "Vertical position of the input on the canvas",
+                                        i32::MIN, i32::MAX, 0, flags),
+                glib::ParamSpecInt::new("width", "Width", "Width of the input on the canvas (0: natural width)",
+                                        0, i32::MAX, 0, flags),
+                glib::ParamSpecInt::new("height", "Height", "Height of the input on the canvas (0: natural height)",
+                                        0, i32::MAX, 0, flags),
+                glib::ParamSpecDouble::new("alpha", "Alpha", "Opacity of the input",
+                                           0.0, 1.0, 1.0, flags),
+                glib::ParamSpecUInt::new("zorder", "Z-order", "Stacking order of the input, higher ones being drawn on top",
+                                         0, u32::MAX, 0, flags),
+                glib::ParamSpecEnum::new("sizing-policy", "Sizing policy", "How the input is scaled to its width and height",
+                                         SizingPolicy::static_type(), SizingPolicy::default() as i32, flags),
+                glib::ParamSpecEnum::new("blend-mode", "Blend mode", "How the input colors are combined with the ones below it",
+                                         BlendMode::static_type(), BlendMode::default() as i32, flags),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "xpos" => settings.xpos = value.get().expect("type checked upstream"),
+            "ypos" => settings.ypos = value.get().expect("type checked upstream"),
+            "width" => settings.width = value.get().expect("type checked upstream"),
+            "height" => settings.height = value.get().expect("type checked upstream"),
+            "alpha" => settings.alpha = value.get().expect("type checked upstream"),
+            "zorder" => settings.zorder = value.get().expect("type checked upstream"),
+            "sizing-policy" => settings.sizing_policy = value.get().expect("type checked upstream"),
+            "blend-mode" => settings.blend_mode = value.get().expect("type checked upstream"),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "xpos" => settings.xpos.to_value(),
+            "ypos" => settings.ypos.to_value(),
+            "width" => settings.width.to_value(),
+            "height" => settings.height.to_value(),
+            "alpha" => settings.alpha.to_value(),
+            "zorder" => settings.zorder.to_value(),
+            "sizing-policy" => settings.sizing_policy.to_value(),
+            "blend-mode" => settings.blend_mode.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcVideoMixerPad {}
+impl PadImpl for DkcVideoMixerPad {}
+impl AggregatorPadImpl for DkcVideoMixerPad {}
+
+impl DkcVideoMixerPad {
+    /* Draw the current frame of this input onto the canvas */
+    fn draw(&self, data: &mut [u8], width: usize, height: usize, stride: usize) {
+        let settings = *self.settings.lock().unwrap();
+        if settings.alpha <= 0.0 {
+            return;
+        }
+        let state = self.state.lock().unwrap();
+
+        let (info, buffer) = match (state.info.as_ref(), state.current.as_ref()) {
+            (Some(info), Some(buffer)) => (info, buffer),
+            _ => return,
+        };
+        let frame = match gst_video::VideoFrameRef::from_buffer_ref_readable(buffer.as_ref(), info) {
+            Ok(frame) => frame,
+            Err(_) => return,
+        };
+
+        let (src_width, src_height) = (frame.width() as usize, frame.height() as usize);
+        let src_stride = frame.plane_stride()[0] as usize;
+        let src_data = match frame.plane_data(0) {
+            Ok(src_data) => src_data,
+            Err(_) => return,
+        };
+
+        let (x, y, rect_width, rect_height) = placement(&settings, src_width as i32, src_height as i32);
+        if rect_width <= 0 || rect_height <= 0 || src_width == 0 || src_height == 0 {
+            return;
+        }
+
+        let (scale_x, scale_y) = (src_width as f32 / rect_width as f32, src_height as f32 / rect_height as f32);
+        let alpha = settings.alpha as f32;
+        let opacity = (settings.alpha * 255.0).round().clamp(0.0, 255.0) as u32;
+        /* Inputs drawn at their natural size are not resampled */
+        let scaled = rect_width as usize != src_width || rect_height as usize != src_height;
+
+        for dst_y in y.max(0)..(y + rect_height).min(height as i32) {
+            let src_y = (dst_y - y) as f32 * scale_y + (scale_y - 1.0) / 2.0;
+            let line = &mut data[dst_y as usize * stride..][..width * 4];
+
+            for dst_x in x.max(0)..(x + rect_width).min(width as i32) {
+                let mut src = if scaled {
+                    let src_x = (dst_x - x) as f32 * scale_x + (scale_x - 1.0) / 2.0;
+                    sample(src_data, src_width, src_height, src_stride, src_x, src_y)
+                } else {
+                    let src = &src_data[(dst_y - y) as usize * src_stride + (dst_x - x) as usize * 4..][..4];
+                    if settings.blend_mode == BlendMode::Normal {
+                        over(src, opacity, &mut line[dst_x as usize * 4..][..4]);
+                        continue;
+                    }
+                    [src[0] as f32 / 255.0, src[1] as f32 / 255.0, src[2] as f32 / 255.0, src[3] as f32 / 255.0]
+                };
+                src[3] *= alpha;
+                if src[3] <= 0.0 {
+                    continue;
+                }
+
+                let pixel = &mut line[dst_x as usize * 4..][..4];
+                let dst = [pixel[0] as f32 / 255.0, pixel[1] as f32 / 255.0,
+                           pixel[2] as f32 / 255.0, pixel[3] as f32 / 255.0];
+                for (channel, value) in pixel.iter_mut().zip(blend(settings.blend_mode, src, dst).iter()) {
+                    *channel = (value * 255.0).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    }
+}
+
+struct Settings {
+    background: Background,
+    background_color: u32,
+    background_location: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            background: Background::default(),
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            background_location: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    info: Option<gst_video::VideoInfo>,
+    /* Running time of the next output frame */
+    position: Option<gst::ClockTime>,
+}
+
+#[derive(Default)]
+pub struct DkcVideoMixer {
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+    background_image: Mutex<Option<Mask>>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "dkcvideomixer",
+        gst::DebugColorFlags::empty(),
+        Some("DankCaster video mixer"),
+    )
+});
+
+/* Output caps have a framerate, variable ones (0/1) being rejected; the default one is a
+ * fallback only */
+fn frame_duration(info: &gst_video::VideoInfo) -> gst::ClockTime {
+    let framerate = info.fps();
+    if framerate.numer() <= 0 || framerate.denom() <= 0 {
+        return gst::ClockTime::SECOND / DEFAULT_FRAMERATE as u64;
+    }
+
+    gst::ClockTime::from_nseconds(gst::ClockTime::SECOND.nseconds() * framerate.denom() as u64
+                                  / framerate.numer() as u64)
+}
+
+impl DkcVideoMixer {
+    fn load_background(&self, obj: &super::DkcVideoMixer, location: Option<&str>) {
+        let image = match location {
+            Some(location) => match decode(location) {
+                Ok(image) => Some(image),
+                Err(err) => {
+                    gst::warning!(CAT, obj: obj, "Could not load background image {}: {}", location, err);
+                    None
+                },
+            },
+            None => None,
+        };
+
+        *self.background_image.lock().unwrap() = image;
+    }
+
+    fn pads(&self, aggregator: &super::DkcVideoMixer) -> Vec<super::DkcVideoMixerPad> {
+        aggregator.sink_pads().into_iter()
+            .filter_map(|pad| pad.downcast::<super::DkcVideoMixerPad>().ok())
+            .collect()
+    }
+
+    /* Running time of the next buffer queued on an input */
+    fn queued_running_time(pad: &super::DkcVideoMixerPad) -> Option<gst::ClockTime> {
+        let buffer = pad.peek_buffer()?;
+        let segment = pad.segment();
+        segment.downcast_ref::<gst::ClockTime>()?.to_running_time(buffer.pts())
+    }
+
+    /* Stream time of an input at a running time, its controlled values being synced against it */
+    fn stream_time(pad: &super::DkcVideoMixerPad, running_time: gst::ClockTime) -> gst::ClockTime {
+        let segment = pad.segment();
+        segment.downcast_ref::<gst::ClockTime>()
+            .and_then(|segment| segment.to_stream_time(segment.position_from_running_time(running_time)))
+            .unwrap_or(running_time)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DkcVideoMixer {
+    const NAME: &'static str = "DkcVideoMixer";
+    type Type = super::DkcVideoMixer;
+    type ParentType = gst_base::Aggregator;
+}
+
+impl ObjectImpl for DkcVideoMixer {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecEnum::new(
+                    "background",
+                    "Background",
+                    "What is drawn where no input covers the canvas",
+                    Background::static_type(),
+                    Background::default() as i32,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecUInt::new(
+                    "background-color",
+                    "Background color",
+                    "Color of the color background, as 0xAARRGGBB",
+                    0,
+                    u32::MAX,
+                    DEFAULT_BACKGROUND_COLOR,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+                glib::ParamSpecString::new(
+                    "background-location",
+                    "Background location",
+                    "Path of the image background, stretched over the canvas",
+                    None,
+                    glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_PLAYING,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, obj: &Self::Type, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "background" => self.settings.lock().unwrap().background = value.get().expect("type checked upstream"),
+            "background-color" => self.settings.lock().unwrap().background_color = value.get().expect("type checked upstream"),
+            "background-location" => {
+                let location = value.get::<Option<String>>().expect("type checked upstream");
+                self.load_background(obj, location.as_deref());
+                self.settings.lock().unwrap().background_location = location;
+            },
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "background" => settings.background.to_value(),
+            "background-color" => settings.background_color.to_value(),
+            "background-location" => settings.background_location.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for DkcVideoMixer {}
+
+impl ElementImpl for DkcVideoMixer {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "DankCaster Video Mixer",
+                "Filter/Editor/Video/Compositor",
+                "Composites video inputs with per-input position, size, opacity and blend mode",
+                "Julien 'Blaxar' Bardagi <blaxar.waldarax@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::builder("video/x-raw")
+                .field("format", "RGBA")
+                .field("width", gst::IntRange::new(1, i32::MAX))
+                .field("height", gst::IntRange::new(1, i32::MAX))
+                .field("framerate", gst::FractionRange::new(gst::Fraction::new(0, 1), gst::Fraction::new(i32::MAX, 1)))
+                .build();
+            /* Frames are output at a fixed rate */
+            let src_caps = gst::Caps::builder("video/x-raw")
+                .field("format", "RGBA")
+                .field("width", gst::IntRange::new(1, i32::MAX))
+                .field("height", gst::IntRange::new(1, i32::MAX))
+                .field("framerate", gst::FractionRange::new(gst::Fraction::new(1, i32::MAX), gst::Fraction::new(i32::MAX, 1)))
+                .build();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &src_caps,
+            )
+            .unwrap();
+
+            let sink_pad_template = gst::PadTemplate::with_gtype(
+                "sink_%u",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Request,
+                &caps,
+                super::DkcVideoMixerPad::static_type(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl AggregatorImpl for DkcVideoMixer {
+    fn start(&self, _aggregator: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        *self.state.lock().unwrap() = State::default();
+        Ok(())
+    }
+
+    fn flush(&self, aggregator: &Self::Type) -> Result<gst::FlowSuccess, gst::FlowError> {
+        self.state.lock().unwrap().position = None;
+        for pad in self.pads(aggregator) {
+            DkcVideoMixerPad::from_instance(&pad).state.lock().unwrap().current = None;
+        }
+
+        self.parent_flush(aggregator)
+    }
+
+    fn sink_event(&self, aggregator: &Self::Type, aggregator_pad: &gst_base::AggregatorPad, event: gst::Event) -> bool {
+        if let gst::EventView::Caps(caps) = event.view() {
+            let info = gst_video::VideoInfo::from_caps(caps.caps()).ok();
+            if info.is_none() {
+                gst::warning!(CAT, obj: aggregator_pad, "Invalid input caps {:?}", caps.caps());
+            }
+
+            if let Some(pad) = aggregator_pad.downcast_ref::<super::DkcVideoMixerPad>() {
+                DkcVideoMixerPad::from_instance(pad).state.lock().unwrap().info = info;
+            }
+        }
+
+        self.parent_sink_event(aggregator, aggregator_pad, event)
+    }
+
+    fn fixate_src_caps(&self, aggregator: &Self::Type, caps: gst::Caps) -> gst::Caps {
+        let mut caps = caps;
+        if let Some(structure) = caps.make_mut().structure_mut(0) {
+            structure.fixate_field_nearest_int("width", 1280);
+            structure.fixate_field_nearest_int("height", 720);
+            structure.fixate_field_nearest_fraction("framerate", gst::Fraction::new(DEFAULT_FRAMERATE, 1));
+        }
+
+        self.parent_fixate_src_caps(aggregator, caps)
+    }
+
+    fn negotiated_src_caps(&self, aggregator: &Self::Type, caps: &gst::Caps) -> Result<(), gst::LoggableError> {
+        let info = gst_video::VideoInfo::from_caps(caps)
+            .map_err(|_| gst::loggable_error!(CAT, "Invalid output caps {:?}", caps))?;
+
+        /* Inputs are sampled once per output frame */
+        aggregator.set_latency(frame_duration(&info), None);
+        self.state.lock().unwrap().info = Some(info);
+
+        self.parent_negotiated_src_caps(aggregator, caps)
+    }
+
+    fn next_time(&self, aggregator: &Self::Type) -> Option<gst::ClockTime> {
+        self.state.lock().unwrap().position.or_else(|| aggregator.current_running_time())
+    }
+
+    fn aggregate(&self, aggregator: &Self::Type, timeout: bool) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let info = self.state.lock().unwrap().info.clone().ok_or(gst::FlowError::NotNegotiated)?;
+        let duration = frame_duration(&info);
+        let pads = self.pads(aggregator);
+
+        if !pads.is_empty() && pads.iter().all(|pad| pad.is_eos() && pad.peek_buffer().is_none()) {
+            return Err(gst::FlowError::Eos);
+        }
+
+        /* Output starts with the first input frame, or when the first deadline passes */
+        let position = {
+            let mut state = self.state.lock().unwrap();
+            let position = state.position
+                .or_else(|| pads.iter().filter_map(Self::queued_running_time).min())
+                .or_else(|| if timeout { aggregator.current_running_time() } else { None })
+                .unwrap_or(gst::ClockTime::ZERO);
+            state.position = Some(position);
+            position
+        };
+        let end = position + duration;
+
+        /* Each input holds its latest frame starting before the end of the output one */
+        let mut need_data = false;
+        for pad in pads.iter() {
+            let pad_imp = DkcVideoMixerPad::from_instance(pad);
+            loop {
+                match pad.peek_buffer() {
+                    Some(buffer) => {
+                        if Self::queued_running_time(pad).map_or(false, |running_time| running_time >= end) {
+                            break;
+                        }
+                        pad_imp.state.lock().unwrap().current = Some(buffer);
+                        pad.drop_buffer();
+                    },
+                    None => {
+                        if pad.is_eos() {
+                            pad_imp.state.lock().unwrap().current = None;
+                        } else if !timeout {
+                            need_data = true;
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+
+        if need_data {
+            return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
+        }
+
+        let mut pads = pads;
+        for pad in pads.iter() {
+            let _ = pad.sync_values(Self::stream_time(pad, position));
+        }
+        pads.sort_by_key(|pad| DkcVideoMixerPad::from_instance(pad).settings.lock().unwrap().zorder);
+
+        let mut buffer = gst::Buffer::with_size(info.size()).map_err(|_| gst::FlowError::Error)?;
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(position);
+            buffer.set_duration(duration);
+
+            let mut frame = gst_video::VideoFrameRef::from_buffer_ref_writable(buffer, &info)
+                .map_err(|_| gst::FlowError::Error)?;
+            let (width, height) = (frame.width() as usize, frame.height() as usize);
+            let stride = frame.plane_stride()[0] as usize;
+            let data = frame.plane_data_mut(0).map_err(|_| gst::FlowError::Error)?;
+
+            let (background, color) = {
+                let settings = self.settings.lock().unwrap();
+                (settings.background, settings.background_color)
+            };
+            fill_background(background, color, self.background_image.lock().unwrap().as_ref(),
+                            data, width, height, stride);
+            for pad in pads.iter() {
+                DkcVideoMixerPad::from_instance(pad).draw(data, width, height, stride);
+            }
+        }
+
+        self.state.lock().unwrap().position = Some(end);
+        gst::trace!(CAT, obj: aggregator, "Pushing frame at {}", position);
+
+        aggregator.finish_buffer(buffer)
+    }
+}
@@ -0,0 +1,149 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+pub(crate) use imp::{blend_channel, BlendMode};
+
+glib::wrapper! {
+    pub struct DkcVideoMixer(ObjectSubclass<imp::DkcVideoMixer>) @extends gst_base::Aggregator, gst::Element, gst::Object;
+}
+
+glib::wrapper! {
+    pub struct DkcVideoMixerPad(ObjectSubclass<imp::DkcVideoMixerPad>) @extends gst_base::AggregatorPad, gst::Pad, gst::Object;
+}
+
+// GStreamer elements need to be thread-safe. For the private implementation this is automatically
+// enforced but for the public wrapper type we need to specify this manually.
+unsafe impl Send for DkcVideoMixer {}
+unsafe impl Sync for DkcVideoMixer {}
+unsafe impl Send for DkcVideoMixerPad {}
+unsafe impl Sync for DkcVideoMixerPad {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "dkcvideomixer",
+        gst::Rank::None,
+        DkcVideoMixer::static_type(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::*;
+    use gst::prelude::*;
+    use videomixer::imp::{blend, fill_background, over, placement, Background, BlendMode, PadSettings, SizingPolicy};
+
+    fn set_up() {
+        use std::sync::{Once};
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+            plugin_desc::plugin_register_static().expect("Could not register Dkc plugin");
+        });
+    }
+
+    #[test]
+    fn test_blend() {
+        let dst = [0.5, 0.5, 0.5, 1.0];
+        let src = [0.5, 1.0, 0.0, 1.0];
+
+        assert_eq!(blend(BlendMode::Normal, src, dst), [0.5, 1.0, 0.0, 1.0]);
+        assert_eq!(blend(BlendMode::Additive, src, dst), [1.0, 1.0, 0.5, 1.0]);
+        assert_eq!(blend(BlendMode::Multiply, src, dst), [0.25, 0.5, 0.0, 1.0]);
+        assert_eq!(blend(BlendMode::Screen, src, dst), [0.75, 1.0, 0.5, 1.0]);
+        assert_eq!(blend(BlendMode::Subtract, src, dst), [0.0, 0.0, 0.5, 1.0]);
+
+        // Half transparent inputs are mixed with what is below them.
+        assert_eq!(blend(BlendMode::Multiply, [0.0, 0.0, 0.0, 0.5], dst), [0.25, 0.25, 0.25, 1.0]);
+
+        // Blending over a transparent canvas draws the input as is.
+        assert_eq!(blend(BlendMode::Subtract, src, [0.0; 4]), src);
+    }
+
+    #[test]
+    fn test_over() {
+        let to_f32 = |pixel: &[u8]| [pixel[0] as f32 / 255.0, pixel[1] as f32 / 255.0,
+                                     pixel[2] as f32 / 255.0, pixel[3] as f32 / 255.0];
+
+        // Integer normal blending matches the blend function, rounding apart.
+        for (src, dst) in [([200u8, 100, 0, 255], [10u8, 20, 30, 255]),
+                           ([200, 100, 0, 128], [10, 20, 30, 255]),
+                           ([200, 100, 0, 128], [10, 20, 30, 64]),
+                           ([200, 100, 0, 0], [10, 20, 30, 255])] {
+            let mut pixel = dst;
+            over(&src, 255, &mut pixel);
+
+            let expected = blend(BlendMode::Normal, to_f32(&src), to_f32(&dst));
+            for (value, expected) in pixel.iter().zip(expected.iter()) {
+                assert!((*value as f32 - expected * 255.0).abs() <= 1.0);
+            }
+        }
+
+        // Opacity scales the input alpha.
+        let mut pixel = [0, 0, 0, 255];
+        over(&[255, 255, 255, 255], 0, &mut pixel);
+        assert_eq!(pixel, [0, 0, 0, 255]);
+        over(&[255, 255, 255, 255], 51, &mut pixel);
+        assert_eq!(pixel, [51, 51, 51, 255]);
+    }
+
+    #[test]
+    fn test_placement() {
+        let mut settings = PadSettings { xpos: 10, ypos: 20, ..Default::default() };
+
+        // Natural size, then stretched, then fitted and centered.
+        assert_eq!(placement(&settings, 640, 360), (10, 20, 640, 360));
+        settings.width = 400;
+        settings.height = 400;
+        assert_eq!(placement(&settings, 640, 360), (10, 20, 400, 400));
+        settings.sizing_policy = SizingPolicy::KeepAspectRatio;
+        assert_eq!(placement(&settings, 640, 360), (10, 107, 400, 225));
+    }
+
+    #[test]
+    fn test_fill_background() {
+        let mut data = vec![0u8; 2 * 2 * 4];
+
+        fill_background(Background::Color, 0x80ff0000, None, &mut data, 2, 2, 8);
+        assert_eq!(&data[..4], &[0xff, 0x00, 0x00, 0x80]);
+
+        // Without a loaded image, the color shows instead.
+        fill_background(Background::Image, 0xff0000ff, None, &mut data, 2, 2, 8);
+        assert_eq!(&data[12..], &[0x00, 0x00, 0xff, 0xff]);
+
+        let image = alphamask::Mask::new(1, 1, vec![1, 2, 3, 4]).unwrap();
+        fill_background(Background::Image, 0, Some(&image), &mut data, 2, 2, 8);
+        assert_eq!(data, [1, 2, 3, 4].repeat(4));
+    }
+
+    #[test]
+    fn test_properties() {
+        set_up();
+
+        let mixer = gst::ElementFactory::make("dkcvideomixer", Some("mixer"))
+            .expect("Could not make dkcvideomixer element");
+        let pad = mixer.request_pad_simple("sink_%u").expect("Could not get request pad");
+
+        assert_eq!(pad.name(), "sink_0");
+
+        pad.set_property("xpos", 10i32);
+        pad.set_property("alpha", 0.5f64);
+        pad.set_property_from_str("sizing-policy", "keep-aspect-ratio");
+        pad.set_property_from_str("blend-mode", "screen");
+
+        assert_eq!(pad.property::<i32>("xpos"), 10);
+        assert_eq!(pad.property::<f64>("alpha"), 0.5);
+        assert_eq!(pad.property_value("blend-mode").serialize().unwrap(), "screen");
+
+        mixer.set_property_from_str("background", "black");
+        assert_eq!(mixer.property_value("background").serialize().unwrap(), "black");
+        mixer.set_property_from_str("background", "color");
+        mixer.set_property("background-color", 0xff00ff00u32);
+        assert_eq!(mixer.property::<u32>("background-color"), 0xff00ff00);
+    }
+
+}